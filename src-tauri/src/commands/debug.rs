@@ -1,4 +1,7 @@
-use crate::program_manager::unit::SearchTestResult;
+use crate::program_manager::program_loader::RootScanStat;
+use crate::program_manager::program_ranker::RankerGcReport;
+use crate::program_manager::program_source::ProgramSourceStat;
+use crate::program_manager::unit::{SearchBenchmarkItem, SearchTestReport};
 use crate::state::app_state::AppState;
 use std::sync::Arc;
 /// 这个页面存放用于测试的代码
@@ -10,7 +13,7 @@ pub async fn test_search_algorithm<R: Runtime>(
     _window: tauri::Window<R>,
     state: tauri::State<'_, Arc<AppState>>,
     search_text: String,
-) -> Result<Vec<SearchTestResult>, String> {
+) -> Result<SearchTestReport, String> {
     let program_manager = state.get_program_manager();
     Ok(program_manager.test_search_algorithm(&search_text).await)
 }
@@ -20,9 +23,12 @@ pub async fn test_search_algorithm_time<R: Runtime>(
     _app: tauri::AppHandle<R>,
     _window: tauri::Window<R>,
     state: tauri::State<'_, Arc<AppState>>,
-) -> Result<(f64, f64, f64), String> {
+    benchmark: Option<bool>,
+) -> Result<(f64, f64, f64, Vec<SearchBenchmarkItem>), String> {
     let program_manager = state.get_program_manager();
-    Ok(program_manager.test_search_algorithm_time().await)
+    Ok(program_manager
+        .test_search_algorithm_time(benchmark.unwrap_or(false))
+        .await)
 }
 
 #[tauri::command]
@@ -47,3 +53,36 @@ pub async fn get_search_keys<R: Runtime>(
     let search_keywords = program_manager.get_search_keywords(&show_name).await;
     Ok(search_keywords)
 }
+
+/// 获取上一次清理排序统计数据的结果
+#[tauri::command]
+pub async fn command_get_ranker_gc_report<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<RankerGcReport, String> {
+    let program_manager = state.get_program_manager();
+    Ok(program_manager.get_ranker_gc_report())
+}
+
+/// 获取每个程序来源在上一次加载时的数量与耗时
+#[tauri::command]
+pub async fn command_get_program_source_stats<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<ProgramSourceStat>, String> {
+    let program_manager = state.get_program_manager();
+    Ok(program_manager.get_program_source_stats())
+}
+
+/// 获取每个索引根目录在上一次扫描时的文件数量、命中数量与耗时
+#[tauri::command]
+pub async fn command_get_root_scan_stats<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<RootScanStat>, String> {
+    let program_manager = state.get_program_manager();
+    Ok(program_manager.get_root_scan_stats())
+}
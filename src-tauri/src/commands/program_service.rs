@@ -2,8 +2,17 @@ use crate::commands::ui_command::hide_window;
 use crate::modules::config::config_manager::PartialRuntimeConfig;
 use crate::modules::config::default::ICON_CACHE_DIR;
 use crate::modules::config::default::MODELS_DIR;
+use crate::modules::program_manager::config::program_loader_config::PartialProgramLoaderConfig;
+use crate::modules::program_manager::config::program_manager_config::PartialProgramManagerConfig;
+use crate::modules::program_manager::config::program_ranker_config::{
+    PartialProgramRankerConfig, QueryPin,
+};
+use crate::modules::program_manager::program_ranker::pin_matches;
+use crate::modules::program_manager::usage_importer::{UsageImportReport, UsageImportSource};
+use crate::modules::program_manager::weight_fitter::WeightFittingReport;
 use crate::modules::program_manager::FallbackReason;
-use crate::modules::program_manager::{LaunchMethod, LaunchMethodKind};
+use crate::modules::program_manager::HiddenProgramInfo;
+use crate::modules::program_manager::{LaunchMethod, LaunchMethodKind, MatchHighlight};
 use crate::save_config_to_file;
 use crate::state::app_state::AppState;
 use crate::update_app_setting;
@@ -27,7 +36,7 @@ pub struct ProgramInfo {
 /// 更新搜索窗口
 
 #[derive(Serialize, Debug)]
-pub struct SearchResult(u64, String);
+pub struct SearchResult(u64, String, MatchHighlight);
 
 #[derive(Serialize, Debug)]
 pub struct LaunchTemplateInfo {
@@ -248,7 +257,7 @@ pub async fn handle_search_text<R: Runtime>(
 
     let mut ret = Vec::new();
     for item in results {
-        ret.push(SearchResult(item.0, item.1));
+        ret.push(SearchResult(item.0, item.1, item.2));
     }
 
     if search_text.trim().is_empty() {
@@ -274,7 +283,7 @@ pub async fn command_get_latest_launch_program(
         .await;
     let mut ret = Vec::new();
     for item in results {
-        ret.push(SearchResult(item.0, item.1));
+        ret.push(SearchResult(item.0, item.1, MatchHighlight::default()));
     }
     debug!("latest_launch_propgram: {:?}", ret);
     Ok(ret)
@@ -296,6 +305,246 @@ pub async fn command_get_search_status_tip(
     Ok(code.to_string())
 }
 
+/// 根据查询-启动记录拟合智能排序的权重，找到更好的权重时写回配置
+#[tauri::command]
+pub async fn command_fit_ranker_weights<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<WeightFittingReport, String> {
+    let program_manager = state.get_program_manager();
+    let report = program_manager.fit_ranker_weights().await;
+    if report.applied {
+        info!(
+            "💾 排序权重已更新: MRR {:.3} -> {:.3}",
+            report.mrr_before, report.mrr_after
+        );
+        save_ranker_config(&state, report.after.to_partial_config()).await;
+    }
+    Ok(report)
+}
+
+/// 从其他启动器导出的文件中导入使用记录
+#[tauri::command]
+pub async fn command_import_launcher_usage<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+    source: UsageImportSource,
+    path: String,
+) -> Result<UsageImportReport, String> {
+    let program_manager = state.get_program_manager();
+    let report = program_manager
+        .import_launcher_usage(source, Path::new(&path))
+        .await
+        .map_err(|e| e.to_string())?;
+    if report.matched_count > 0 {
+        save_config_to_file(false).await;
+    }
+    Ok(report)
+}
+
+/// 把程序管理器的部分配置写回运行时配置并保存
+///
+/// refresh: 为 true 时保存之后重新应用配置（会重新扫描程序）
+async fn save_program_manager_config(
+    state: &AppState,
+    partial: PartialProgramManagerConfig,
+    refresh: bool,
+) {
+    let runtime_config = state.get_runtime_config();
+    runtime_config.update(PartialRuntimeConfig {
+        app_config: None,
+        ui_config: None,
+        shortcut_config: None,
+        program_manager_config: Some(partial),
+        window_state: None,
+    });
+    save_config_to_file(refresh).await;
+}
+
+/// 把排序器的部分配置写回运行时配置并保存
+async fn save_ranker_config(state: &AppState, ranker: PartialProgramRankerConfig) {
+    save_program_manager_config(
+        state,
+        PartialProgramManagerConfig {
+            ranker: Some(ranker),
+            ..Default::default()
+        },
+        true,
+    )
+    .await;
+}
+
+/// 更新固定项并保存，固定项直接交给程序管理器，不需要重新扫描程序
+async fn save_query_pins(state: &AppState, query_pins: Vec<QueryPin>) {
+    state
+        .get_program_manager()
+        .set_query_pins(query_pins.clone())
+        .await;
+    save_program_manager_config(
+        state,
+        PartialProgramManagerConfig {
+            ranker: Some(PartialProgramRankerConfig {
+                query_pins: Some(query_pins),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+}
+
+/// 把程序管理器中被隐藏的程序写回运行时配置并保存，程序管理器已经更新过注册表，不需要重新扫描
+async fn save_hidden_programs(state: &AppState) {
+    let hidden_programs = state
+        .get_program_manager()
+        .get_hidden_programs()
+        .into_iter()
+        .map(|item| (item.launch_method, item.show_name))
+        .collect();
+    save_program_manager_config(
+        state,
+        PartialProgramManagerConfig {
+            loader: Some(PartialProgramLoaderConfig {
+                hidden_programs: Some(hidden_programs),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+}
+
+/// 从搜索结果中隐藏一个程序，重新加载程序之后也不会再出现
+#[tauri::command]
+pub async fn command_hide_program<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+    program_guid: u64,
+) -> Result<(), String> {
+    let hidden = state
+        .get_program_manager()
+        .hide_program(program_guid)
+        .await
+        .ok_or_else(|| format!("Program GUID {} not found", program_guid))?;
+    info!(
+        "🙈 隐藏程序: {} ({})",
+        hidden.show_name, hidden.launch_method
+    );
+    save_hidden_programs(&state).await;
+    Ok(())
+}
+
+/// 获取所有被隐藏的程序
+#[tauri::command]
+pub async fn command_get_hidden_programs(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<HiddenProgramInfo>, String> {
+    Ok(state.get_program_manager().get_hidden_programs())
+}
+
+/// 取消隐藏一个程序，返回它之前是否被隐藏
+#[tauri::command]
+pub async fn command_unhide_program<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+    launch_method: String,
+) -> Result<bool, String> {
+    if !state
+        .get_program_manager()
+        .unhide_program(&launch_method)
+        .await
+    {
+        return Ok(false);
+    }
+    info!("🙉 取消隐藏程序: {}", launch_method);
+    save_hidden_programs(&state).await;
+    Ok(true)
+}
+
+/// 把程序固定在上一次查询的结果最前面
+///
+/// is_prefix: 为 true 时，输入以这个查询开头时也固定
+#[tauri::command]
+pub async fn command_pin_program_for_query<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+    program_guid: u64,
+    is_prefix: bool,
+) -> Result<(), String> {
+    let query = state.get_last_search_query();
+    let program_manager = state.get_program_manager();
+    let pin = program_manager
+        .build_query_pin(program_guid, &query, is_prefix)
+        .await
+        .ok_or_else(|| format!("Cannot pin program {} for query '{}'", program_guid, query))?;
+
+    let mut query_pins = state
+        .get_runtime_config()
+        .get_program_manager_config()
+        .get_ranker_config()
+        .get_query_pins();
+    match query_pins
+        .iter_mut()
+        .find(|item| item.query == pin.query && item.launch_method == pin.launch_method)
+    {
+        Some(existing) => existing.is_prefix = pin.is_prefix,
+        None => query_pins.push(pin),
+    }
+    info!("📌 固定程序: GUID={}, 查询='{}'", program_guid, query);
+    save_query_pins(&state, query_pins).await;
+    Ok(())
+}
+
+/// 取消程序在上一次查询上的所有固定项，返回是否有固定项被删除
+#[tauri::command]
+pub async fn command_unpin_program_for_query<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    _window: tauri::Window<R>,
+    state: tauri::State<'_, Arc<AppState>>,
+    program_guid: u64,
+) -> Result<bool, String> {
+    let query = state.get_last_search_query();
+    let program_manager = state.get_program_manager();
+    let Some(target) = program_manager
+        .build_query_pin(program_guid, &query, false)
+        .await
+    else {
+        return Ok(false);
+    };
+
+    let mut query_pins = state
+        .get_runtime_config()
+        .get_program_manager_config()
+        .get_ranker_config()
+        .get_query_pins();
+    let count = query_pins.len();
+    query_pins.retain(|item| {
+        item.launch_method != target.launch_method || !pin_matches(item, &target.query)
+    });
+    if query_pins.len() == count {
+        return Ok(false);
+    }
+    info!("📌 取消固定程序: GUID={}, 查询='{}'", program_guid, query);
+    save_query_pins(&state, query_pins).await;
+    Ok(true)
+}
+
+/// 获取固定在上一次查询最前面的程序
+#[tauri::command]
+pub async fn command_get_query_pinned_programs(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<u64>, String> {
+    let query = state.get_last_search_query();
+    Ok(state.get_program_manager().get_pinned_programs(&query))
+}
+
 #[tauri::command]
 pub async fn command_load_remote_config<R: Runtime>(
     _app: tauri::AppHandle<R>,
@@ -1,6 +1,7 @@
 use crate::error::{AppError, AppResult, OptionExt, ResultExt};
 use crate::utils::defer::defer;
 use crate::utils::windows::get_u16_vec;
+use bincode::{Decode, Encode};
 use core::mem::MaybeUninit;
 use fnv::FnvHasher;
 use image::GenericImageView;
@@ -42,7 +43,7 @@ use windows_core::BOOL;
 use windows_core::PCWSTR;
 
 /// 图片的身份标识，根据不同的身份，使用不同的函数来获取
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
 pub enum ImageIdentity {
     /// 普通的文件类型 => 获取路径的图片，如果是图片则返回png格式，如果是普通文件，则获取图标的png格式
     File(String),
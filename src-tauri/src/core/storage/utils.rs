@@ -87,53 +87,45 @@ pub fn read_or_create_bytes(path: &str, content: Option<Vec<u8>>) -> Result<Vec<
 /// 将lnk解析为绝对路径
 /// 优先使用本地的编码，如果失败，则使用utf16编码
 pub fn get_lnk_target_path(lnk_path: &str) -> Option<String> {
-    let shell_link_result = lnk::ShellLink::open(lnk_path, encoding_rs::GB18030);
+    match resolve_lnk_target_path(lnk_path) {
+        Ok(path) => Some(path),
+        Err(message) => {
+            warn!("{}", message);
+            None
+        }
+    }
+}
 
-    let shell_link = match shell_link_result {
+/// 与 `get_lnk_target_path` 相同，但不记录日志，解析失败时返回原因
+///
+/// 用于扫描大量快捷方式的场合，损坏或者指向非文件目标的快捷方式很常见，不需要每次都记录
+pub fn resolve_lnk_target_path(lnk_path: &str) -> Result<String, String> {
+    let shell_link = match lnk::ShellLink::open(lnk_path, encoding_rs::GB18030) {
         Ok(link) => link,
-        Err(e_gb18030) => {
-            warn!(
-                "Failed to open LNK file '{}' with GB18030 encoding: {:?}",
-                lnk_path, e_gb18030
-            );
-            // 2. 二次尝试：如果首次尝试失败，则使用 UTF-16LE 编码
-            match lnk::ShellLink::open(lnk_path, encoding_rs::UTF_16LE) {
-                Ok(link) => {
-                    warn!(
-                        "在主要编码尝试失败后，成功使用 UTF-16LE 编码打开 LNK 文件: {}",
-                        lnk_path
-                    );
-                    link
-                }
-                Err(e_utf16) => {
-                    warn!(
-                        "尝试使用 UTF-16LE 编码打开 LNK 文件 '{}' 再次失败: {:?}",
-                        lnk_path, e_utf16
-                    );
-                    return None;
-                }
+        // 二次尝试：如果首次尝试失败，则使用 UTF-16LE 编码
+        Err(e_gb18030) => match lnk::ShellLink::open(lnk_path, encoding_rs::UTF_16LE) {
+            Ok(link) => link,
+            Err(e_utf16) => {
+                return Err(format!(
+                    "无法打开 LNK 文件 '{}': GB18030 {:?}, UTF-16LE {:?}",
+                    lnk_path, e_gb18030, e_utf16
+                ));
             }
-        }
+        },
     };
 
     // 从成功打开的 shell_link 中提取路径信息
     let link_info = match shell_link.link_info() {
         Some(info) => info,
-        None => {
-            warn!("无法从 LNK 文件 '{}' 获取 link_info。", lnk_path);
-            return None;
-        }
+        None => return Err(format!("无法从 LNK 文件 '{}' 获取 link_info。", lnk_path)),
     };
 
     match link_info.local_base_path() {
-        Some(path) => Some(path.to_string()),
-        None => {
-            warn!(
-                "无法从 LNK 文件 '{}' 获取基本路径 (local_base_path)。",
-                lnk_path
-            );
-            None
-        }
+        Some(path) => Ok(path.to_string()),
+        None => Err(format!(
+            "无法从 LNK 文件 '{}' 获取基本路径 (local_base_path)。",
+            lnk_path
+        )),
     }
 }
 
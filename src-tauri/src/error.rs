@@ -64,6 +64,10 @@ pub enum AppError {
     #[error("存储错误: {message}")]
     StorageError { message: String },
 
+    /// 导入其他程序的数据时遇到的错误，通常是文件格式不对
+    #[error("导入错误: {message}")]
+    ImportError { message: String },
+
     /// 自动启动错误
     #[error("自动启动错误: {0}")]
     AutostartError(#[from] AutostartError),
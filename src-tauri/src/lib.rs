@@ -39,7 +39,7 @@ use device_query::DeviceState;
 use modules::config::app_config::PartialAppConfig;
 use modules::config::config_manager::RuntimeConfig;
 use modules::config::default::{
-    APP_PIC_PATH, REMOTE_CONFIG_NAME, SEMANTIC_EMBEDDING_CACHE_FILE_NAME,
+    APP_PIC_PATH, PROGRAM_SNAPSHOT_PATH, REMOTE_CONFIG_NAME, SEMANTIC_EMBEDDING_CACHE_FILE_NAME,
 };
 use modules::config::load_string_to_runtime_config_;
 use modules::config::save_runtime_config_to_string;
@@ -104,6 +104,9 @@ pub fn run() {
                 info!("正在初始化应用状态和配置系统");
                 init_app_state(app).await;
 
+                info!("正在从快照恢复程序注册表");
+                let is_snapshot_restored = restore_program_snapshot().await;
+
                 // 阶段3: UI组件初始化（依赖核心状态）
                 info!("=== 阶段3: UI组件初始化 ===");
 
@@ -126,7 +129,8 @@ pub fn run() {
                 info!("=== 阶段5: 配置应用和外部服务 ===");
 
                 info!("正在更新应用设置");
-                update_app_setting().await;
+                // 已经从快照恢复了注册表时，在后台重新扫描程序，不阻塞启动
+                apply_app_setting(is_snapshot_restored).await;
 
                 info!("正在注册深度链接");
                 app.deep_link()
@@ -192,6 +196,17 @@ pub fn run() {
             command_read_file,
             command_open_models_dir,
             command_get_search_status_tip,
+            command_fit_ranker_weights,
+            command_get_ranker_gc_report,
+            command_pin_program_for_query,
+            command_unpin_program_for_query,
+            command_get_query_pinned_programs,
+            command_hide_program,
+            command_get_hidden_programs,
+            command_unhide_program,
+            command_import_launcher_usage,
+            command_get_program_source_stats,
+            command_get_root_scan_stats,
         ])
         .run(tauri::generate_context!())
         .expect_programming("error while running tauri application");
@@ -463,8 +478,69 @@ fn init_setting_window(app: tauri::AppHandle) {
     });
 }
 
+/// 从本地的快照中恢复程序注册表，返回是否恢复成功
+async fn restore_program_snapshot() -> bool {
+    let Ok(bytes) = std::fs::read(&*PROGRAM_SNAPSHOT_PATH) else {
+        debug!("没有找到程序快照");
+        return false;
+    };
+    let state = ServiceLocator::get_state();
+    let restored_count = state
+        .get_program_manager()
+        .load_from_snapshot(
+            state.get_runtime_config().get_program_manager_config(),
+            &bytes,
+        )
+        .await;
+    restored_count > 0
+}
+
+/// 重新扫描程序并增量更新注册表，完成后保存程序快照
+async fn refresh_program_registry() {
+    let state = ServiceLocator::get_state();
+    let handle = state.get_main_handle();
+    let runtime_config = state.get_runtime_config();
+    let program_manager = state.get_program_manager();
+    let storage_manager = state.get_storage_manager();
+    // 获取当前最新的描述信息的内容
+    let semantic_store_str = match storage_manager
+        .download_file_str(SEMANTIC_DESCRIPTION_FILE_NAME.to_string())
+        .await
+    {
+        Some(data) => data,
+        None => {
+            // 如果没有获取到，则使用空的json对象，同时上传这个
+            let ret = "{}".to_string();
+            storage_manager
+                .upload_file_str(SEMANTIC_DESCRIPTION_FILE_NAME.to_string(), ret.clone())
+                .await;
+            ret
+        }
+    };
+
+    let registry_diff = program_manager
+        .load_from_config(
+            runtime_config.get_program_manager_config(),
+            Some(semantic_store_str),
+        )
+        .await;
+    if let Err(e) = handle.emit("program_registry_changed", &registry_diff) {
+        tracing::debug!("emit program_registry_changed failed: {:?}", e);
+    }
+
+    let snapshot = program_manager.export_program_snapshot().await;
+    if let Err(e) = std::fs::write(&*PROGRAM_SNAPSHOT_PATH, snapshot) {
+        warn!("保存程序快照失败: {}", e);
+    }
+}
+
 /// 更新程序的状态
 async fn update_app_setting() {
+    apply_app_setting(false).await;
+}
+
+/// 应用当前的配置，`background_rescan` 为真时在后台重新扫描程序，不等待扫描完成
+async fn apply_app_setting(background_rescan: bool) {
     let state = ServiceLocator::get_state();
     // 如果当前可见，则忽略更新
     if state.get_search_bar_visible() {
@@ -492,31 +568,15 @@ async fn update_app_setting() {
         info!("日志级别已根据配置动态更新为: {:?}", tracing_level);
     }
 
-    // 2. 重新更新程序索引的路径
-    let program_manager = state.get_program_manager();
-    let storage_manager = state.get_storage_manager();
-    // 获取当前最新的描述信息的内容
-    let semantic_store_str = match storage_manager
-        .download_file_str(SEMANTIC_DESCRIPTION_FILE_NAME.to_string())
-        .await
-    {
-        Some(data) => data,
-        None => {
-            // 如果没有获取到，则使用空的json对象，同时上传这个
-            let ret = "{}".to_string();
-            storage_manager
-                .upload_file_str(SEMANTIC_DESCRIPTION_FILE_NAME.to_string(), ret.clone())
-                .await;
-            ret
-        }
-    };
-
-    program_manager
-        .load_from_config(
-            runtime_config.get_program_manager_config(),
-            Some(semantic_store_str),
-        )
-        .await;
+    // 2. 重新更新程序索引的路径，后台扫描完成后再发送刷新结束事件
+    if background_rescan {
+        tauri::async_runtime::spawn(async {
+            refresh_program_registry().await;
+            emit_refresh_program_end();
+        });
+    } else {
+        refresh_program_registry().await;
+    }
 
     // 3. 判断要不要开机自启动
     if let Err(e) = handle_auto_start() {
@@ -543,8 +603,8 @@ async fn update_app_setting() {
     update_tray_menu_language();
 
     // 发送刷新结束事件
-    if let Err(e) = handle.emit("refresh_program_end", "") {
-        tracing::debug!("emit refresh_program_end failed: {:?}", e);
+    if !background_rescan {
+        emit_refresh_program_end();
     }
 
     // 发送窗口更新事件
@@ -570,6 +630,14 @@ async fn update_app_setting() {
     state.set_timer_guard(guard_value);
 }
 
+/// 通知前端程序已经刷新完成
+fn emit_refresh_program_end() {
+    let handle = ServiceLocator::get_state().get_main_handle();
+    if let Err(e) = handle.emit("refresh_program_end", "") {
+        tracing::debug!("emit refresh_program_end failed: {:?}", e);
+    }
+}
+
 /// 保存程序的配置信息
 /// 1. 将需要保存的东西保到配置信息中
 /// 2. 保存动态数据
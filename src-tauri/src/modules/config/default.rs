@@ -23,6 +23,14 @@ lazy_static! {
             .expect_programming("Failed to convert path to string")
             .to_string()
     };
+    /// 程序注册表快照的路径，快照中都是本机的路径，所以保存在本地而不是远程存储中
+    pub static ref PROGRAM_SNAPSHOT_PATH: String = {
+        Path::new(&*DATA_DIR_PATH)
+            .join("ZeroLaunch_program_snapshot.bin")
+            .to_str()
+            .expect_programming("Failed to convert path to string")
+            .to_string()
+    };
     /// 图标缓存文件夹的路径
     pub static ref ICON_CACHE_DIR: String = {
         Path::new(&*DATA_DIR_PATH)
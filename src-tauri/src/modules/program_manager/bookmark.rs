@@ -0,0 +1,363 @@
+/// 这个文件用于读取浏览器的书签，让书签可以像网页一样被搜索到
+///
+/// 支持的数据：
+/// - Chromium 系（Chrome、Edge 等）：用户目录下的 `Bookmarks`，是一个 JSON 文件
+/// - Firefox：用户目录下的 `places.sqlite`，书签在 `moz_bookmarks` 表中，网址在 `moz_places` 表中
+///
+/// 配置的路径既可以是上面的文件，也可以是包含它们的用户目录
+use crate::error::{AppError, AppResult};
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Chromium 的书签文件名
+const CHROMIUM_BOOKMARKS_FILE: &str = "Bookmarks";
+
+/// Firefox 的书签数据库文件名
+const FIREFOX_PLACES_FILE: &str = "places.sqlite";
+
+/// Firefox 的根文件夹（书签菜单、书签工具栏等），它们的名字不作为搜索关键字
+const FIREFOX_ROOT_GUIDS: [&str; 6] = [
+    "root________",
+    "menu________",
+    "toolbar_____",
+    "unfiled_____",
+    "mobile______",
+    "tags________",
+];
+
+/// Firefox 中书签条目的类型，文件夹为 2
+const FIREFOX_TYPE_BOOKMARK: i64 = 1;
+
+/// 一个浏览器书签
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub title: String,
+    pub url: String,
+    /// 从外到内的文件夹名字（不包含浏览器的根文件夹），Firefox 的标签也算在这里
+    pub folders: Vec<String>,
+}
+
+fn bookmark_error(message: impl Into<String>) -> AppError {
+    AppError::ImportError {
+        message: message.into(),
+    }
+}
+
+/// 只索引可以用浏览器打开的网址，`javascript:`、`place:` 之类的都跳过
+fn is_indexable_url(url: &str) -> bool {
+    let lower = url.to_lowercase();
+    ["http://", "https://", "ftp://", "file://"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+/// 读取一个路径中的书签，根据文件名判断是哪种浏览器
+pub fn read_bookmarks(path: &Path) -> AppResult<Vec<Bookmark>> {
+    let file = if path.is_dir() {
+        [CHROMIUM_BOOKMARKS_FILE, FIREFOX_PLACES_FILE]
+            .iter()
+            .map(|name| path.join(name))
+            .find(|file| file.is_file())
+            .ok_or_else(|| bookmark_error(format!("在 {} 中没有找到书签文件", path.display())))?
+    } else {
+        path.to_path_buf()
+    };
+
+    let is_sqlite = file
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("sqlite"));
+    if is_sqlite {
+        read_firefox_places(&file)
+    } else {
+        parse_chromium_bookmarks(&fs::read_to_string(&file)?)
+    }
+}
+
+/// Chromium 书签文件中的一个节点，`type` 为 `url` 或 `folder`
+#[derive(Debug, Deserialize)]
+struct ChromiumNode {
+    #[serde(default, rename = "type")]
+    node_type: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    children: Vec<ChromiumNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChromiumBookmarks {
+    /// `bookmark_bar`、`other`、`synced` 三个根文件夹
+    roots: HashMap<String, serde_json::Value>,
+}
+
+fn collect_chromium_node(
+    node: &ChromiumNode,
+    folders: &mut Vec<String>,
+    result: &mut Vec<Bookmark>,
+) {
+    if node.node_type == "url" {
+        if is_indexable_url(&node.url) {
+            result.push(Bookmark {
+                title: node.name.trim().to_string(),
+                url: node.url.trim().to_string(),
+                folders: folders.clone(),
+            });
+        }
+        return;
+    }
+    let name = node.name.trim();
+    if !name.is_empty() {
+        folders.push(name.to_string());
+    }
+    for child in &node.children {
+        collect_chromium_node(child, folders, result);
+    }
+    if !name.is_empty() {
+        folders.pop();
+    }
+}
+
+/// 解析 Chromium 的 `Bookmarks` 文件
+pub fn parse_chromium_bookmarks(text: &str) -> AppResult<Vec<Bookmark>> {
+    let bookmarks: ChromiumBookmarks = serde_json::from_str(text)?;
+    let mut root_names: Vec<&String> = bookmarks.roots.keys().collect();
+    root_names.sort();
+
+    let mut result = Vec::new();
+    for root_name in root_names {
+        // roots 中还有 `sync_transaction_version` 之类不是文件夹的字段
+        let Ok(root) = ChromiumNode::deserialize(&bookmarks.roots[root_name]) else {
+            continue;
+        };
+        // 根文件夹的名字（如 "书签栏"）不作为关键字
+        let mut folders = Vec::new();
+        for child in &root.children {
+            collect_chromium_node(child, &mut folders, &mut result);
+        }
+    }
+    Ok(result)
+}
+
+/// 读取 Firefox 的 `places.sqlite`
+///
+/// Firefox 运行时会锁住数据库，所以先把数据库（以及还没有合并的 `-wal` 文件）复制到临时目录再读取
+fn read_firefox_places(path: &Path) -> AppResult<Vec<Bookmark>> {
+    let temp_dir = std::env::temp_dir().join(format!("zerolaunch-places-{}", std::process::id()));
+    fs::create_dir_all(&temp_dir)?;
+    let result = (|| {
+        let copied = temp_dir.join(FIREFOX_PLACES_FILE);
+        fs::copy(path, &copied)?;
+        let wal = PathBuf::from(format!("{}-wal", path.display()));
+        if wal.is_file() {
+            fs::copy(&wal, temp_dir.join(format!("{}-wal", FIREFOX_PLACES_FILE)))?;
+        }
+        let connection = Connection::open(&copied)
+            .map_err(|e| bookmark_error(format!("无法打开 Firefox 的书签数据库: {}", e)))?;
+        query_firefox_bookmarks(&connection)
+            .map_err(|e| bookmark_error(format!("无法读取 Firefox 的书签: {}", e)))
+    })();
+    let _ = fs::remove_dir_all(&temp_dir);
+    result
+}
+
+/// `moz_bookmarks` 中的一行
+struct FirefoxItem {
+    parent: i64,
+    item_type: i64,
+    guid: String,
+    title: String,
+    url: String,
+}
+
+/// 从 `moz_bookmarks` 与 `moz_places` 中读出所有的书签，并沿着 `parent` 找到它所在的文件夹
+pub fn query_firefox_bookmarks(connection: &Connection) -> rusqlite::Result<Vec<Bookmark>> {
+    let mut statement = connection.prepare(
+        "SELECT b.id, b.parent, b.type, IFNULL(b.guid, ''), IFNULL(b.title, ''), IFNULL(p.url, '')
+         FROM moz_bookmarks b LEFT JOIN moz_places p ON b.fk = p.id
+         ORDER BY b.parent, b.position",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            FirefoxItem {
+                parent: row.get(1)?,
+                item_type: row.get(2)?,
+                guid: row.get(3)?,
+                title: row.get(4)?,
+                url: row.get(5)?,
+            },
+        ))
+    })?;
+    let mut items: Vec<(i64, FirefoxItem)> = Vec::new();
+    for row in rows {
+        items.push(row?);
+    }
+    let by_id: HashMap<i64, &FirefoxItem> = items.iter().map(|(id, item)| (*id, item)).collect();
+
+    let mut result = Vec::new();
+    for (_, item) in &items {
+        if item.item_type != FIREFOX_TYPE_BOOKMARK || !is_indexable_url(&item.url) {
+            continue;
+        }
+        let mut folders = Vec::new();
+        let mut parent = by_id.get(&item.parent);
+        let mut depth = 0;
+        while let Some(folder) = parent {
+            // 限制层数，避免损坏的数据库里出现环
+            depth += 1;
+            if depth > items.len() || FIREFOX_ROOT_GUIDS.contains(&folder.guid.as_str()) {
+                break;
+            }
+            if !folder.title.trim().is_empty() {
+                folders.push(folder.title.trim().to_string());
+            }
+            parent = by_id.get(&folder.parent);
+        }
+        folders.reverse();
+        result.push(Bookmark {
+            title: item.title.trim().to_string(),
+            url: item.url.trim().to_string(),
+            folders,
+        });
+    }
+    Ok(result)
+}
+
+/// 按网址去重，保持第一次出现的顺序
+///
+/// 同一个网址出现在多个文件夹（或者 Firefox 中带了标签）时，合并所有的文件夹名字，
+/// 名字取第一个不为空的，都为空时使用网址
+pub fn merge_bookmarks(bookmarks: Vec<Bookmark>) -> Vec<Bookmark> {
+    let mut result: Vec<Bookmark> = Vec::new();
+    let mut position: HashMap<String, usize> = HashMap::new();
+    for bookmark in bookmarks {
+        match position.get(&bookmark.url) {
+            Some(&index) => {
+                let existing = &mut result[index];
+                if existing.title.is_empty() {
+                    existing.title = bookmark.title;
+                }
+                for folder in bookmark.folders {
+                    if !existing.folders.contains(&folder) {
+                        existing.folders.push(folder);
+                    }
+                }
+            }
+            None => {
+                position.insert(bookmark.url.clone(), result.len());
+                result.push(bookmark);
+            }
+        }
+    }
+    for bookmark in &mut result {
+        if bookmark.title.is_empty() {
+            bookmark.title = bookmark.url.clone();
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chromium_bookmarks() {
+        let text = r#"{
+            "checksum": "0",
+            "roots": {
+                "bookmark_bar": {
+                    "name": "书签栏", "type": "folder",
+                    "children": [
+                        { "name": "GitHub", "type": "url", "url": "https://github.com/" },
+                        { "name": "工作", "type": "folder", "children": [
+                            { "name": "文档", "type": "folder", "children": [
+                                { "name": "Rust Book", "type": "url", "url": "https://doc.rust-lang.org/book/" }
+                            ]},
+                            { "name": "脚本", "type": "url", "url": "javascript:void(0)" }
+                        ]}
+                    ]
+                },
+                "other": { "name": "其他书签", "type": "folder", "children": [] },
+                "sync_transaction_version": "1"
+            },
+            "version": 1
+        }"#;
+        let bookmarks = parse_chromium_bookmarks(text).unwrap();
+        assert_eq!(
+            bookmarks,
+            vec![
+                Bookmark {
+                    title: "GitHub".to_string(),
+                    url: "https://github.com/".to_string(),
+                    folders: vec![],
+                },
+                Bookmark {
+                    title: "Rust Book".to_string(),
+                    url: "https://doc.rust-lang.org/book/".to_string(),
+                    folders: vec!["工作".to_string(), "文档".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query_firefox_bookmarks() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT);
+                 CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER,
+                     parent INTEGER, position INTEGER, title TEXT, guid TEXT);
+                 INSERT INTO moz_places VALUES (1, 'https://www.rust-lang.org/'), (2, 'place:sort=8');
+                 INSERT INTO moz_bookmarks VALUES
+                     (1, 2, NULL, 0, 0, '', 'root________'),
+                     (2, 2, NULL, 1, 0, 'toolbar', 'toolbar_____'),
+                     (4, 2, NULL, 1, 1, 'tags', 'tags________'),
+                     (10, 2, NULL, 2, 0, '编程', 'folder000001'),
+                     (11, 1, 1, 10, 0, 'Rust', 'bookmark0001'),
+                     (12, 1, 2, 2, 1, '最近访问', 'bookmark0002'),
+                     (20, 2, NULL, 4, 0, 'lang', 'tag000000001'),
+                     (21, 1, 1, 20, 0, NULL, 'bookmark0003');",
+            )
+            .unwrap();
+
+        let bookmarks = merge_bookmarks(query_firefox_bookmarks(&connection).unwrap());
+        assert_eq!(
+            bookmarks,
+            vec![Bookmark {
+                title: "Rust".to_string(),
+                url: "https://www.rust-lang.org/".to_string(),
+                folders: vec!["编程".to_string(), "lang".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_merge_bookmarks() {
+        let bookmark = |title: &str, url: &str, folders: &[&str]| Bookmark {
+            title: title.to_string(),
+            url: url.to_string(),
+            folders: folders.iter().map(|f| f.to_string()).collect(),
+        };
+        let merged = merge_bookmarks(vec![
+            bookmark("", "https://a.com/", &["x"]),
+            bookmark("B", "https://b.com/", &[]),
+            bookmark("A", "https://a.com/", &["x", "y"]),
+            bookmark("", "https://c.com/", &[]),
+        ]);
+        assert_eq!(
+            merged,
+            vec![
+                bookmark("A", "https://a.com/", &["x", "y"]),
+                bookmark("B", "https://b.com/", &[]),
+                bookmark("https://c.com/", "https://c.com/", &[]),
+            ]
+        );
+    }
+}
@@ -3,16 +3,21 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct PartialProgramLoaderConfig {
     pub target_paths: Option<Vec<DirectoryConfig>>,
     pub program_bias: Option<HashMap<String, (f64, String)>>,
     pub is_scan_uwp_programs: Option<bool>,
+    pub is_scan_desktop_entries: Option<bool>,
     pub index_web_pages: Option<Vec<(String, String)>>,
     pub custom_command: Option<Vec<(String, String)>>,
     pub forbidden_paths: Option<Vec<String>>,
     pub program_alias: Option<HashMap<String, Vec<String>>>,
     pub semantic_descriptions: Option<HashMap<String, String>>,
+    pub hidden_programs: Option<HashMap<String, String>>,
+    pub bookmark_paths: Option<Vec<String>>,
+    pub disabled_sources: Option<Vec<String>>,
+    pub source_options: Option<HashMap<String, serde_json::Value>>,
 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DirectoryConfig {
@@ -26,6 +31,12 @@ pub struct DirectoryConfig {
     pub pattern_type: String,
     /// 要禁止的程序关键字
     pub excluded_keywords: Vec<String>,
+    /// 扫描这个文件夹最多等待的时间（毫秒），超时后放弃这次扫描并保留上一次的结果，0 表示不限制
+    #[serde(default)]
+    pub max_scan_millis: u64,
+    /// 扫描这个文件夹最多遍历的文件与文件夹数量，超出后停止扫描它，0 表示不限制
+    #[serde(default)]
+    pub max_scan_entries: u64,
 }
 
 impl DirectoryConfig {
@@ -46,6 +57,8 @@ impl DirectoryConfig {
                 "卸载".to_string(),
                 "zerolaunch-rs".to_string(),
             ],
+            max_scan_millis: 0,
+            max_scan_entries: 0,
         }
     }
 }
@@ -62,6 +75,9 @@ pub struct ProgramLoaderConfigInner {
     /// 是不是要遍历uwp应用
     #[serde(default = "ProgramLoaderConfigInner::default_is_scan_uwp_programs")]
     pub is_scan_uwp_programs: bool,
+    /// 是不是要遍历 freedesktop 的 .desktop 文件(Linux)
+    #[serde(default = "ProgramLoaderConfigInner::default_is_scan_desktop_entries")]
+    pub is_scan_desktop_entries: bool,
     /// 索引的网页(关键字，网址)
     #[serde(default = "ProgramLoaderConfigInner::default_index_web_pages")]
     pub index_web_pages: Vec<(String, String)>,
@@ -76,6 +92,18 @@ pub struct ProgramLoaderConfigInner {
     /// 程序的语义性描述信息 (launch_method) => (description)
     #[serde(default = "ProgramLoaderConfigInner::default_semantic_descriptions")]
     pub semantic_descriptions: HashMap<String, String>,
+    /// 用户隐藏的程序，不会出现在搜索结果中 (launch_method) => (show_name)
+    #[serde(default = "ProgramLoaderConfigInner::default_hidden_programs")]
+    pub hidden_programs: HashMap<String, String>,
+    /// 要索引书签的浏览器用户目录，或者其中的 `Bookmarks` / `places.sqlite` 文件
+    #[serde(default = "ProgramLoaderConfigInner::default_bookmark_paths")]
+    pub bookmark_paths: Vec<String>,
+    /// 被关闭的程序来源 (source id)
+    #[serde(default = "ProgramLoaderConfigInner::default_disabled_sources")]
+    pub disabled_sources: Vec<String>,
+    /// 每个程序来源自己的选项 (source id) => (options)
+    #[serde(default = "ProgramLoaderConfigInner::default_source_options")]
+    pub source_options: HashMap<String, serde_json::Value>,
 }
 
 impl Default for ProgramLoaderConfigInner {
@@ -84,11 +112,16 @@ impl Default for ProgramLoaderConfigInner {
             target_paths: Self::default_target_paths(),
             program_bias: Self::default_program_bias(),
             is_scan_uwp_programs: Self::default_is_scan_uwp_programs(),
+            is_scan_desktop_entries: Self::default_is_scan_desktop_entries(),
             index_web_pages: Self::default_index_web_pages(),
             custom_command: Self::default_custom_command(),
             forbidden_paths: Self::default_forbidden_paths(),
             program_alias: Self::default_program_alias(),
             semantic_descriptions: Self::default_semantic_descriptions(),
+            hidden_programs: Self::default_hidden_programs(),
+            bookmark_paths: Self::default_bookmark_paths(),
+            disabled_sources: Self::default_disabled_sources(),
+            source_options: Self::default_source_options(),
         }
     }
 }
@@ -121,6 +154,10 @@ impl ProgramLoaderConfigInner {
         true
     }
 
+    pub(crate) fn default_is_scan_desktop_entries() -> bool {
+        cfg!(target_os = "linux")
+    }
+
     pub(crate) fn default_index_web_pages() -> Vec<(String, String)> {
         vec![
             // 预置 Bing 搜索，占位符 {} 会在运行时被替换为用户输入
@@ -146,6 +183,22 @@ impl ProgramLoaderConfigInner {
     pub(crate) fn default_semantic_descriptions() -> HashMap<String, String> {
         HashMap::new()
     }
+
+    pub(crate) fn default_hidden_programs() -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    pub(crate) fn default_bookmark_paths() -> Vec<String> {
+        Vec::new()
+    }
+
+    pub(crate) fn default_disabled_sources() -> Vec<String> {
+        Vec::new()
+    }
+
+    pub(crate) fn default_source_options() -> HashMap<String, serde_json::Value> {
+        HashMap::new()
+    }
 }
 
 impl ProgramLoaderConfigInner {
@@ -154,11 +207,16 @@ impl ProgramLoaderConfigInner {
             target_paths: Some(self.target_paths.clone()),
             program_bias: Some(self.program_bias.clone()),
             is_scan_uwp_programs: Some(self.is_scan_uwp_programs),
+            is_scan_desktop_entries: Some(self.is_scan_desktop_entries),
             index_web_pages: Some(self.index_web_pages.clone()),
             custom_command: Some(self.custom_command.clone()),
             forbidden_paths: Some(self.forbidden_paths.clone()),
             program_alias: Some(self.program_alias.clone()),
             semantic_descriptions: Some(self.semantic_descriptions.clone()),
+            hidden_programs: Some(self.hidden_programs.clone()),
+            bookmark_paths: Some(self.bookmark_paths.clone()),
+            disabled_sources: Some(self.disabled_sources.clone()),
+            source_options: Some(self.source_options.clone()),
         }
     }
 
@@ -172,6 +230,9 @@ impl ProgramLoaderConfigInner {
         if let Some(partial_is_scan_uwp_programs) = partial_config.is_scan_uwp_programs {
             self.is_scan_uwp_programs = partial_is_scan_uwp_programs;
         }
+        if let Some(partial_is_scan_desktop_entries) = partial_config.is_scan_desktop_entries {
+            self.is_scan_desktop_entries = partial_is_scan_desktop_entries;
+        }
         if let Some(partial_index_web_pages) = partial_config.index_web_pages {
             self.index_web_pages = partial_index_web_pages;
         }
@@ -187,6 +248,18 @@ impl ProgramLoaderConfigInner {
         if let Some(partial_semantic_descriptions) = partial_config.semantic_descriptions {
             self.semantic_descriptions = partial_semantic_descriptions;
         }
+        if let Some(partial_hidden_programs) = partial_config.hidden_programs {
+            self.hidden_programs = partial_hidden_programs;
+        }
+        if let Some(partial_bookmark_paths) = partial_config.bookmark_paths {
+            self.bookmark_paths = partial_bookmark_paths;
+        }
+        if let Some(partial_disabled_sources) = partial_config.disabled_sources {
+            self.disabled_sources = partial_disabled_sources;
+        }
+        if let Some(partial_source_options) = partial_config.source_options {
+            self.source_options = partial_source_options;
+        }
     }
 }
 #[derive(Debug)]
@@ -220,6 +293,10 @@ impl ProgramLoaderConfig {
         self.inner.read().is_scan_uwp_programs
     }
 
+    pub fn get_is_scan_desktop_entries(&self) -> bool {
+        self.inner.read().is_scan_desktop_entries
+    }
+
     pub fn get_index_web_pages(&self) -> Vec<(String, String)> {
         self.inner.read().index_web_pages.clone()
     }
@@ -241,4 +318,16 @@ impl ProgramLoaderConfig {
     pub fn get_semantic_descriptions(&self) -> HashMap<String, String> {
         self.inner.read().semantic_descriptions.clone()
     }
+    pub fn get_hidden_programs(&self) -> HashMap<String, String> {
+        self.inner.read().hidden_programs.clone()
+    }
+    pub fn get_bookmark_paths(&self) -> Vec<String> {
+        self.inner.read().bookmark_paths.clone()
+    }
+    pub fn get_disabled_sources(&self) -> Vec<String> {
+        self.inner.read().disabled_sources.clone()
+    }
+    pub fn get_source_options(&self) -> HashMap<String, serde_json::Value> {
+        self.inner.read().source_options.clone()
+    }
 }
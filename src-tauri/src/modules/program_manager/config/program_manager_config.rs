@@ -3,6 +3,7 @@ use super::image_loader_config::RuntimeImageLoaderConfig;
 use crate::modules::program_manager::config::image_loader_config::ImageLoaderConfig;
 use crate::modules::program_manager::config::program_ranker_config::PartialProgramRankerConfig;
 use crate::modules::program_manager::config::program_ranker_config::ProgramRankerConfig;
+use crate::modules::program_manager::score_normalizer::ScoreNormalization;
 use crate::modules::program_manager::semantic_manager::EmbeddingBackend;
 use crate::program_manager::config::program_loader_config::PartialProgramLoaderConfig;
 use crate::program_manager::config::program_loader_config::ProgramLoaderConfig;
@@ -11,12 +12,13 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct PartialProgramManagerConfig {
     pub ranker: Option<PartialProgramRankerConfig>,
     pub loader: Option<PartialProgramLoaderConfig>,
     pub image_loader: Option<PartialImageLoaderConfig>,
     pub search_model: Option<Arc<SearchModelConfig>>,
+    pub score_normalization: Option<ScoreNormalization>,
     pub enable_lru_search_cache: Option<bool>,
     pub search_cache_capacity: Option<usize>,
 }
@@ -27,6 +29,8 @@ pub struct ProgramManagerConfigInner {
     pub loader_config: Arc<ProgramLoaderConfig>,
     pub image_loader: Arc<ImageLoaderConfig>,
     pub search_model: Arc<SearchModelConfig>,
+    /// 基础分数的归一化方式，使排序权重在不同的搜索模型下效果一致
+    pub score_normalization: ScoreNormalization,
     pub enable_lru_search_cache: bool,
     pub search_cache_capacity: usize,
 }
@@ -38,6 +42,7 @@ impl Default for ProgramManagerConfigInner {
             loader_config: Arc::new(ProgramLoaderConfig::default()),
             image_loader: Arc::new(ImageLoaderConfig::default()),
             search_model: Arc::new(SearchModelConfig::default()),
+            score_normalization: ScoreNormalization::default(),
             enable_lru_search_cache: false,
             search_cache_capacity: 120,
         }
@@ -51,6 +56,7 @@ impl ProgramManagerConfigInner {
             loader: Some(self.loader_config.to_partial()),
             image_loader: Some(self.image_loader.to_partial()),
             search_model: Some(self.search_model.clone()),
+            score_normalization: Some(self.score_normalization),
             enable_lru_search_cache: Some(self.enable_lru_search_cache),
             search_cache_capacity: Some(self.search_cache_capacity),
         }
@@ -68,6 +74,9 @@ impl ProgramManagerConfigInner {
         if let Some(new_search_model) = partial_config.search_model {
             self.search_model = new_search_model;
         }
+        if let Some(score_normalization) = partial_config.score_normalization {
+            self.score_normalization = score_normalization;
+        }
         if let Some(enable_cache) = partial_config.enable_lru_search_cache {
             self.enable_lru_search_cache = enable_cache;
         }
@@ -115,6 +124,10 @@ impl ProgramManagerConfig {
         self.inner.read().search_model.clone()
     }
 
+    pub fn get_score_normalization(&self) -> ScoreNormalization {
+        self.inner.read().score_normalization
+    }
+
     pub fn is_lru_search_cache_enabled(&self) -> bool {
         self.inner.read().enable_lru_search_cache
     }
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 
 /// 部分程序排序器配置（用于运行时数据导出）
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct PartialProgramRankerConfig {
     pub launch_info: Option<VecDeque<HashMap<String, u64>>>,
     pub history_launch_time: Option<HashMap<String, u64>>,
@@ -12,6 +12,16 @@ pub struct PartialProgramRankerConfig {
     pub latest_launch_time: Option<HashMap<String, i64>>,
     /// 查询亲和度存储: 查询词 -> { "launch_method.get_text()" -> QueryAffinityData }
     pub query_affinity_store: Option<HashMap<String, HashMap<String, QueryAffinityData>>>,
+    /// 按一周中的小时统计的启动次数: "launch_method.get_text()" -> [168 个小时的启动次数]
+    pub hour_of_week_usage: Option<HashMap<String, Vec<u64>>>,
+    /// 不在程序注册表中的启动方式已经连续缺席了多少次刷新: "launch_method.get_text()" -> 次数
+    pub absent_refreshes: Option<HashMap<String, u32>>,
+    /// 启动顺序统计: 先启动的 launch_method_text -> { 紧接着启动的 launch_method_text -> 次数 }
+    pub launch_transitions: Option<HashMap<String, HashMap<String, u64>>>,
+    /// 从其他启动器导入过的使用记录: 来源 -> 导入的情况
+    pub usage_imports: Option<HashMap<String, UsageImportState>>,
+    /// 用户固定在某些查询的结果最前面的程序，按列表中的顺序排列
+    pub query_pins: Option<Vec<QueryPin>>,
     /// 历史总分权重系数 (默认1.2)
     pub history_weight: Option<f64>,
     /// 近期习惯权重系数 (7天内,默认2.5)
@@ -20,12 +30,22 @@ pub struct PartialProgramRankerConfig {
     pub temporal_weight: Option<f64>,
     /// 查询亲和系数 (默认3.5)
     pub query_affinity_weight: Option<f64>,
+    /// 使用时段系数 (默认1.0)
+    pub time_of_day_weight: Option<f64>,
     /// 查询亲和时间衰减常数(秒) (默认259200 = 3天)
     pub query_affinity_time_decay: Option<i64>,
     /// 短期热度衰减常数(秒) (默认10800 = 3小时)
     pub temporal_decay: Option<i64>,
+    /// 两次启动间隔在这个时间(秒)以内才算作先后启动 (默认900 = 15分钟)
+    pub transition_window: Option<i64>,
     /// 是否启用排序算法 (默认true)
     pub is_enable: Option<bool>,
+    /// 最多保留多少个程序的启动统计，0 表示不限制 (默认3000)
+    pub max_tracked_programs: Option<usize>,
+    /// 超过多少天没有启动的程序的统计会被清理，0 表示不限制 (默认365)
+    pub max_launch_age_days: Option<u32>,
+    /// 连续多少次刷新都不在程序注册表中的程序的统计会被清理，0 表示不清理 (默认100)
+    pub prune_after_absent_refreshes: Option<u32>,
 }
 
 /// 查询亲和度数据
@@ -35,6 +55,29 @@ pub struct QueryAffinityData {
     pub last_launch_time: i64,
 }
 
+/// 从一个来源导入过的使用记录，再次导入同一个来源时只计入增加的部分
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageImportState {
+    /// 最后一次导入的时间（时间戳）
+    pub imported_at: i64,
+    /// 导入的记录覆盖的时间范围（来源中记录的启动时间），没有导入过带时间的记录时为 0
+    pub period_start: i64,
+    pub period_end: i64,
+    /// 已经计入的启动次数: "launch_method.get_text()" -> { 查询词（没有查询时为空） -> 次数 }
+    pub launch_counts: HashMap<String, HashMap<String, u64>>,
+}
+
+/// 固定在某个查询的结果最前面的程序
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryPin {
+    /// 规范化后的查询词（小写，合并空格）
+    pub query: String,
+    /// 为 true 时，输入以 `query` 开头（例如继续输入了更多字符）也生效
+    pub is_prefix: bool,
+    /// launch_method.get_text()
+    pub launch_method: String,
+}
+
 /// 程序排序器配置内部结构
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
@@ -54,6 +97,21 @@ pub struct ProgramRankerConfigInner {
     /// 查询亲和度存储
     #[serde(default = "ProgramRankerConfigInner::default_query_affinity_store")]
     pub query_affinity_store: HashMap<String, HashMap<String, QueryAffinityData>>,
+    /// 按一周中的小时统计的启动次数
+    #[serde(default = "ProgramRankerConfigInner::default_hour_of_week_usage")]
+    pub hour_of_week_usage: HashMap<String, Vec<u64>>,
+    /// 不在程序注册表中的启动方式已经连续缺席了多少次刷新
+    #[serde(default = "ProgramRankerConfigInner::default_absent_refreshes")]
+    pub absent_refreshes: HashMap<String, u32>,
+    /// 启动顺序统计
+    #[serde(default = "ProgramRankerConfigInner::default_launch_transitions")]
+    pub launch_transitions: HashMap<String, HashMap<String, u64>>,
+    /// 从其他启动器导入过的使用记录
+    #[serde(default = "ProgramRankerConfigInner::default_usage_imports")]
+    pub usage_imports: HashMap<String, UsageImportState>,
+    /// 固定在查询结果最前面的程序
+    #[serde(default = "ProgramRankerConfigInner::default_query_pins")]
+    pub query_pins: Vec<QueryPin>,
     /// 历史总分权重系数
     #[serde(default = "ProgramRankerConfigInner::default_history_weight")]
     pub history_weight: f64,
@@ -66,15 +124,30 @@ pub struct ProgramRankerConfigInner {
     /// 查询亲和系数
     #[serde(default = "ProgramRankerConfigInner::default_query_affinity_weight")]
     pub query_affinity_weight: f64,
+    /// 使用时段系数
+    #[serde(default = "ProgramRankerConfigInner::default_time_of_day_weight")]
+    pub time_of_day_weight: f64,
     /// 查询亲和时间衰减常数
     #[serde(default = "ProgramRankerConfigInner::default_query_affinity_time_decay")]
     pub query_affinity_time_decay: i64,
     /// 短期热度衰减常数
     #[serde(default = "ProgramRankerConfigInner::default_temporal_decay")]
     pub temporal_decay: i64,
+    /// 算作先后启动的最大间隔
+    #[serde(default = "ProgramRankerConfigInner::default_transition_window")]
+    pub transition_window: i64,
     /// 是否启用排序算法
     #[serde(default = "ProgramRankerConfigInner::default_is_enable")]
     pub is_enable: bool,
+    /// 最多保留多少个程序的启动统计
+    #[serde(default = "ProgramRankerConfigInner::default_max_tracked_programs")]
+    pub max_tracked_programs: usize,
+    /// 超过多少天没有启动的程序的统计会被清理
+    #[serde(default = "ProgramRankerConfigInner::default_max_launch_age_days")]
+    pub max_launch_age_days: u32,
+    /// 连续多少次刷新都不在程序注册表中的程序的统计会被清理
+    #[serde(default = "ProgramRankerConfigInner::default_prune_after_absent_refreshes")]
+    pub prune_after_absent_refreshes: u32,
 }
 
 impl Default for ProgramRankerConfigInner {
@@ -85,13 +158,23 @@ impl Default for ProgramRankerConfigInner {
             last_update_data: Self::default_last_update_data(),
             latest_launch_time: Self::default_latest_launch_time(),
             query_affinity_store: Self::default_query_affinity_store(),
+            hour_of_week_usage: Self::default_hour_of_week_usage(),
+            absent_refreshes: Self::default_absent_refreshes(),
+            launch_transitions: Self::default_launch_transitions(),
+            usage_imports: Self::default_usage_imports(),
+            query_pins: Self::default_query_pins(),
             history_weight: Self::default_history_weight(),
             recent_habit_weight: Self::default_recent_habit_weight(),
             temporal_weight: Self::default_temporal_weight(),
             query_affinity_weight: Self::default_query_affinity_weight(),
+            time_of_day_weight: Self::default_time_of_day_weight(),
             query_affinity_time_decay: Self::default_query_affinity_time_decay(),
             temporal_decay: Self::default_temporal_decay(),
+            transition_window: Self::default_transition_window(),
             is_enable: Self::default_is_enable(),
+            max_tracked_programs: Self::default_max_tracked_programs(),
+            max_launch_age_days: Self::default_max_launch_age_days(),
+            prune_after_absent_refreshes: Self::default_prune_after_absent_refreshes(),
         }
     }
 }
@@ -120,6 +203,26 @@ impl ProgramRankerConfigInner {
         HashMap::new()
     }
 
+    pub(crate) fn default_hour_of_week_usage() -> HashMap<String, Vec<u64>> {
+        HashMap::new()
+    }
+
+    pub(crate) fn default_absent_refreshes() -> HashMap<String, u32> {
+        HashMap::new()
+    }
+
+    pub(crate) fn default_launch_transitions() -> HashMap<String, HashMap<String, u64>> {
+        HashMap::new()
+    }
+
+    pub(crate) fn default_usage_imports() -> HashMap<String, UsageImportState> {
+        HashMap::new()
+    }
+
+    pub(crate) fn default_query_pins() -> Vec<QueryPin> {
+        Vec::new()
+    }
+
     pub(crate) fn default_history_weight() -> f64 {
         1.2
     }
@@ -136,6 +239,10 @@ impl ProgramRankerConfigInner {
         3.5
     }
 
+    pub(crate) fn default_time_of_day_weight() -> f64 {
+        1.0
+    }
+
     pub(crate) fn default_query_affinity_time_decay() -> i64 {
         259200 // 3 days in seconds
     }
@@ -144,10 +251,26 @@ impl ProgramRankerConfigInner {
         10800 // 3 hours in seconds
     }
 
+    pub(crate) fn default_transition_window() -> i64 {
+        900 // 15 minutes in seconds
+    }
+
     pub(crate) fn default_is_enable() -> bool {
         true
     }
 
+    pub(crate) fn default_max_tracked_programs() -> usize {
+        3000
+    }
+
+    pub(crate) fn default_max_launch_age_days() -> u32 {
+        365
+    }
+
+    pub(crate) fn default_prune_after_absent_refreshes() -> u32 {
+        100
+    }
+
     pub fn to_partial(&self) -> PartialProgramRankerConfig {
         PartialProgramRankerConfig {
             history_launch_time: Some(self.history_launch_time.clone()),
@@ -155,13 +278,23 @@ impl ProgramRankerConfigInner {
             launch_info: Some(self.launch_info.clone()),
             latest_launch_time: Some(self.latest_launch_time.clone()),
             query_affinity_store: Some(self.query_affinity_store.clone()),
+            hour_of_week_usage: Some(self.hour_of_week_usage.clone()),
+            absent_refreshes: Some(self.absent_refreshes.clone()),
+            launch_transitions: Some(self.launch_transitions.clone()),
+            usage_imports: Some(self.usage_imports.clone()),
+            query_pins: Some(self.query_pins.clone()),
             history_weight: Some(self.history_weight),
             recent_habit_weight: Some(self.recent_habit_weight),
             temporal_weight: Some(self.temporal_weight),
             query_affinity_weight: Some(self.query_affinity_weight),
+            time_of_day_weight: Some(self.time_of_day_weight),
             query_affinity_time_decay: Some(self.query_affinity_time_decay),
             temporal_decay: Some(self.temporal_decay),
+            transition_window: Some(self.transition_window),
             is_enable: Some(self.is_enable),
+            max_tracked_programs: Some(self.max_tracked_programs),
+            max_launch_age_days: Some(self.max_launch_age_days),
+            prune_after_absent_refreshes: Some(self.prune_after_absent_refreshes),
         }
     }
 
@@ -181,6 +314,21 @@ impl ProgramRankerConfigInner {
         if let Some(partial_query_affinity_store) = partial_config.query_affinity_store {
             self.query_affinity_store = partial_query_affinity_store;
         }
+        if let Some(partial_hour_of_week_usage) = partial_config.hour_of_week_usage {
+            self.hour_of_week_usage = partial_hour_of_week_usage;
+        }
+        if let Some(partial_absent_refreshes) = partial_config.absent_refreshes {
+            self.absent_refreshes = partial_absent_refreshes;
+        }
+        if let Some(partial_launch_transitions) = partial_config.launch_transitions {
+            self.launch_transitions = partial_launch_transitions;
+        }
+        if let Some(partial_usage_imports) = partial_config.usage_imports {
+            self.usage_imports = partial_usage_imports;
+        }
+        if let Some(partial_query_pins) = partial_config.query_pins {
+            self.query_pins = partial_query_pins;
+        }
         if let Some(weight) = partial_config.history_weight {
             self.history_weight = weight;
         }
@@ -193,15 +341,30 @@ impl ProgramRankerConfigInner {
         if let Some(weight) = partial_config.query_affinity_weight {
             self.query_affinity_weight = weight;
         }
+        if let Some(weight) = partial_config.time_of_day_weight {
+            self.time_of_day_weight = weight;
+        }
         if let Some(decay) = partial_config.query_affinity_time_decay {
             self.query_affinity_time_decay = decay;
         }
         if let Some(decay) = partial_config.temporal_decay {
             self.temporal_decay = decay;
         }
+        if let Some(window) = partial_config.transition_window {
+            self.transition_window = window;
+        }
         if let Some(enable) = partial_config.is_enable {
             self.is_enable = enable;
         }
+        if let Some(max_tracked_programs) = partial_config.max_tracked_programs {
+            self.max_tracked_programs = max_tracked_programs;
+        }
+        if let Some(max_launch_age_days) = partial_config.max_launch_age_days {
+            self.max_launch_age_days = max_launch_age_days;
+        }
+        if let Some(prune_after_absent_refreshes) = partial_config.prune_after_absent_refreshes {
+            self.prune_after_absent_refreshes = prune_after_absent_refreshes;
+        }
     }
 }
 
@@ -245,6 +408,26 @@ impl ProgramRankerConfig {
         self.inner.read().query_affinity_store.clone()
     }
 
+    pub fn get_hour_of_week_usage(&self) -> HashMap<String, Vec<u64>> {
+        self.inner.read().hour_of_week_usage.clone()
+    }
+
+    pub fn get_absent_refreshes(&self) -> HashMap<String, u32> {
+        self.inner.read().absent_refreshes.clone()
+    }
+
+    pub fn get_launch_transitions(&self) -> HashMap<String, HashMap<String, u64>> {
+        self.inner.read().launch_transitions.clone()
+    }
+
+    pub fn get_usage_imports(&self) -> HashMap<String, UsageImportState> {
+        self.inner.read().usage_imports.clone()
+    }
+
+    pub fn get_query_pins(&self) -> Vec<QueryPin> {
+        self.inner.read().query_pins.clone()
+    }
+
     pub fn get_history_weight(&self) -> f64 {
         self.inner.read().history_weight
     }
@@ -261,6 +444,10 @@ impl ProgramRankerConfig {
         self.inner.read().query_affinity_weight
     }
 
+    pub fn get_time_of_day_weight(&self) -> f64 {
+        self.inner.read().time_of_day_weight
+    }
+
     pub fn get_query_affinity_time_decay(&self) -> i64 {
         self.inner.read().query_affinity_time_decay
     }
@@ -269,10 +456,26 @@ impl ProgramRankerConfig {
         self.inner.read().temporal_decay
     }
 
+    pub fn get_transition_window(&self) -> i64 {
+        self.inner.read().transition_window
+    }
+
     pub fn get_is_enable(&self) -> bool {
         self.inner.read().is_enable
     }
 
+    pub fn get_max_tracked_programs(&self) -> usize {
+        self.inner.read().max_tracked_programs
+    }
+
+    pub fn get_max_launch_age_days(&self) -> u32 {
+        self.inner.read().max_launch_age_days
+    }
+
+    pub fn get_prune_after_absent_refreshes(&self) -> u32 {
+        self.inner.read().prune_after_absent_refreshes
+    }
+
     pub fn update(&self, partial_config: PartialProgramRankerConfig) {
         let mut inner = self.inner.write();
         inner.update(partial_config);
@@ -0,0 +1,469 @@
+/// 这个文件用于解析 freedesktop 规范中的 `.desktop` 文件（Linux 下的应用程序入口）
+/// 规范地址：https://specifications.freedesktop.org/desktop-entry-spec/latest/
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// 一个解析后的 `[Desktop Entry]` 段
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopEntry {
+    /// 按照当前语言选出的名字
+    pub name: String,
+    /// 未本地化的名字（与 `name` 相同时为空）
+    pub default_name: String,
+    /// 通用名字，如 "Web Browser"
+    pub generic_name: String,
+    /// 额外的搜索关键字
+    pub keywords: Vec<String>,
+    /// 去除了字段代码的启动命令
+    pub exec: String,
+    /// 图标名或图标的绝对路径
+    pub icon: String,
+    /// 用于判断程序是否已安装的可执行文件
+    pub try_exec: String,
+    pub no_display: bool,
+    pub hidden: bool,
+    pub only_show_in: Vec<String>,
+    pub not_show_in: Vec<String>,
+}
+
+impl DesktopEntry {
+    /// 判断这个入口是否应该出现在启动器中
+    ///
+    /// `current_desktops` 是 `$XDG_CURRENT_DESKTOP` 中的桌面环境列表
+    pub fn should_show(&self, current_desktops: &[String]) -> bool {
+        if self.no_display || self.hidden || self.exec.is_empty() {
+            return false;
+        }
+        if !self.only_show_in.is_empty()
+            && !self
+                .only_show_in
+                .iter()
+                .any(|desktop| current_desktops.contains(desktop))
+        {
+            return false;
+        }
+        if self
+            .not_show_in
+            .iter()
+            .any(|desktop| current_desktops.contains(desktop))
+        {
+            return false;
+        }
+        self.try_exec.is_empty() || find_executable(&self.try_exec).is_some()
+    }
+}
+
+/// 解析一个 `.desktop` 文件的内容，只处理 `Type=Application` 的入口
+///
+/// `locale` 为 `lang_COUNTRY.ENCODING@MODIFIER` 形式的语言设置，例如 `zh_CN.UTF-8`
+pub fn parse_desktop_entry(content: &str, locale: &str) -> Option<DesktopEntry> {
+    let mut fields: HashMap<String, String> = HashMap::new();
+    let mut in_main_group = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_main_group {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            fields
+                .entry(key.trim().to_string())
+                .or_insert_with(|| unescape_value(value.trim()));
+        }
+    }
+
+    if fields.get("Type").map(String::as_str) != Some("Application") {
+        return None;
+    }
+
+    let locale_keys = locale_lookup_keys(locale);
+    let localized = |key: &str| -> String {
+        for suffix in &locale_keys {
+            if let Some(value) = fields.get(&format!("{}[{}]", key, suffix)) {
+                return value.clone();
+            }
+        }
+        fields.get(key).cloned().unwrap_or_default()
+    };
+
+    let default_name = fields.get("Name").cloned().unwrap_or_default();
+    let name = localized("Name");
+    if name.is_empty() {
+        return None;
+    }
+
+    let is_true = |key: &str| fields.get(key).map(String::as_str) == Some("true");
+
+    Some(DesktopEntry {
+        default_name: if default_name == name {
+            String::new()
+        } else {
+            default_name
+        },
+        name,
+        generic_name: localized("GenericName"),
+        keywords: split_list(&localized("Keywords")),
+        exec: strip_field_codes(fields.get("Exec").map(String::as_str).unwrap_or_default()),
+        icon: fields.get("Icon").cloned().unwrap_or_default(),
+        try_exec: fields.get("TryExec").cloned().unwrap_or_default(),
+        no_display: is_true("NoDisplay"),
+        hidden: is_true("Hidden"),
+        only_show_in: split_list(
+            fields
+                .get("OnlyShowIn")
+                .map(String::as_str)
+                .unwrap_or_default(),
+        ),
+        not_show_in: split_list(
+            fields
+                .get("NotShowIn")
+                .map(String::as_str)
+                .unwrap_or_default(),
+        ),
+    })
+}
+
+/// 按规范生成本地化键的查找顺序：
+/// lang_COUNTRY@MODIFIER, lang_COUNTRY, lang@MODIFIER, lang
+fn locale_lookup_keys(locale: &str) -> Vec<String> {
+    // 去除编码部分 (.UTF-8)
+    let (without_modifier, modifier) = match locale.split_once('@') {
+        Some((head, modifier)) => (head, Some(modifier)),
+        None => (locale, None),
+    };
+    let without_encoding = without_modifier
+        .split_once('.')
+        .map(|(head, _)| head)
+        .unwrap_or(without_modifier);
+    let (lang, country) = match without_encoding.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (without_encoding, None),
+    };
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return Vec::new();
+    }
+
+    let mut keys = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        keys.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        keys.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        keys.push(format!("{}@{}", lang, modifier));
+    }
+    keys.push(lang.to_string());
+    keys
+}
+
+/// 处理值中的转义字符 (\s \n \t \r \\)
+fn unescape_value(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            // 列表中的 "\;" 需要保留给 split_list 处理
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// 将以 `;` 分隔的列表拆开（支持 `\;` 转义）
+fn split_list(value: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            ';' => {
+                let item = current.trim();
+                if !item.is_empty() {
+                    result.push(item.to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    let item = current.trim();
+    if !item.is_empty() {
+        result.push(item.to_string());
+    }
+    result
+}
+
+/// 去除 Exec 中的字段代码 (%f %F %u %U %i %c %k ...)，并将 `%%` 还原为 `%`
+pub fn strip_field_codes(exec: &str) -> String {
+    let mut result = String::with_capacity(exec.len());
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        // 其余的字段代码（包括已废弃的 %d %D %n %N %v %m）都直接丢弃
+        if chars.next() == Some('%') {
+            result.push('%');
+        }
+    }
+    result.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 在 `$PATH` 中查找一个可执行文件（绝对路径则直接检查是否存在）
+fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return path.exists().then(|| path.to_path_buf());
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(name))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// `$XDG_DATA_HOME` 与 `$XDG_DATA_DIRS`，按优先级从高到低排列
+fn xdg_data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match env::var_os("XDG_DATA_HOME").filter(|value| !value.is_empty()) {
+        Some(data_home) => dirs.push(PathBuf::from(data_home)),
+        None => {
+            if let Some(home) = env::var_os("HOME") {
+                dirs.push(PathBuf::from(home).join(".local/share"));
+            }
+        }
+    }
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from),
+    );
+    dirs
+}
+
+/// 所有存放 `.desktop` 文件的目录，按优先级从高到低排列
+pub fn application_dirs() -> Vec<PathBuf> {
+    xdg_data_dirs()
+        .into_iter()
+        .map(|dir| dir.join("applications"))
+        .collect()
+}
+
+/// 当前桌面环境的名字列表 (`$XDG_CURRENT_DESKTOP`)
+pub fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .map(|value| {
+            value
+                .split(':')
+                .filter(|item| !item.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 当前的语言设置，依次读取 `$LC_ALL`、`$LC_MESSAGES`、`$LANG`
+pub fn current_locale() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| env::var(key).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+/// 收集所有的 `.desktop` 文件
+///
+/// 返回值：(desktop file id, 文件路径)。同一个 id 只保留优先级最高的目录中的文件，
+/// 子目录中的文件的 id 为 "子目录-文件名"
+pub fn collect_desktop_files(dirs: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut result = Vec::new();
+    for dir in dirs {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(dir)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("desktop") {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(dir) else {
+                continue;
+            };
+            let id = relative
+                .to_string_lossy()
+                .replace(std::path::MAIN_SEPARATOR, "-");
+            if seen.insert(id.clone()) {
+                result.push((id, path.to_path_buf()));
+            }
+        }
+    }
+    result
+}
+
+/// 读取并解析一个 `.desktop` 文件
+pub fn load_desktop_entry(path: &Path, locale: &str) -> Option<DesktopEntry> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_desktop_entry(&content, locale),
+        Err(e) => {
+            debug!("读取desktop文件失败: {:?}, {}", path, e);
+            None
+        }
+    }
+}
+
+/// 通过图标主题查找图标文件
+///
+/// 依次查找 `hicolor` 主题中从大到小的尺寸，最后查找 `pixmaps` 目录
+pub fn lookup_icon(icon: &str) -> Option<PathBuf> {
+    if icon.is_empty() {
+        return None;
+    }
+    let icon_path = Path::new(icon);
+    if icon_path.is_absolute() {
+        return icon_path.exists().then(|| icon_path.to_path_buf());
+    }
+
+    const SIZES: [&str; 8] = [
+        "scalable", "512x512", "256x256", "128x128", "96x96", "64x64", "48x48", "32x32",
+    ];
+    const EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+    let data_dirs = xdg_data_dirs();
+    for data_dir in &data_dirs {
+        for size in SIZES {
+            for ext in EXTENSIONS {
+                let candidate = data_dir
+                    .join("icons/hicolor")
+                    .join(size)
+                    .join("apps")
+                    .join(format!("{}.{}", icon, ext));
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+    for data_dir in &data_dirs {
+        for ext in EXTENSIONS {
+            let candidate = data_dir.join("pixmaps").join(format!("{}.{}", icon, ext));
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = r#"
+[Desktop Entry]
+Version=1.0
+Type=Application
+Name=Firefox Web Browser
+Name[zh_CN]=Firefox 网络浏览器
+GenericName=Web Browser
+GenericName[zh_CN]=网络浏览器
+Keywords=Internet;WWW;Browser;Web\;Explorer;
+Exec=firefox %u
+Icon=firefox
+OnlyShowIn=GNOME;KDE;
+
+[Desktop Action new-window]
+Name=Open a New Window
+Exec=firefox --new-window %u
+"#;
+
+    #[test]
+    fn test_parse_localized_entry() {
+        let entry = parse_desktop_entry(FIREFOX, "zh_CN.UTF-8").unwrap();
+        assert_eq!(entry.name, "Firefox 网络浏览器");
+        assert_eq!(entry.default_name, "Firefox Web Browser");
+        assert_eq!(entry.generic_name, "网络浏览器");
+        assert_eq!(
+            entry.keywords,
+            vec!["Internet", "WWW", "Browser", "Web;Explorer"]
+        );
+        assert_eq!(entry.exec, "firefox");
+        assert_eq!(entry.icon, "firefox");
+
+        let entry = parse_desktop_entry(FIREFOX, "en_US.UTF-8").unwrap();
+        assert_eq!(entry.name, "Firefox Web Browser");
+        assert!(entry.default_name.is_empty());
+    }
+
+    #[test]
+    fn test_should_show() {
+        let entry = parse_desktop_entry(FIREFOX, "").unwrap();
+        assert!(entry.should_show(&["GNOME".to_string()]));
+        assert!(!entry.should_show(&["XFCE".to_string()]));
+
+        let hidden = parse_desktop_entry(
+            "[Desktop Entry]\nType=Application\nName=A\nExec=a\nNoDisplay=true\n",
+            "",
+        )
+        .unwrap();
+        assert!(!hidden.should_show(&[]));
+
+        let missing = parse_desktop_entry(
+            "[Desktop Entry]\nType=Application\nName=A\nExec=a\nTryExec=/nonexistent/zerolaunch\n",
+            "",
+        )
+        .unwrap();
+        assert!(!missing.should_show(&[]));
+    }
+
+    #[test]
+    fn test_strip_field_codes() {
+        assert_eq!(
+            strip_field_codes("code --new-window %F"),
+            "code --new-window"
+        );
+        assert_eq!(strip_field_codes("printf 100%% %i %c %k"), "printf 100%");
+        assert!(parse_desktop_entry("[Desktop Entry]\nType=Link\nName=A\n", "").is_none());
+    }
+}
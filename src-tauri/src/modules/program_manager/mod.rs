@@ -1,41 +1,73 @@
+pub mod bookmark;
 pub mod config;
+pub mod desktop_entry;
 pub mod image_loader;
 pub mod localization_translation;
+pub mod pe_version;
 pub mod pinyin_mapper;
 pub mod program_launcher;
 pub mod program_loader;
 pub mod program_ranker;
+pub mod program_snapshot;
+pub mod program_source;
+pub mod program_watcher;
+pub mod query_parser;
+pub mod score_normalizer;
+pub mod search_eval;
 pub mod search_model;
 pub mod semantic_backend;
 pub mod semantic_manager;
 use crate::program_manager::search_engine::TraditionalSearchEngine;
 pub mod search_engine;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod unit;
+pub mod usage_importer;
+pub mod weight_fitter;
 pub mod window_activator;
 use crate::core::image_processor::ImageProcessor;
-use crate::error::{OptionExt, ResultExt};
+use crate::core::storage::windows_utils::{get_desktop_path, get_start_menu_paths};
+use crate::error::{AppResult, OptionExt, ResultExt};
 use crate::modules::program_manager::config::program_manager_config::RuntimeProgramConfig;
-use crate::modules::program_manager::search_engine::{SearchEngine, SemanticSearchEngine};
+use crate::modules::program_manager::search_engine::{
+    HybridSearchEngine, SearchEngine, SemanticSearchEngine,
+};
 use crate::program_manager::config::program_manager_config::ProgramManagerConfig;
+use crate::program_manager::program_source::ProgramSourceStat;
 use crate::program_manager::search_model::*;
 use crate::program_manager::semantic_manager::SemanticManager;
 use crate::program_manager::unit::*;
+use config::image_loader_config::RuntimeImageLoaderConfig;
 use config::program_manager_config::PartialProgramManagerConfig;
+use config::program_ranker_config::{ProgramRankerConfig, QueryPin};
 use dashmap::DashMap;
 use image_loader::ImageLoader;
 use lru::LruCache;
 use program_launcher::ProgramLauncher;
-use program_loader::ProgramLoader;
-use program_ranker::ProgramRanker;
-use std::collections::HashMap;
+use program_loader::{ProgramLoader, RootScanStat};
+use program_ranker::{
+    normalize_affinity_query, ProgramRanker, RankerGcReport, RankerScoreComponents,
+    UnverifiedPrograms,
+};
+use program_watcher::{ProgramWatcher, WatchBatch};
+use query_parser::{parse_query, QueryFilters};
+use rayon::prelude::*;
+use score_normalizer::{ScoreNormalization, NO_MATCH_SCORE};
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock, RwLockWriteGuard};
 use tracing::{info, warn};
 pub use unit::{
-    EmbeddingVec, LaunchMethod, LaunchMethodKind, Program, SearchTestResult, SemanticStoreItem,
+    EmbeddingVec, HiddenProgramInfo, LaunchMethod, LaunchMethodKind, MatchHighlight, Program,
+    ProgramRegistryDiff, SearchBenchmarkItem, SearchTestReport, SearchTestResult,
+    SemanticStoreItem,
 };
+use usage_importer::{read_usage_file, UsageImportReport, UsageImportSource, UsageMatcher};
+use weight_fitter::{fit_weights, FittingSample, WeightFittingReport};
 use window_activator::WindowActivator;
 
 /// 语义搜索回退原因（用于 command 层决定提示内容）
@@ -47,7 +79,29 @@ pub enum FallbackReason {
 }
 
 /// 短期搜索结果缓存（统一，基于 LruCache）
-type ShortTermSearchResultsCache = Arc<RwLock<Option<LruCache<String, Vec<SearchMatchResult>>>>>;
+type ShortTermSearchResultsCache = Arc<RwLock<Option<LruCache<String, CachedSearch>>>>;
+
+/// 短期缓存中的一次搜索
+#[derive(Debug, Clone)]
+struct CachedSearch {
+    /// 排序并截断后的结果
+    results: Vec<SearchMatchResult>,
+    /// 这次搜索建立的会话，缓存命中时恢复，使之后的逐字输入依然可以缩小搜索范围
+    session: Option<SearchSession>,
+}
+
+/// 上一次搜索的会话信息，用于逐字输入时缩小搜索范围
+#[derive(Debug, Clone)]
+struct SearchSession {
+    /// 上一次交给评分器的查询文本
+    query: String,
+    /// 上一次查询的过滤条件，过滤条件不同时会话无效
+    filters: QueryFilters,
+    /// 上一次查询中匹配到的程序
+    hit_programs: Vec<u64>,
+    /// 会话建立时的搜索代数，注册表或排序器变化后会失效
+    generation: u64,
+}
 
 /// 程序管理器 - 使用细粒度锁优化并发性能
 #[derive(Debug)]
@@ -62,6 +116,8 @@ pub struct ProgramManager {
     program_launcher: Arc<ProgramLauncher>,
     /// 程序排序器
     program_ranker: Arc<ProgramRanker>,
+    /// 索引目录的文件监听器
+    program_watcher: Arc<ProgramWatcher>,
     /// 当前程序的搜索引擎
     search_engine: Arc<RwLock<Arc<dyn SearchEngine>>>,
     /// 图标获取器
@@ -74,6 +130,16 @@ pub struct ProgramManager {
     short_term_result_cache: ShortTermSearchResultsCache,
     /// 当前回退原因
     fallback_reason: Arc<RwLock<FallbackReason>>,
+    /// 上一次搜索的会话
+    search_session: Arc<RwLock<Option<SearchSession>>>,
+    /// 搜索代数：注册表或排序器状态变化时递增，使搜索会话失效
+    search_generation: Arc<AtomicU64>,
+    /// 查询语法中 `in:` 可以使用的位置别名 (别名) => ([目录])
+    query_locations: Arc<HashMap<String, Vec<String>>>,
+    /// 基础分数的归一化方式
+    score_normalization: Arc<RwLock<ScoreNormalization>>,
+    /// 重新加载程序时持有，后台扫描与设置变化引起的刷新依次进行，不会交错地修改加载器与注册表
+    reload_lock: Arc<Mutex<()>>,
 }
 
 /// 内部搜索结果，包含分数和程序ID
@@ -81,6 +147,31 @@ pub struct ProgramManager {
 pub(crate) struct SearchMatchResult {
     score: f64,
     program_guid: u64,
+    /// 搜索模型给出的基础分数（归一化、固定偏移量与智能排序之前）
+    base_score: f64,
+    /// 命中的关键字与字符位置，只有最终返回的结果才会计算
+    keyword_match: Option<KeywordMatch>,
+    /// 基础分数是否表示匹配（用于逐字输入时缩小搜索范围）
+    is_hit: bool,
+    /// 归一化后的基础分数，与下面的加分一起在搜索时记录，用于解释排序结果
+    normalized_score: f64,
+    /// 智能排序的各项加分（已乘以权重）
+    ranker_components: RankerScoreComponents,
+}
+
+impl SearchMatchResult {
+    /// 使用搜索模型给出的基础分数创建一个搜索结果
+    pub(crate) fn new(program_guid: u64, base_score: f64, is_hit: bool) -> Self {
+        SearchMatchResult {
+            score: base_score,
+            program_guid,
+            base_score,
+            keyword_match: None,
+            is_hit,
+            normalized_score: base_score,
+            ranker_components: RankerScoreComponents::default(),
+        }
+    }
 }
 
 impl ProgramManager {
@@ -98,6 +189,7 @@ impl ProgramManager {
             program_loader: Arc::new(ProgramLoader::new(semantic_manager.clone())),
             program_launcher: Arc::new(ProgramLauncher::new()),
             program_ranker: Arc::new(ProgramRanker::new()),
+            program_watcher: Arc::new(ProgramWatcher::default()),
             search_engine: Arc::new(RwLock::new(Arc::new(TraditionalSearchEngine::default()))),
             image_loader: Arc::new(ImageLoader::new(image_loader_config)),
             program_locater: Arc::new(DashMap::new()),
@@ -105,6 +197,11 @@ impl ProgramManager {
             semantic_manager,
             short_term_result_cache: Arc::new(RwLock::new(None)),
             fallback_reason: Arc::new(RwLock::new(FallbackReason::None)),
+            search_session: Arc::new(RwLock::new(None)),
+            search_generation: Arc::new(AtomicU64::new(0)),
+            query_locations: Arc::new(Self::build_query_locations()),
+            score_normalization: Arc::new(RwLock::new(ScoreNormalization::default())),
+            reload_lock: Arc::new(Mutex::new(())),
         };
         if pm
             .semantic_manager
@@ -114,6 +211,64 @@ impl ProgramManager {
         }
         pm
     }
+
+    /// 使用给定的程序创建一个不扫描磁盘的程序管理器，用于离线评估与测试
+    pub(crate) fn with_programs(
+        programs: Vec<Arc<Program>>,
+        search_engine: Arc<dyn SearchEngine>,
+        ranker_config: &ProgramRankerConfig,
+        score_normalization: ScoreNormalization,
+    ) -> Self {
+        let manager = ProgramManager::new(RuntimeProgramConfig {
+            image_loader_config: RuntimeImageLoaderConfig {
+                default_app_icon_path: String::new(),
+                default_web_icon_path: String::new(),
+            },
+            embedding_backend: None,
+            embedding_cache_bytes: None,
+        });
+        {
+            let mut program_registry = manager
+                .program_registry
+                .try_write()
+                .expect_programming("新建的程序管理器不应该被锁住");
+            let (_, added, _) = Self::patch_program_registry(
+                &mut program_registry,
+                &manager.program_locater,
+                programs,
+                |_| false,
+            );
+            manager.program_ranker.load_and_update_programs(
+                ranker_config,
+                &added,
+                &[],
+                &UnverifiedPrograms::default(),
+            );
+            search_engine.on_registry_changed(&program_registry);
+        }
+        *manager
+            .search_engine
+            .try_write()
+            .expect_programming("新建的程序管理器不应该被锁住") = search_engine;
+        *manager
+            .score_normalization
+            .try_write()
+            .expect_programming("新建的程序管理器不应该被锁住") = score_normalization;
+        manager
+    }
+
+    /// 查询语法中 `in:` 可以使用的位置别名
+    fn build_query_locations() -> HashMap<String, Vec<String>> {
+        let mut locations = HashMap::new();
+        if let Ok(desktop_path) = get_desktop_path() {
+            locations.insert("desktop".to_string(), vec![desktop_path]);
+        }
+        if let Ok((common, user)) = get_start_menu_paths() {
+            locations.insert("startmenu".to_string(), vec![common, user]);
+        }
+        locations
+    }
+
     pub async fn get_runtime_data(&self) -> ProgramManagerRuntimeData {
         // 这里我认为，semantic_store 是一个 HashMap<String, SemanticStoreItem>，而SemanticStoreItem是一个内部的类，它最好不要被外部的信息所接触
         // 所以由ProgramManager来管理其实例化
@@ -133,6 +288,7 @@ impl ProgramManager {
                 loader: None,
                 image_loader: None,
                 search_model: None,
+                score_normalization: None,
                 enable_lru_search_cache: None,
                 search_cache_capacity: None,
             },
@@ -141,11 +297,15 @@ impl ProgramManager {
     }
 
     /// 使用配置信息初始化自身与子模块
+    ///
+    /// 程序注册表以启动方式为键进行增量更新，返回本次更新的变化摘要。
+    /// 同一时间只会进行一次加载，正在加载时再次调用会等待前一次完成后再使用新的配置加载
     pub async fn load_from_config(
         &self,
         config: Arc<ProgramManagerConfig>,
         semantic_store: Option<String>,
-    ) {
+    ) -> ProgramRegistryDiff {
+        let _reload_guard = self.reload_lock.lock().await;
         let program_loader_config = &config.get_loader_config();
         let program_ranker_config = &config.get_ranker_config();
         let image_loader_config = &config.get_image_loader_config();
@@ -187,25 +347,68 @@ impl ProgramManager {
 
         self.semantic_manager.update_semantic_store(semantic_store);
 
-        // 清空并更新程序注册表
-        let mut program_registry = self.program_registry.write().await;
-        program_registry.clear();
-        *program_registry = new_programs;
+        // 被隐藏的程序与没有扫描完整的根目录中的程序不在这次的结果中并不代表已经被卸载了
+        let root_scan_stats = self.program_loader.get_root_scan_stats();
+        let unverified = UnverifiedPrograms {
+            launch_methods: program_loader_config
+                .get_hidden_programs()
+                .into_keys()
+                .collect(),
+            roots: root_scan_stats
+                .iter()
+                .filter(|stat| !stat.is_complete())
+                .map(|stat| stat.root_path.clone())
+                .collect(),
+        };
+        // 超出预算的根目录中上一次扫描到的程序继续保留在注册表中
+        let truncated_roots = UnverifiedPrograms {
+            roots: root_scan_stats
+                .iter()
+                .filter(|stat| stat.is_truncated())
+                .map(|stat| stat.root_path.clone())
+                .collect(),
+            ..Default::default()
+        };
+        if !truncated_roots.roots.is_empty() {
+            info!(
+                "这些根目录没有扫描完，保留上一次扫描的程序: {:?}",
+                truncated_roots.roots
+            );
+        }
 
-        let programs_to_register: Vec<(u64, LaunchMethod)> = program_registry
-            .iter()
-            .map(|program| (program.program_guid, program.launch_method.clone()))
-            .collect();
+        // 增量更新程序注册表与定位器
+        let mut program_registry = self.program_registry.write().await;
+        let (registry_diff, added_programs, removed_programs) = Self::patch_program_registry(
+            &mut program_registry,
+            &self.program_locater,
+            new_programs,
+            |launch_method| {
+                let method_text = launch_method.get_text();
+                !unverified.launch_methods.contains(&method_text)
+                    && truncated_roots.is_in_roots(&method_text)
+            },
+        );
+        drop(program_registry);
+        self.search_generation.fetch_add(1, Ordering::SeqCst);
 
-        // 加载配置和注册程序到 Ranker
-        self.program_ranker
-            .load_and_register_programs(program_ranker_config, &programs_to_register);
+        // 加载配置并增量注册程序到 Ranker
+        self.program_ranker.load_and_update_programs(
+            program_ranker_config,
+            &added_programs,
+            &removed_programs,
+            &unverified,
+        );
+        info!(
+            "程序注册表增量更新完成: 新增 {}，移除 {}，变化 {}，未变 {}，保留 {}",
+            registry_diff.added,
+            registry_diff.removed,
+            registry_diff.changed,
+            registry_diff.unchanged,
+            registry_diff.kept
+        );
 
-        // 更新定位器
-        self.program_locater.clear();
-        for (index, program) in program_registry.iter().enumerate() {
-            self.program_locater.insert(program.program_guid, index);
-        }
+        // 监听索引目录，新安装/删除的程序无需等待下一次刷新
+        self.start_program_watcher();
 
         let is_traditional_search = search_config.is_traditional_search();
         // 语义后端可用性（AI开关+模型权重就绪）
@@ -228,21 +431,43 @@ impl ProgramManager {
         let search_engine: Arc<dyn SearchEngine> =
             if !has_backend || !backend_ready || is_traditional_search {
                 if !is_traditional_search {
-                    search_config = Arc::new(SearchModelConfig::default());
+                    search_config = Arc::new(search_config.traditional_fallback());
                 }
                 let new_search_model = SearchModelFactory::create_scorer(search_config.clone());
                 Arc::new(TraditionalSearchEngine::new(Arc::new(new_search_model)))
+            } else if let SearchModelConfig::Hybrid {
+                fusion,
+                lexical_weight,
+                semantic_weight,
+                ..
+            } = search_config.as_ref()
+            {
+                let lexical_model = SearchModelFactory::create_scorer(Arc::new(
+                    search_config.traditional_fallback(),
+                ));
+                Arc::new(HybridSearchEngine::new(
+                    Arc::new(lexical_model),
+                    self.semantic_manager.clone(),
+                    *fusion,
+                    *lexical_weight,
+                    *semantic_weight,
+                ))
             } else {
                 Arc::new(SemanticSearchEngine::new(self.semantic_manager.clone()))
             };
 
-        let mut search_engine_lock = self.search_engine.write().await;
-        *search_engine_lock = search_engine;
+        // 持有注册表的读锁，保证索引与注册表一致（文件监听线程会先写注册表再更新索引）
+        let program_registry = self.program_registry.read().await;
+        search_engine.on_registry_changed(program_registry.as_ref());
+        *self.search_engine.write().await = search_engine;
+        drop(program_registry);
 
         if has_backend && (is_traditional_search || !backend_ready) {
             self.semantic_manager.release_backend_resources();
         }
 
+        *self.score_normalization.write().await = config.get_score_normalization();
+
         // 根据配置更新短期搜索缓存（启用时刷新实例，禁用时清空）
         let enable_cache = config.is_lru_search_cache_enabled();
         let capacity = config.get_search_cache_capacity();
@@ -255,6 +480,276 @@ impl ProgramManager {
         } else {
             *cache_guard = None;
         }
+
+        registry_diff
+    }
+
+    /// 从快照中恢复程序注册表，让搜索在重新扫描磁盘之前就可以使用
+    ///
+    /// 只在注册表还是空的时候（即启动时）生效，使用传统搜索模型，之后的 `load_from_config` 会增量更新注册表并切换到配置的搜索引擎。
+    /// 返回恢复的程序数量
+    pub async fn load_from_snapshot(
+        &self,
+        config: Arc<ProgramManagerConfig>,
+        bytes: &[u8],
+    ) -> usize {
+        let Some(snapshot_programs) = program_snapshot::decode_snapshot(bytes) else {
+            warn!("程序快照无法解析或版本不一致，忽略");
+            return 0;
+        };
+
+        let _reload_guard = self.reload_lock.lock().await;
+        let mut program_registry = self.program_registry.write().await;
+        if !program_registry.is_empty() {
+            return 0;
+        }
+        self.program_loader
+            .load_from_config(&config.get_loader_config());
+        let programs = self.program_loader.restore_programs(snapshot_programs);
+        let (registry_diff, added_programs, _) = Self::patch_program_registry(
+            &mut program_registry,
+            &self.program_locater,
+            programs,
+            |_| false,
+        );
+        self.search_generation.fetch_add(1, Ordering::SeqCst);
+
+        self.program_ranker
+            .load_and_register_programs(&config.get_ranker_config(), &added_programs);
+
+        let search_config = config.get_search_model_config();
+        let search_config = if search_config.is_traditional_search() {
+            search_config
+        } else {
+            Arc::new(search_config.traditional_fallback())
+        };
+        let search_engine: Arc<dyn SearchEngine> = Arc::new(TraditionalSearchEngine::new(
+            Arc::new(SearchModelFactory::create_scorer(search_config)),
+        ));
+        search_engine.on_registry_changed(program_registry.as_ref());
+        *self.search_engine.write().await = search_engine;
+        drop(program_registry);
+
+        *self.score_normalization.write().await = config.get_score_normalization();
+        info!("已从快照恢复 {} 个程序", registry_diff.added);
+        registry_diff.added
+    }
+
+    /// 把当前的程序注册表编码为快照
+    pub async fn export_program_snapshot(&self) -> Vec<u8> {
+        let program_registry = self.program_registry.read().await;
+        program_snapshot::encode_snapshot(program_registry.as_ref())
+    }
+
+    /// 以启动方式为键，将新加载的程序增量合并到注册表中
+    ///
+    /// - 未变化的程序保留原有的实例与guid
+    /// - 内容变化的程序使用新内容，但沿用原有的guid
+    /// - 不再存在的程序被移除，新出现的程序被追加到末尾
+    /// - 没有加载出来但 `keep_absent` 返回 true 的程序（所在的根目录没有扫描完）保持不变
+    ///
+    /// 返回值：(变化摘要, 新增的程序, 被移除的程序guid)
+    fn patch_program_registry(
+        program_registry: &mut Vec<Arc<Program>>,
+        program_locater: &DashMap<u64, usize>,
+        new_programs: Vec<Arc<Program>>,
+        keep_absent: impl Fn(&LaunchMethod) -> bool,
+    ) -> (ProgramRegistryDiff, Vec<(u64, LaunchMethod)>, Vec<u64>) {
+        let mut diff = ProgramRegistryDiff::default();
+        let mut added_programs = Vec::new();
+        let mut removed_programs = Vec::new();
+
+        // 启动方式 -> 在 new_programs 中的下标
+        let mut new_program_index: HashMap<&LaunchMethod, usize> = HashMap::new();
+        for (index, program) in new_programs.iter().enumerate() {
+            new_program_index
+                .entry(&program.launch_method)
+                .or_insert(index);
+        }
+        let mut consumed = vec![false; new_programs.len()];
+
+        // 倒序遍历，这样 swap_remove 换过来的元素一定已经处理过了
+        for index in (0..program_registry.len()).rev() {
+            let old_program = program_registry[index].clone();
+            match new_program_index.get(&old_program.launch_method) {
+                Some(&new_index) => {
+                    consumed[new_index] = true;
+                    let new_program = &new_programs[new_index];
+                    if old_program.has_same_content(new_program) {
+                        diff.unchanged += 1;
+                    } else {
+                        let mut updated = (**new_program).clone();
+                        updated.program_guid = old_program.program_guid;
+                        program_registry[index] = Arc::new(updated);
+                        diff.changed += 1;
+                    }
+                }
+                None if keep_absent(&old_program.launch_method) => {
+                    diff.kept += 1;
+                }
+                None => {
+                    Self::remove_program_at(program_registry, program_locater, index);
+                    removed_programs.push(old_program.program_guid);
+                    diff.removed += 1;
+                }
+            }
+        }
+
+        for (new_index, program) in new_programs.into_iter().enumerate() {
+            if consumed[new_index] {
+                continue;
+            }
+            added_programs.push((program.program_guid, program.launch_method.clone()));
+            Self::push_program(program_registry, program_locater, program);
+            diff.added += 1;
+        }
+
+        (diff, added_programs, removed_programs)
+    }
+
+    /// 从注册表中移除指定下标的程序，并维护定位器
+    fn remove_program_at(
+        program_registry: &mut Vec<Arc<Program>>,
+        program_locater: &DashMap<u64, usize>,
+        index: usize,
+    ) -> Arc<Program> {
+        let program = program_registry.swap_remove(index);
+        program_locater.remove(&program.program_guid);
+        if let Some(moved) = program_registry.get(index) {
+            program_locater.insert(moved.program_guid, index);
+        }
+        program
+    }
+
+    /// 向注册表末尾追加一个程序，并维护定位器
+    fn push_program(
+        program_registry: &mut Vec<Arc<Program>>,
+        program_locater: &DashMap<u64, usize>,
+        program: Arc<Program>,
+    ) {
+        program_locater.insert(program.program_guid, program_registry.len());
+        program_registry.push(program);
+    }
+
+    /// 按当前的索引目录（重新）启动文件监听器
+    fn start_program_watcher(&self) {
+        let roots = self.program_loader.get_target_roots();
+        let program_registry = self.program_registry.clone();
+        let program_locater = self.program_locater.clone();
+        let program_loader = self.program_loader.clone();
+        let program_ranker = self.program_ranker.clone();
+        let search_engine = self.search_engine.clone();
+        let short_term_result_cache = self.short_term_result_cache.clone();
+        let search_generation = self.search_generation.clone();
+        let reload_lock = self.reload_lock.clone();
+        self.program_watcher.watch(&roots, move |batch| {
+            // 与重新加载程序一样持有 `reload_lock`，不会与后台扫描交错地修改加载器与注册表
+            let _reload_guard = reload_lock.blocking_lock();
+            if Self::apply_watch_batch(
+                &program_registry,
+                &program_locater,
+                &program_loader,
+                &program_ranker,
+                &search_engine,
+                &short_term_result_cache,
+                batch,
+            ) {
+                search_generation.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+    }
+
+    /// 将文件监听器报告的变化应用到注册表（在监听线程中执行，调用者需要持有 `reload_lock`）
+    ///
+    /// 返回值：注册表是否发生了变化
+    fn apply_watch_batch(
+        program_registry: &RwLock<Vec<Arc<Program>>>,
+        program_locater: &DashMap<u64, usize>,
+        program_loader: &ProgramLoader,
+        program_ranker: &ProgramRanker,
+        search_engine: &RwLock<Arc<dyn SearchEngine>>,
+        short_term_result_cache: &ShortTermSearchResultsCache,
+        batch: WatchBatch,
+    ) -> bool {
+        if batch.is_empty() {
+            return false;
+        }
+        let is_removed = |path: &Path| batch.removed.iter().any(|item| path.starts_with(item));
+        let is_changed = |path: &Path| batch.changed.iter().any(|item| path.starts_with(item));
+
+        // 忘记被删除（包括被删除的文件夹中）与被修改的已注册文件的名字：
+        // 被删除的之后可以被重新添加，被修改的这一次就会被重新加载
+        let forgotten_paths: Vec<PathBuf> = program_registry
+            .blocking_read()
+            .iter()
+            .filter_map(|program| match &program.launch_method {
+                LaunchMethod::Path(path) | LaunchMethod::File(path) => {
+                    let path = Path::new(path);
+                    (is_removed(path) || is_changed(path)).then(|| path.to_path_buf())
+                }
+                _ => None,
+            })
+            .collect();
+        program_loader.forget_program_paths(&forgotten_paths);
+        let new_programs = program_loader.load_program_from_changed_paths(&batch.changed);
+
+        let mut registry = program_registry.blocking_write();
+
+        // 被删除的文件（或被删除的文件夹中的文件）
+        let mut removed_programs = Vec::new();
+        for index in (0..registry.len()).rev() {
+            let removed = match &registry[index].launch_method {
+                LaunchMethod::Path(path) | LaunchMethod::File(path) => is_removed(Path::new(path)),
+                _ => false,
+            };
+            if removed {
+                let program = Self::remove_program_at(&mut registry, program_locater, index);
+                removed_programs.push(program.program_guid);
+            }
+        }
+
+        // 新出现的文件，以及内容发生变化的已注册文件（沿用原有的guid）
+        let mut added_programs = Vec::new();
+        let mut changed_count = 0;
+        for program in new_programs {
+            match registry
+                .iter()
+                .position(|item| item.launch_method == program.launch_method)
+            {
+                Some(index) => {
+                    if registry[index].has_same_content(&program) {
+                        continue;
+                    }
+                    let mut updated = (*program).clone();
+                    updated.program_guid = registry[index].program_guid;
+                    registry[index] = Arc::new(updated);
+                    changed_count += 1;
+                }
+                None => {
+                    added_programs.push((program.program_guid, program.launch_method.clone()));
+                    Self::push_program(&mut registry, program_locater, program);
+                }
+            }
+        }
+
+        if added_programs.is_empty() && removed_programs.is_empty() && changed_count == 0 {
+            return false;
+        }
+        search_engine.blocking_read().on_registry_changed(&registry);
+        drop(registry);
+        program_ranker.update_programs(&added_programs, &removed_programs);
+
+        // 缓存中的结果可能引用了已被移除的程序
+        if let Some(cache) = short_term_result_cache.blocking_write().as_mut() {
+            cache.clear();
+        }
+        info!(
+            "根据文件变化更新程序: 新增 {}，移除 {}，变化 {}",
+            added_programs.len(),
+            removed_programs.len(),
+            changed_count
+        );
+        true
     }
 
     /// 获取当前回退原因
@@ -304,26 +799,80 @@ impl ProgramManager {
     /// 使用搜索算法搜索，并给出指定长度的序列
     /// user_input: 用户输入的字符串
     /// result_count: 返回的结果，这个值与 `config.show_item_count` 的值保持一致
-    /// 返回值：Vec(应用唯一标识符，展示给用户的名字)
-    pub async fn update(&self, user_input: &str, result_count: u32) -> Vec<(u64, String)> {
+    /// 返回值：Vec(应用唯一标识符，展示给用户的名字，高亮信息)
+    pub async fn update(
+        &self,
+        user_input: &str,
+        result_count: u32,
+    ) -> Vec<(u64, String, MatchHighlight)> {
         // 使用核心搜索算法
         let match_results = self.perform_search(user_input, result_count).await;
         // 转换为所需的输出格式
         let program_registry = self.program_registry.read().await;
-        let mut result: Vec<(u64, String)> = Vec::new();
+        let mut result: Vec<(u64, String, MatchHighlight)> = Vec::new();
+
+        // 搜索框为空时，把根据上一个启动的程序建议的程序排在最前面
+        let suggested = if user_input.trim().is_empty() {
+            self.program_ranker
+                .get_suggested_next_programs(result_count as usize)
+        } else {
+            Vec::new()
+        };
+        for guid in &suggested {
+            if let Some(index) = self.program_locater.get(guid) {
+                let program = &program_registry[*index];
+                result.push((
+                    program.program_guid,
+                    program.show_name.clone(),
+                    MatchHighlight::default(),
+                ));
+            }
+        }
+
         for match_result in match_results {
+            if result.len() >= result_count as usize {
+                break;
+            }
+            if suggested.contains(&match_result.program_guid) {
+                continue;
+            }
             let index = *self
                 .program_locater
                 .get(&match_result.program_guid)
                 .expect_programming("程序定位器中未找到程序GUID");
             let program = &program_registry[index];
-            result.push((program.program_guid, program.show_name.clone()));
+            let highlight = self.build_highlight(program, match_result.keyword_match.as_ref());
+            result.push((program.program_guid, program.show_name.clone(), highlight));
         }
         result
     }
 
+    /// 将命中的关键字映射为 `show_name` 上的高亮区间
+    fn build_highlight(
+        &self,
+        program: &Program,
+        keyword_match: Option<&KeywordMatch>,
+    ) -> MatchHighlight {
+        let Some(keyword_match) = keyword_match else {
+            return MatchHighlight::default();
+        };
+        let Some(keyword) = program.search_keywords.get(keyword_match.keyword_index) else {
+            return MatchHighlight::default();
+        };
+        MatchHighlight {
+            keyword: keyword.clone(),
+            ranges: self.program_loader.map_match_to_show_name(
+                &program.show_name,
+                keyword,
+                &keyword_match.indices,
+            ),
+            keyword_ranges: indices_to_ranges(keyword_match.indices.clone()),
+        }
+    }
+
     /// 测试算法
-    pub async fn test_search_algorithm(&self, user_input: &str) -> Vec<SearchTestResult> {
+    pub async fn test_search_algorithm(&self, user_input: &str) -> SearchTestReport {
+        let parsed_query = parse_query(&remove_repeated_space(&user_input.to_lowercase()));
         // 使用核心搜索算法
         let total_size = self.get_program_count().await;
         let match_results = self.perform_search(user_input, total_size as u32).await;
@@ -331,21 +880,73 @@ impl ProgramManager {
         // 转换为详细的测试结果格式
         let mut results: Vec<SearchTestResult> = Vec::new();
         let program_registry = self.program_registry.read().await;
+        let search_engine = self.search_engine.read().await;
         for match_result in match_results {
             let index = *self
                 .program_locater
                 .get(&match_result.program_guid)
                 .expect_programming("程序定位器中未找到程序GUID");
             let program = &program_registry[index];
+            let breakdown = self.explain_score(
+                search_engine.as_ref(),
+                program,
+                &match_result,
+                &parsed_query.text,
+            );
             results.push(SearchTestResult {
                 program_name: program.show_name.clone(),
                 program_keywords: program.search_keywords.join(", "),
                 program_path: program.launch_method.get_text(),
                 score: match_result.score,
+                breakdown,
             });
         }
 
-        results
+        SearchTestReport {
+            query_text: parsed_query.text,
+            filters: parsed_query.filters.describe(),
+            results,
+        }
+    }
+
+    /// 拆解一个搜索结果的得分：每个关键字的得分、固定偏移量、归一化与智能排序的各项加分
+    ///
+    /// 归一化后的分数与智能排序的加分使用搜索时记录下来的值，与结果的排序完全一致
+    fn explain_score(
+        &self,
+        search_engine: &dyn SearchEngine,
+        program: &Arc<Program>,
+        match_result: &SearchMatchResult,
+        query_text: &str,
+    ) -> ScoreBreakdown {
+        let keyword_scores: Vec<KeywordScore> = program
+            .search_keywords
+            .iter()
+            .zip(search_engine.keyword_scores(query_text, program))
+            .map(|(keyword, score)| KeywordScore {
+                keyword: keyword.clone(),
+                score,
+            })
+            .collect();
+        // 与搜索模型一致：分数相同时取靠前的关键字
+        let mut winning: Option<(&KeywordScore, f64)> = None;
+        for item in &keyword_scores {
+            if let Some(score) = item.score {
+                if winning.is_none_or(|(_, best_score)| score > best_score) {
+                    winning = Some((item, score));
+                }
+            }
+        }
+        let winning_keyword = winning.map(|(item, _)| item.keyword.clone());
+
+        ScoreBreakdown {
+            keyword_scores,
+            winning_keyword,
+            stable_bias: program.stable_bias,
+            base_score: match_result.base_score,
+            normalized_score: match_result.normalized_score,
+            ranker: match_result.ranker_components,
+        }
     }
 
     /// 获取当前程序维护的东西
@@ -366,8 +967,247 @@ impl ProgramManager {
     }
 
     /// 记录查询-程序启动关联
+    ///
+    /// 只记录交给评分器的文本，查询语法中的过滤条件不参与亲和度的计算
     pub fn record_query_launch(&self, query: &str, program_guid: u64) {
-        self.program_ranker.record_query_launch(query, program_guid);
+        let parsed_query = parse_query(&remove_repeated_space(&query.to_lowercase()));
+        self.program_ranker
+            .record_query_launch(&parsed_query.text, program_guid);
+        self.search_generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// 为查询构建一个固定项，查询语法中的过滤条件不参与匹配
+    pub async fn build_query_pin(
+        &self,
+        program_guid: u64,
+        query: &str,
+        is_prefix: bool,
+    ) -> Option<QueryPin> {
+        let program = self.get_program_by_guid(program_guid).await?;
+        let parsed_query = parse_query(&remove_repeated_space(&query.to_lowercase()));
+        let query = normalize_affinity_query(&parsed_query.text);
+        if query.is_empty() {
+            return None;
+        }
+        Some(QueryPin {
+            query,
+            is_prefix,
+            launch_method: program.launch_method.get_text(),
+        })
+    }
+
+    /// 更新固定在查询结果最前面的程序，立即对之后的搜索生效，不需要重新加载程序
+    pub async fn set_query_pins(&self, query_pins: Vec<QueryPin>) {
+        self.program_ranker.set_query_pins(query_pins);
+        if let Some(cache) = self.short_term_result_cache.write().await.as_mut() {
+            cache.clear();
+        }
+    }
+
+    /// 隐藏一个程序，直接从注册表中移除，不需要重新扫描程序
+    ///
+    /// 返回被隐藏的程序，找不到这个程序时返回 `None`
+    pub async fn hide_program(&self, program_guid: u64) -> Option<HiddenProgramInfo> {
+        let program = self.get_program_by_guid(program_guid).await?;
+        let hidden = HiddenProgramInfo {
+            launch_method: program.launch_method.get_text(),
+            show_name: program.show_name.clone(),
+        };
+        self.program_loader
+            .hide_program(hidden.launch_method.clone(), hidden.show_name.clone());
+
+        let mut program_registry = self.program_registry.write().await;
+        let index = self.get_program_index(program_guid)?;
+        Self::remove_program_at(&mut program_registry, &self.program_locater, index);
+        self.finish_registry_update(program_registry, &[], &[program_guid])
+            .await;
+        Some(hidden)
+    }
+
+    /// 取消隐藏一个程序，返回它之前是否被隐藏
+    ///
+    /// 索引目录中的程序会立即重新加载，其他来源的程序在下一次刷新程序时出现
+    pub async fn unhide_program(&self, launch_method: &str) -> bool {
+        if !self.program_loader.unhide_program(launch_method) {
+            return false;
+        }
+        // 隐藏之前加载的程序还占用着名字
+        let path = PathBuf::from(launch_method);
+        self.program_loader
+            .forget_program_paths(std::slice::from_ref(&path));
+        let new_programs = self.program_loader.load_program_from_changed_paths(&[path]);
+
+        let mut program_registry = self.program_registry.write().await;
+        let mut added_programs = Vec::new();
+        for program in new_programs {
+            if program_registry
+                .iter()
+                .any(|item| item.launch_method == program.launch_method)
+            {
+                continue;
+            }
+            added_programs.push((program.program_guid, program.launch_method.clone()));
+            Self::push_program(&mut program_registry, &self.program_locater, program);
+        }
+        if !added_programs.is_empty() {
+            self.finish_registry_update(program_registry, &added_programs, &[])
+                .await;
+        }
+        true
+    }
+
+    /// 获取所有被隐藏的程序，按名字排序
+    pub fn get_hidden_programs(&self) -> Vec<HiddenProgramInfo> {
+        let mut result: Vec<HiddenProgramInfo> = self
+            .program_loader
+            .get_hidden_programs()
+            .into_iter()
+            .map(|(launch_method, show_name)| HiddenProgramInfo {
+                launch_method,
+                show_name,
+            })
+            .collect();
+        result.sort_by(|a, b| {
+            a.show_name
+                .cmp(&b.show_name)
+                .then_with(|| a.launch_method.cmp(&b.launch_method))
+        });
+        result
+    }
+
+    /// 直接修改注册表之后，通知搜索引擎与排序器，并让之前的搜索结果失效
+    async fn finish_registry_update(
+        &self,
+        program_registry: RwLockWriteGuard<'_, Vec<Arc<Program>>>,
+        added_programs: &[(u64, LaunchMethod)],
+        removed_programs: &[u64],
+    ) {
+        self.search_engine
+            .read()
+            .await
+            .on_registry_changed(&program_registry);
+        drop(program_registry);
+        self.program_ranker
+            .update_programs(added_programs, removed_programs);
+        self.search_generation.fetch_add(1, Ordering::SeqCst);
+        if let Some(cache) = self.short_term_result_cache.write().await.as_mut() {
+            cache.clear();
+        }
+    }
+
+    /// 获取固定在这个查询最前面的程序
+    pub fn get_pinned_programs(&self, query: &str) -> Vec<u64> {
+        let parsed_query = parse_query(&remove_repeated_space(&query.to_lowercase()));
+        self.program_ranker.get_pinned_programs(&parsed_query.text)
+    }
+
+    /// 从其他启动器导出的文件中导入使用记录，写入智能排序的历史启动次数与查询亲和度
+    pub async fn import_launcher_usage(
+        &self,
+        source: UsageImportSource,
+        path: &Path,
+    ) -> AppResult<UsageImportReport> {
+        let usages = read_usage_file(source, path)?;
+        let (records, mut report) = {
+            let program_registry = self.program_registry.read().await;
+            UsageMatcher::new(&program_registry).match_usages(&usages)
+        };
+        let (imported_launches, imported_queries) = self
+            .program_ranker
+            .import_launch_records(source.id(), &records);
+        report.imported_launches = imported_launches;
+        report.imported_queries = imported_queries;
+        self.search_generation.fetch_add(1, Ordering::SeqCst);
+        if let Some(cache) = self.short_term_result_cache.write().await.as_mut() {
+            cache.clear();
+        }
+        info!(
+            "导入使用记录: 记录 {}，对应上 {}，启动次数 {}，查询关联 {}",
+            report.record_count,
+            report.matched_count,
+            report.imported_launches,
+            report.imported_queries
+        );
+        Ok(report)
+    }
+
+    /// 获取上一次清理排序统计数据的结果
+    pub fn get_ranker_gc_report(&self) -> RankerGcReport {
+        self.program_ranker.get_last_gc_report()
+    }
+
+    /// 重放所有的查询-启动记录，拟合智能排序的权重
+    ///
+    /// 这里只负责计算，是否写回配置由调用者根据 `applied` 决定
+    pub async fn fit_ranker_weights(&self) -> WeightFittingReport {
+        let records = self.program_ranker.query_launch_records();
+        let program_registry = self.program_registry.read().await;
+        let search_engine = self.search_engine.read().await;
+        let score_normalization = *self.score_normalization.read().await;
+
+        let mut samples = Vec::new();
+        for record in records {
+            let parsed_query = parse_query(&remove_repeated_space(&record.query.to_lowercase()));
+            if parsed_query.text.is_empty() {
+                continue;
+            }
+            let filtered_programs: Vec<Arc<Program>>;
+            let programs = if parsed_query.filters.is_empty() {
+                program_registry.as_ref()
+            } else {
+                filtered_programs = program_registry
+                    .iter()
+                    .filter(|program| parsed_query.filters.matches(program, &self.query_locations))
+                    .cloned()
+                    .collect();
+                &filtered_programs
+            };
+
+            let mut results = search_engine.perform_search(&parsed_query.text, programs);
+            score_normalization.normalize(&mut results);
+            results.retain(|result| result.is_hit);
+            for result in results.iter_mut() {
+                result.score += self.program_stable_bias(&program_registry, result.program_guid);
+            }
+            // 当前的搜索模型已经匹配不到这个程序了，调整权重也无济于事
+            let Some(target) = results
+                .iter()
+                .position(|result| result.program_guid == record.program_guid)
+            else {
+                continue;
+            };
+            let candidates = results
+                .iter()
+                .map(|result| {
+                    // 这条记录的所有启动都是被重放的，不能让它们自己给自己加分
+                    let excluded_launches = if result.program_guid == record.program_guid {
+                        record.launch_count
+                    } else {
+                        0
+                    };
+                    let components = self.program_ranker.calculate_raw_score_components(
+                        result.program_guid,
+                        &parsed_query.text,
+                        excluded_launches,
+                    );
+                    (result.score, components)
+                })
+                .collect();
+            samples.push(FittingSample {
+                weight: record.launch_count as f64,
+                candidates,
+                target,
+            });
+        }
+        drop(search_engine);
+        drop(program_registry);
+
+        let report = fit_weights(&samples, self.program_ranker.get_weights());
+        info!(
+            "拟合排序权重: 样本 {}，MRR {:.3} -> {:.3}，应用: {}",
+            report.sample_count, report.mrr_before, report.mrr_after, report.applied
+        );
+        report
     }
 
     /// 启动一个程序
@@ -379,6 +1219,7 @@ impl ProgramManager {
     ) {
         // 先记录启动统计
         self.program_ranker.record_launch(program_guid);
+        self.search_generation.fetch_add(1, Ordering::SeqCst);
 
         // 获取程序的 launch_method
         let program = self.get_program_by_guid(program_guid).await;
@@ -416,20 +1257,35 @@ impl ProgramManager {
         program_registry.len()
     }
     /// 测试搜索算法的时间开销
-    pub async fn test_search_algorithm_time(&self) -> (f64, f64, f64) {
+    ///
+    /// `benchmark` 为 true 时，额外使用程序名字的前缀作为查询，并报告每次查询的耗时与候选集大小
+    ///
+    /// 返回值：(最大耗时，最小耗时，平均耗时，基准测试的明细)
+    pub async fn test_search_algorithm_time(
+        &self,
+        benchmark: bool,
+    ) -> (f64, f64, f64, Vec<SearchBenchmarkItem>) {
         let mut max_time: f64 = 0.0;
         let mut min_time: f64 = 5000.0;
         let mut average_time: f64 = 0.0;
-        let program_registry = self.program_registry.read().await;
-        let count = (*program_registry)
-            .iter()
-            .flat_map(|program| program.search_keywords.iter())
-            .map(|alias| alias.len())
-            .max()
-            .unwrap_or(0);
+        let (count, benchmark_queries) = {
+            let program_registry = self.program_registry.read().await;
+            let count = (*program_registry)
+                .iter()
+                .flat_map(|program| program.search_keywords.iter())
+                .map(|alias| alias.len())
+                .max()
+                .unwrap_or(0);
+            let benchmark_queries = if benchmark {
+                Self::build_benchmark_queries(&program_registry)
+            } else {
+                Vec::new()
+            };
+            (count, benchmark_queries)
+        };
 
         if count == 0 {
-            return (0.0, 0.0, 0.0);
+            return (0.0, 0.0, 0.0, Vec::new());
         }
 
         for i in 1..=count {
@@ -444,12 +1300,72 @@ impl ProgramManager {
         }
 
         average_time /= count as f64;
-        (max_time, min_time, average_time)
+
+        let mut benchmark_items = Vec::with_capacity(benchmark_queries.len());
+        for query in benchmark_queries {
+            let (candidate_count, program_count) = self.count_search_candidates(&query).await;
+            let start = Instant::now();
+            self.update(&query, 5).await;
+            benchmark_items.push(SearchBenchmarkItem {
+                query,
+                time_ms: start.elapsed().as_secs_f64() * 1000.0,
+                candidate_count,
+                program_count,
+            });
+        }
+        (max_time, min_time, average_time, benchmark_items)
+    }
+
+    /// 从注册表中均匀地选出一些程序，使用它们名字的前缀作为基准测试的查询
+    fn build_benchmark_queries(program_registry: &[Arc<Program>]) -> Vec<String> {
+        const SAMPLE_PROGRAM_COUNT: usize = 20;
+        const MAX_PREFIX_LEN: usize = 6;
+        let step = (program_registry.len() / SAMPLE_PROGRAM_COUNT).max(1);
+        let mut queries: Vec<String> = Vec::new();
+        for program in program_registry
+            .iter()
+            .step_by(step)
+            .take(SAMPLE_PROGRAM_COUNT)
+        {
+            let Some(keyword) = program.search_keywords.first() else {
+                continue;
+            };
+            let chars: Vec<char> = keyword.chars().collect();
+            for len in 1..=chars.len().min(MAX_PREFIX_LEN) {
+                let query: String = chars[..len].iter().collect();
+                if !queries.contains(&query) {
+                    queries.push(query);
+                }
+            }
+        }
+        queries
+    }
+
+    /// 统计一次查询需要评分的程序数量
+    ///
+    /// 返回值：(候选程序数量，程序总数)
+    async fn count_search_candidates(&self, user_input: &str) -> (usize, usize) {
+        let user_input = remove_repeated_space(&user_input.to_lowercase());
+        let program_registry = self.program_registry.read().await;
+        let search_engine = self.search_engine.read().await;
+        let candidate_count = search_engine
+            .select_candidates(&user_input, program_registry.as_ref())
+            .map(|candidates| candidates.len())
+            .unwrap_or(program_registry.len());
+        (candidate_count, program_registry.len())
     }
     /// 获得加载程序的时间开销
     pub async fn get_program_loader_loading_time(&self) -> f64 {
         self.program_loader.get_loading_time()
     }
+    /// 获得每个程序来源在上一次加载时的数量与耗时
+    pub fn get_program_source_stats(&self) -> Vec<ProgramSourceStat> {
+        self.program_loader.get_source_stats()
+    }
+    /// 获得每个索引根目录在上一次扫描时的情况
+    pub fn get_root_scan_stats(&self) -> Vec<RootScanStat> {
+        self.program_loader.get_root_scan_stats()
+    }
     /// 获得搜索关键字
     pub async fn get_search_keywords(&self, show_name: &str) -> Vec<String> {
         self.program_loader.convert_search_keywords(show_name)
@@ -503,28 +1419,148 @@ impl ProgramManager {
         results
     }
 
+    /// 获取注册表中一个程序的固定偏移量
+    fn program_stable_bias(&self, program_registry: &[Arc<Program>], program_guid: u64) -> f64 {
+        let index = *self
+            .program_locater
+            .get(&program_guid)
+            .expect_programming("程序定位器中未找到程序GUID");
+        program_registry[index].stable_bias
+    }
+
     async fn perform_search(&self, user_input: &str, result_count: u32) -> Vec<SearchMatchResult> {
         // 预处理用户输入
         let user_input = user_input.to_lowercase();
         let user_input = remove_repeated_space(&user_input);
 
-        // 统一短期缓存命中直接返回
+        // 统一短期缓存命中直接返回，并恢复这次搜索建立的会话
         if let Some(cached) = {
             let mut cache_guard = self.short_term_result_cache.write().await;
             cache_guard
                 .as_mut()
                 .and_then(|cache| cache.get(&user_input).cloned())
         } {
-            return cached.into_iter().take(result_count as usize).collect();
+            *self.search_session.write().await = cached.session;
+            return cached
+                .results
+                .into_iter()
+                .take(result_count as usize)
+                .collect();
         }
 
         let ranker = &self.program_ranker;
+        // 解析查询语法，过滤条件在评分之前使用
+        let parsed_query = parse_query(&user_input);
+        let query_text = parsed_query.text;
+        let filters = parsed_query.filters;
 
         let program_registry = self.program_registry.read().await;
         let search_engine = self.search_engine.read().await;
+        let no_match_score = search_engine.no_match_score();
+
+        // 如果这次输入是在上一次输入的后面继续输入的，上一次没有匹配到的程序这一次也一定不匹配，不需要再评分
+        let generation = self.search_generation.load(Ordering::SeqCst);
+        let narrowed_guids: Option<HashSet<u64>> = if no_match_score.is_some() {
+            let session = self.search_session.read().await;
+            session
+                .as_ref()
+                .filter(|session| {
+                    session.generation == generation
+                        && session.filters == filters
+                        && query_text.starts_with(&session.query)
+                })
+                .map(|session| session.hit_programs.iter().copied().collect())
+        } else {
+            None
+        };
+        let filtered_programs: Option<Vec<Arc<Program>>> = if filters.is_empty() {
+            None
+        } else {
+            Some(
+                program_registry
+                    .iter()
+                    .filter(|program| filters.matches(program, &self.query_locations))
+                    .cloned()
+                    .collect(),
+            )
+        };
+        let programs = filtered_programs
+            .as_deref()
+            .unwrap_or(program_registry.as_ref());
+
         // 计算所有程序的匹配分数
-        let mut match_scores: Vec<SearchMatchResult> =
-            search_engine.perform_search(&user_input, program_registry.as_ref(), ranker);
+        let mut match_scores: Vec<SearchMatchResult> = match (&narrowed_guids, no_match_score) {
+            (Some(narrowed_guids), Some(no_match_score)) => {
+                let candidates: Vec<Arc<Program>> = programs
+                    .iter()
+                    .filter(|program| narrowed_guids.contains(&program.program_guid))
+                    .cloned()
+                    .collect();
+                let mut scored: HashMap<u64, SearchMatchResult> = search_engine
+                    .perform_search(&query_text, &candidates)
+                    .into_iter()
+                    .map(|result| (result.program_guid, result))
+                    .collect();
+                // 跳过的程序直接使用不匹配的分数，结果与不缩小范围时完全一致（包括顺序）
+                programs
+                    .iter()
+                    .filter_map(|program| {
+                        if narrowed_guids.contains(&program.program_guid) {
+                            scored.remove(&program.program_guid)
+                        } else {
+                            Some(SearchMatchResult::new(
+                                program.program_guid,
+                                no_match_score,
+                                false,
+                            ))
+                        }
+                    })
+                    .collect()
+            }
+            _ => search_engine.perform_search(&query_text, programs),
+        };
+
+        // 先将匹配分数归一化到统一的尺度，再加上固定偏移量与智能排序增强评分
+        self.score_normalization
+            .read()
+            .await
+            .normalize(&mut match_scores);
+        match_scores.par_iter_mut().for_each(|result| {
+            let stable_bias = self.program_stable_bias(&program_registry, result.program_guid);
+            result.normalized_score = result.score;
+            result.ranker_components =
+                ranker.calculate_score_components(result.program_guid, &query_text);
+            result.score += stable_bias + result.ranker_components.total();
+        });
+        // 固定的程序也要满足过滤条件
+        let pinned: Vec<u64> = ranker
+            .get_pinned_programs(&query_text)
+            .into_iter()
+            .filter(|guid| {
+                filters.is_empty()
+                    || self.program_locater.get(guid).is_some_and(|index| {
+                        filters.matches(&program_registry[*index], &self.query_locations)
+                    })
+            })
+            .collect();
+
+        // 记录本次匹配到的程序，供下一次输入使用
+        let session = if no_match_score.is_some() && !query_text.is_empty() {
+            Some(SearchSession {
+                query: query_text.clone(),
+                filters,
+                hit_programs: match_scores
+                    .iter()
+                    .filter(|result| result.is_hit)
+                    .map(|result| result.program_guid)
+                    .collect(),
+                generation,
+            })
+        } else {
+            None
+        };
+        *self.search_session.write().await = session.clone();
+
         // 按分数降序排序
         match_scores.sort_by(|a, b| {
             b.score
@@ -532,14 +1568,603 @@ impl ProgramManager {
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
 
+        // 用户固定的程序按固定的顺序排在最前面，与分数、候选筛选以及是否匹配都无关
+        if !pinned.is_empty() {
+            let mut pinned_results = Vec::with_capacity(pinned.len());
+            for guid in &pinned {
+                if let Some(position) = match_scores
+                    .iter()
+                    .position(|result| result.program_guid == *guid)
+                {
+                    pinned_results.push(match_scores.remove(position));
+                    continue;
+                }
+                // 没有参与这次评分的程序从注册表中取出，按不匹配的程序计算分数
+                let Some(index) = self.program_locater.get(guid).map(|index| *index) else {
+                    continue;
+                };
+                let program = &program_registry[index];
+                let mut result = SearchMatchResult::new(
+                    program.program_guid,
+                    no_match_score.unwrap_or(NO_MATCH_SCORE),
+                    false,
+                );
+                result.ranker_components =
+                    ranker.calculate_score_components(program.program_guid, &query_text);
+                result.score += program.stable_bias + result.ranker_components.total();
+                pinned_results.push(result);
+            }
+            match_scores.splice(0..0, pinned_results);
+        }
+
         // 只保留需要的数量
         match_scores.truncate(result_count as usize);
 
+        // 只为保留下来的结果计算匹配细节
+        for match_result in match_scores.iter_mut() {
+            let index = *self
+                .program_locater
+                .get(&match_result.program_guid)
+                .expect_programming("程序定位器中未找到程序GUID");
+            match_result.keyword_match =
+                search_engine.match_keyword(&query_text, &program_registry[index]);
+        }
+
         // 写入短期缓存
         if let Some(cache) = self.short_term_result_cache.write().await.as_mut() {
-            cache.put(user_input.clone(), match_scores.clone());
+            cache.put(
+                user_input.clone(),
+                CachedSearch {
+                    results: match_scores.clone(),
+                    session,
+                },
+            );
         }
 
         match_scores
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program_manager::config::program_ranker_config::PartialProgramRankerConfig;
+    use crate::program_manager::test_support::test_program;
+
+    fn registry_program(guid: u64, name: &str, path: &str) -> Arc<Program> {
+        Arc::new(Program {
+            launch_method: LaunchMethod::Path(path.to_string()),
+            ..test_program(guid, name, &[&name.to_lowercase()])
+        })
+    }
+
+    /// 使用给定的程序创建一个程序管理器（标准搜索模型），不扫描磁盘
+    fn test_manager(programs: Vec<Arc<Program>>) -> ProgramManager {
+        ProgramManager::with_programs(
+            programs,
+            Arc::new(TraditionalSearchEngine::default()),
+            &ProgramRankerConfig::default(),
+            ScoreNormalization::default(),
+        )
+    }
+
+    /// 结果的顺序与分数，不含随时间变化的智能排序加分
+    fn score_list(results: &[SearchMatchResult]) -> Vec<(u64, f64, bool)> {
+        results
+            .iter()
+            .map(|result| {
+                (
+                    result.program_guid,
+                    result.score - result.ranker_components.total(),
+                    result.is_hit,
+                )
+            })
+            .collect()
+    }
+
+    /// 注册表中每个程序的 guid 都能通过定位器找到它所在的位置
+    fn assert_locater_consistent(registry: &[Arc<Program>], locater: &DashMap<u64, usize>) {
+        assert_eq!(registry.len(), locater.len());
+        for (index, program) in registry.iter().enumerate() {
+            assert_eq!(*locater.get(&program.program_guid).unwrap(), index);
+        }
+    }
+
+    fn guid_of(registry: &[Arc<Program>], path: &str) -> Option<u64> {
+        registry
+            .iter()
+            .find(|program| program.launch_method == LaunchMethod::Path(path.to_string()))
+            .map(|program| program.program_guid)
+    }
+
+    #[test]
+    fn test_patch_program_registry_diff() {
+        let mut registry = Vec::new();
+        let locater = DashMap::new();
+        let initial = vec![
+            registry_program(1, "Alpha", "a.exe"),
+            registry_program(2, "Beta", "b.exe"),
+            registry_program(3, "Gamma", "c.exe"),
+        ];
+        let (diff, added, removed) =
+            ProgramManager::patch_program_registry(&mut registry, &locater, initial, |_| false);
+        assert_eq!(
+            diff,
+            ProgramRegistryDiff {
+                added: 3,
+                ..Default::default()
+            }
+        );
+        assert_eq!(added.len(), 3);
+        assert!(removed.is_empty());
+        assert_locater_consistent(&registry, &locater);
+
+        // 重新扫描得到的程序使用新的 guid：a 被删除，b 未变化，c 改了名字，d 是新安装的
+        let rescanned = vec![
+            registry_program(10, "Beta", "b.exe"),
+            registry_program(11, "Gamma 2", "c.exe"),
+            registry_program(12, "Delta", "d.exe"),
+        ];
+        let (diff, added, removed) =
+            ProgramManager::patch_program_registry(&mut registry, &locater, rescanned, |_| false);
+        assert_eq!(
+            diff,
+            ProgramRegistryDiff {
+                added: 1,
+                removed: 1,
+                changed: 1,
+                unchanged: 1,
+                kept: 0,
+            }
+        );
+        assert_eq!(added, vec![(12, LaunchMethod::Path("d.exe".to_string()))]);
+        assert_eq!(removed, vec![1]);
+        assert_locater_consistent(&registry, &locater);
+
+        // 未变化与内容变化的程序都沿用原有的 guid
+        assert_eq!(guid_of(&registry, "a.exe"), None);
+        assert_eq!(guid_of(&registry, "b.exe"), Some(2));
+        assert_eq!(guid_of(&registry, "c.exe"), Some(3));
+        assert_eq!(guid_of(&registry, "d.exe"), Some(12));
+        let gamma = &registry[*locater.get(&3).unwrap()];
+        assert_eq!(gamma.show_name, "Gamma 2");
+    }
+
+    #[test]
+    fn test_patch_program_registry_keeps_guids_when_unchanged() {
+        let mut registry = Vec::new();
+        let locater = DashMap::new();
+        let programs = |first_guid: u64| {
+            (0..5)
+                .map(|i| {
+                    registry_program(
+                        first_guid + i,
+                        &format!("Program {}", i),
+                        &format!("{}.exe", i),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        ProgramManager::patch_program_registry(&mut registry, &locater, programs(0), |_| false);
+        let guids: Vec<u64> = registry.iter().map(|p| p.program_guid).collect();
+
+        let (diff, added, removed) =
+            ProgramManager::patch_program_registry(&mut registry, &locater, programs(100), |_| {
+                false
+            });
+        assert_eq!(
+            diff,
+            ProgramRegistryDiff {
+                unchanged: 5,
+                ..Default::default()
+            }
+        );
+        assert!(added.is_empty());
+        assert!(removed.is_empty());
+        assert_eq!(
+            registry.iter().map(|p| p.program_guid).collect::<Vec<_>>(),
+            guids
+        );
+        assert_locater_consistent(&registry, &locater);
+    }
+
+    #[test]
+    fn test_patch_program_registry_keeps_programs_in_truncated_roots() {
+        let mut registry = Vec::new();
+        let locater = DashMap::new();
+        let initial = vec![
+            registry_program(1, "Alpha", "C:\\Share\\a.exe"),
+            registry_program(2, "Beta", "C:\\Local\\b.exe"),
+        ];
+        ProgramManager::patch_program_registry(&mut registry, &locater, initial, |_| false);
+
+        // 这次扫描 Share 超出了预算，没有扫描到 a；Local 扫描完整，b 已经被卸载
+        let truncated_roots = UnverifiedPrograms {
+            roots: vec!["C:\\Share".to_string()],
+            ..Default::default()
+        };
+        let (diff, added, removed) =
+            ProgramManager::patch_program_registry(&mut registry, &locater, Vec::new(), |method| {
+                truncated_roots.is_in_roots(&method.get_text())
+            });
+        assert_eq!(
+            diff,
+            ProgramRegistryDiff {
+                removed: 1,
+                kept: 1,
+                ..Default::default()
+            }
+        );
+        assert!(added.is_empty());
+        assert_eq!(removed, vec![2]);
+        assert_eq!(guid_of(&registry, "C:\\Share\\a.exe"), Some(1));
+        assert_locater_consistent(&registry, &locater);
+    }
+
+    #[test]
+    fn test_apply_watch_batch() {
+        use crate::program_manager::config::program_loader_config::DirectoryConfig;
+        use crate::program_manager::pe_version::test_pe::{build_pe, version_resource};
+        use std::fs;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let tools = root.join("Tools");
+        fs::create_dir_all(&tools).unwrap();
+        let write_exe = |path: &Path, description: &str| {
+            let version = version_resource(&[("FileDescription", description)]);
+            fs::write(path, build_pe(&version, true)).unwrap();
+        };
+        write_exe(&tools.join("alpha.exe"), "Alpha Tool");
+        write_exe(&tools.join("beta.exe"), "Beta Tool");
+        write_exe(&root.join("gamma.exe"), "Gamma Tool");
+
+        let semantic_manager = Arc::new(SemanticManager::new(None, HashMap::new()));
+        let program_loader = ProgramLoader::new(semantic_manager);
+        program_loader.add_target_path(DirectoryConfig::new(root.to_string_lossy().to_string(), 3));
+        let program_registry = RwLock::new(Vec::new());
+        let program_locater = DashMap::new();
+        let program_ranker = ProgramRanker::new();
+        let search_engine: RwLock<Arc<dyn SearchEngine>> =
+            RwLock::new(Arc::new(TraditionalSearchEngine::default()));
+        let cache: ShortTermSearchResultsCache = Arc::new(RwLock::new(None));
+        let apply = |changed: Vec<PathBuf>, removed: Vec<PathBuf>| {
+            ProgramManager::apply_watch_batch(
+                &program_registry,
+                &program_locater,
+                &program_loader,
+                &program_ranker,
+                &search_engine,
+                &cache,
+                WatchBatch { changed, removed },
+            )
+        };
+        let find = |path: &Path| {
+            let path = path.to_string_lossy().to_string();
+            program_registry
+                .blocking_read()
+                .iter()
+                .find(|program| program.launch_method == LaunchMethod::Path(path.clone()))
+                .cloned()
+        };
+
+        assert!(apply(vec![tools.clone(), root.join("gamma.exe")], vec![]));
+        assert_eq!(program_registry.blocking_read().len(), 3);
+        let alpha = find(&tools.join("alpha.exe")).unwrap();
+
+        // 已注册的文件被修改：重新加载，guid 不变
+        write_exe(&tools.join("alpha.exe"), "Alpha Studio");
+        assert!(apply(vec![tools.join("alpha.exe")], vec![]));
+        let updated = find(&tools.join("alpha.exe")).unwrap();
+        assert_eq!(updated.program_guid, alpha.program_guid);
+        assert!(updated
+            .search_keywords
+            .iter()
+            .any(|keyword| keyword.contains("studio")));
+        assert_eq!(program_registry.blocking_read().len(), 3);
+        // 没有变化的文件不会触发更新
+        assert!(!apply(vec![root.join("gamma.exe")], vec![]));
+
+        // 整个文件夹被删除：其中所有程序都被移除，名字也都被忘记
+        fs::remove_dir_all(&tools).unwrap();
+        assert!(apply(vec![], vec![tools.clone()]));
+        assert!(find(&tools.join("alpha.exe")).is_none());
+        assert!(find(&tools.join("beta.exe")).is_none());
+        assert_eq!(program_registry.blocking_read().len(), 1);
+        assert_locater_consistent(&program_registry.blocking_read(), &program_locater);
+
+        // 文件夹被移回来后，其中的程序可以被重新添加
+        fs::create_dir_all(&tools).unwrap();
+        write_exe(&tools.join("beta.exe"), "Beta Tool");
+        assert!(apply(vec![tools.clone()], vec![]));
+        assert!(find(&tools.join("beta.exe")).is_some());
+        assert_locater_consistent(&program_registry.blocking_read(), &program_locater);
+    }
+
+    #[tokio::test]
+    async fn test_typed_query_matches_direct_search() {
+        let programs = vec![
+            registry_program(0, "Visual Studio Code", "code.exe"),
+            registry_program(1, "Visual Studio", "devenv.exe"),
+            registry_program(2, "VLC", "vlc.exe"),
+            registry_program(3, "QQ", "qq.exe"),
+            registry_program(4, "Notepad", "notepad.exe"),
+        ];
+
+        let typed = test_manager(programs.clone());
+        *typed.short_term_result_cache.write().await =
+            Some(LruCache::new(NonZeroUsize::new(8).unwrap()));
+        for input in ["v", "vi", "vis"] {
+            typed.perform_search(input, 10).await;
+        }
+        // 退回到缓存过的输入时，会话也从缓存中恢复
+        typed.perform_search("vi", 10).await;
+        assert_eq!(
+            typed.search_session.read().await.as_ref().unwrap().query,
+            "vi"
+        );
+        typed.perform_search("vis", 10).await;
+        let session = typed.search_session.read().await.clone().unwrap();
+        assert_eq!(session.query, "vis");
+        // "qq" 比输入短，不再匹配，继续输入时不会再对它评分
+        assert!(!session.hit_programs.contains(&3));
+        let typed_results = typed.perform_search("visu", 10).await;
+
+        let direct = test_manager(programs);
+        let direct_results = direct.perform_search("visu", 10).await;
+        // 不匹配的程序也以相同的分数与顺序出现在结果中
+        assert_eq!(typed_results.len(), 5);
+        assert_eq!(score_list(&typed_results), score_list(&direct_results));
+    }
+
+    #[tokio::test]
+    async fn test_keyword_index_narrows_candidates() {
+        let programs = vec![
+            registry_program(0, "Visual Studio Code", "code.exe"),
+            registry_program(1, "Visual Studio", "devenv.exe"),
+            registry_program(2, "VLC", "vlc.exe"),
+            registry_program(3, "QQ", "qq.exe"),
+            registry_program(4, "Notepad", "notepad.exe"),
+            registry_program(5, "Steam", "steam.exe"),
+        ];
+        let hits = |results: &[SearchMatchResult]| -> HashSet<u64> {
+            results
+                .iter()
+                .filter(|result| result.is_hit)
+                .map(|result| result.program_guid)
+                .collect()
+        };
+
+        for config in [
+            SearchModelConfig::Standard,
+            SearchModelConfig::Skim,
+            SearchModelConfig::Launchy,
+        ] {
+            let search_model =
+                Arc::new(SearchModelFactory::create_scorer(Arc::new(config.clone())));
+            let manager = ProgramManager::with_programs(
+                programs.clone(),
+                Arc::new(TraditionalSearchEngine::new(search_model.clone())),
+                &ProgramRankerConfig::default(),
+                ScoreNormalization::default(),
+            );
+            // 一两个字符的输入对所有程序评分
+            assert_eq!(manager.count_search_candidates("vs").await, (6, 6));
+            let (candidate_count, program_count) = manager.count_search_candidates("studio").await;
+            assert!(candidate_count < program_count, "{:?}", config);
+
+            // 筛选不会漏掉匹配：命中的程序与逐个评分的结果一致
+            for input in ["studio", "code", "stm", "note pad"] {
+                let indexed = manager.perform_search(input, 10).await;
+                assert_eq!(indexed.len(), programs.len());
+                let expected: HashSet<u64> = programs
+                    .iter()
+                    .filter(|program| {
+                        search_model.is_match(search_model.calculate_score(program, input))
+                    })
+                    .map(|program| program.program_guid)
+                    .collect();
+                assert_eq!(hits(&indexed), expected, "{:?} {}", config, input);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hide_and_unhide_program() {
+        use crate::program_manager::config::program_loader_config::DirectoryConfig;
+        use std::fs;
+
+        let dir = tempfile::tempdir().unwrap();
+        let alpha_path = dir.path().join("alpha.exe");
+        fs::write(&alpha_path, b"").unwrap();
+        fs::write(dir.path().join("beta.exe"), b"").unwrap();
+        let alpha_text = alpha_path.to_string_lossy().to_string();
+
+        let manager = test_manager(Vec::new());
+        manager.program_loader.add_target_path(DirectoryConfig::new(
+            dir.path().to_string_lossy().to_string(),
+            2,
+        ));
+        let programs = manager.program_loader.load_program();
+        {
+            let mut program_registry = manager.program_registry.write().await;
+            let (_, added, _) = ProgramManager::patch_program_registry(
+                &mut program_registry,
+                &manager.program_locater,
+                programs,
+                |_| false,
+            );
+            manager.program_ranker.update_programs(&added, &[]);
+        }
+        let alpha_guid = guid_of(&manager.program_registry.read().await, &alpha_text).unwrap();
+        assert_eq!(
+            manager.perform_search("alpha", 1).await[0].program_guid,
+            alpha_guid
+        );
+
+        // 隐藏：直接从注册表与搜索结果中移除
+        let hidden = manager.hide_program(alpha_guid).await.unwrap();
+        assert_eq!(hidden.show_name, "alpha");
+        assert!(manager.hide_program(alpha_guid).await.is_none());
+        let program_registry = manager.program_registry.read().await.clone();
+        assert_eq!(program_registry.len(), 1);
+        assert!(guid_of(&program_registry, &alpha_text).is_none());
+        assert_locater_consistent(&program_registry, &manager.program_locater);
+        assert!(manager
+            .perform_search("alpha", 10)
+            .await
+            .iter()
+            .all(|result| result.program_guid != alpha_guid));
+        assert_eq!(manager.get_hidden_programs(), vec![hidden]);
+
+        // 取消隐藏：索引目录中的程序立即重新加载
+        assert!(manager.unhide_program(&alpha_text).await);
+        assert!(!manager.unhide_program(&alpha_text).await);
+        assert!(manager.get_hidden_programs().is_empty());
+        let program_registry = manager.program_registry.read().await.clone();
+        assert_eq!(program_registry.len(), 2);
+        assert_locater_consistent(&program_registry, &manager.program_locater);
+        let alpha_guid = guid_of(&program_registry, &alpha_text).unwrap();
+        assert_eq!(
+            manager.perform_search("alpha", 1).await[0].program_guid,
+            alpha_guid
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pinned_program_always_first() {
+        let ranker_config = ProgramRankerConfig::default();
+        ranker_config.update(PartialProgramRankerConfig {
+            query_pins: Some(vec![QueryPin {
+                query: "vis".to_string(),
+                is_prefix: true,
+                launch_method: "qq.exe".to_string(),
+            }]),
+            ..Default::default()
+        });
+        let manager = ProgramManager::with_programs(
+            vec![
+                registry_program(0, "Visual Studio Code", "code.exe"),
+                registry_program(1, "Visual Studio", "devenv.exe"),
+                registry_program(2, "QQ", "qq.exe"),
+            ],
+            Arc::new(TraditionalSearchEngine::default()),
+            &ranker_config,
+            ScoreNormalization::default(),
+        );
+
+        // 输入比固定的查询词短时不生效
+        for input in ["v", "vi"] {
+            let results = manager.perform_search(input, 1).await;
+            assert_ne!(results[0].program_guid, 2);
+        }
+        // "qq" 比输入短，逐字输入时固定的程序不匹配，也不在缩小后的范围内
+        for input in ["vis", "visu"] {
+            let results = manager.perform_search(input, 1).await;
+            assert_eq!(results[0].program_guid, 2);
+        }
+        assert!(!manager
+            .search_session
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .hit_programs
+            .contains(&2));
+        // 被过滤条件排除的固定程序不会出现
+        let results = manager.perform_search("vis !qq", 10).await;
+        assert!(results.iter().all(|result| result.program_guid != 2));
+        assert_eq!(results.len(), 2);
+        let results = manager.perform_search("vis !code", 10).await;
+        assert_eq!(results[0].program_guid, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stable_bias_added_after_normalization() {
+        let programs = |bias: f64| {
+            vec![
+                registry_program(0, "Notepad", "notepad.exe"),
+                Arc::new(Program {
+                    stable_bias: bias,
+                    ..(*registry_program(1, "Notes", "notes.exe")).clone()
+                }),
+            ]
+        };
+        let search = |bias: f64| async move {
+            let manager = test_manager(programs(bias));
+            *manager.score_normalization.write().await = ScoreNormalization::MinMax;
+            manager.perform_search("note", 10).await
+        };
+        let score_of = |results: &[SearchMatchResult], guid: u64| {
+            score_list(results)
+                .into_iter()
+                .find(|(program_guid, _, _)| *program_guid == guid)
+                .unwrap()
+                .1
+        };
+
+        // 归一化只拉伸匹配分数，固定偏移量原样加在归一化之后
+        let plain = search(0.0).await;
+        let biased = search(3.0).await;
+        // 智能排序加分随时间变化，加上再减去之后会有浮点误差
+        assert!((score_of(&biased, 0) - score_of(&plain, 0)).abs() < 1e-9);
+        assert!((score_of(&biased, 1) - (score_of(&plain, 1) + 3.0)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_score_breakdown_matches_cached_result() {
+        let manager = test_manager(vec![
+            registry_program(0, "Notepad", "notepad.exe"),
+            registry_program(1, "Notes", "notes.exe"),
+        ]);
+        *manager.short_term_result_cache.write().await =
+            Some(LruCache::new(NonZeroUsize::new(8).unwrap()));
+        manager.perform_search("note", 10).await;
+        // 排序数据在缓存的结果之后发生了变化
+        manager.program_ranker.record_launch(0);
+
+        let report = manager.test_search_algorithm("note").await;
+        for result in &report.results {
+            let breakdown = &result.breakdown;
+            // 搜索时还没有这次启动
+            assert_eq!(breakdown.ranker.history, 0.0);
+            assert_eq!(
+                result.score,
+                breakdown.normalized_score + breakdown.stable_bias + breakdown.ranker.total()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fit_ranker_weights_excludes_replayed_launches() {
+        // 每个查询下，用户启动的程序的基础分数都低于另一个同名程序
+        let names = ["alpha", "bravo", "delta", "gamma", "omega"];
+        let mut programs = Vec::new();
+        for (index, name) in names.iter().enumerate() {
+            let guid = index as u64 * 2;
+            programs.push(registry_program(guid, name, &format!("{}.exe", name)));
+            programs.push(registry_program(
+                guid + 1,
+                &format!("old {} tool", name),
+                &format!("old-{}.exe", name),
+            ));
+        }
+        let manager = test_manager(programs);
+        for (index, name) in names.iter().enumerate() {
+            let target = index as u64 * 2 + 1;
+            let results = manager.perform_search(name, 10).await;
+            assert_ne!(results[0].program_guid, target);
+            for _ in 0..5 {
+                manager.program_ranker.record_launch(target);
+                manager.record_query_launch(name, target);
+            }
+        }
+
+        // 去掉被重放的启动后，用户启动的程序没有任何加分，依然排在第二，调整权重也不能让它排到前面
+        let report = manager.fit_ranker_weights().await;
+        assert_eq!(report.sample_count, names.len());
+        assert_eq!(report.mrr_before, 0.5);
+        assert_eq!(report.mrr_after, 0.5);
+        assert!(!report.applied);
+    }
+}
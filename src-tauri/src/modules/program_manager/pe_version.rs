@@ -0,0 +1,566 @@
+/// 这个文件用于读取可执行文件（PE 格式）中的版本信息
+///
+/// 很多程序的文件名与它的实际名字不一样，比如 `WINWORD.EXE` 其实是 "Microsoft Word"，
+/// 而这个名字保存在文件的版本资源（`RT_VERSION`）中。这里不依赖系统接口，只读取文件头与资源段，
+/// 所以在任何平台上都可以解析。
+///
+/// 版本资源的结构：
+/// `VS_VERSIONINFO` -> `StringFileInfo` -> `StringTable`（每种语言一个）-> `String`（键值对）
+///
+/// 有多种语言时优先使用与用户界面语言相同的字符串表
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// 资源类型中版本信息的编号
+const RT_VERSION: u32 = 16;
+
+/// 资源目录在数据目录表中的下标
+const RESOURCE_DIRECTORY_INDEX: usize = 2;
+
+/// 版本资源的最大长度，超过的话认为文件已经损坏
+const MAX_VERSION_RESOURCE_SIZE: u32 = 64 * 1024;
+
+/// 美国英语的 LANGID，没有用户语言的字符串表时优先使用它
+const LANG_EN_US: u16 = 0x0409;
+
+/// 文件的版本信息中与程序名字有关的字段
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeVersionInfo {
+    pub file_description: Option<String>,
+    pub product_name: Option<String>,
+    /// 公司名只放进语义描述，不作为关键字：同一家公司的程序太多，按公司名搜索没有区分度
+    pub company_name: Option<String>,
+}
+
+impl PeVersionInfo {
+    /// 所有不为空的名字字段（用作搜索关键字），按描述、产品名的顺序，去掉了重复的值
+    pub fn fields(&self) -> Vec<&str> {
+        let mut result: Vec<&str> = Vec::new();
+        for value in [&self.file_description, &self.product_name]
+            .into_iter()
+            .flatten()
+        {
+            if !result.iter().any(|item| item.eq_ignore_ascii_case(value)) {
+                result.push(value);
+            }
+        }
+        result
+    }
+
+    /// 用于语义搜索的描述：名字字段之后加上公司名，用中文逗号连接
+    pub fn description(&self) -> String {
+        let mut result = self.fields();
+        if let Some(company_name) = &self.company_name {
+            if !result
+                .iter()
+                .any(|item| item.eq_ignore_ascii_case(company_name))
+            {
+                result.push(company_name);
+            }
+        }
+        result.join("，")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields().is_empty() && self.company_name.is_none()
+    }
+}
+
+/// 一个节的位置信息
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_size: u32,
+    raw_pointer: u32,
+}
+
+/// 读取一个可执行文件的版本信息，文件不是 PE 格式或者没有版本信息时返回 `None`
+///
+/// `language` 是用户界面语言的 LANGID，比如简体中文为 `0x0804`
+pub fn read_pe_version_info(path: &Path, language: u16) -> Option<PeVersionInfo> {
+    let file = File::open(path).ok()?;
+    parse_pe_version_info(&mut BufReader::new(file), language)
+}
+
+/// 从 PE 数据中解析版本信息
+pub fn parse_pe_version_info<R: Read + Seek>(
+    reader: &mut R,
+    language: u16,
+) -> Option<PeVersionInfo> {
+    let data = read_version_resource(reader)?;
+    let info = parse_version_resource(&data, language);
+    (!info.is_empty()).then_some(info)
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize) -> Option<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset)).ok()?;
+    let mut buffer = vec![0u8; len];
+    reader.read_exact(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// 把相对虚拟地址转换为文件中的偏移
+fn rva_to_offset(sections: &[Section], rva: u32) -> Option<u64> {
+    sections.iter().find_map(|section| {
+        let size = section.virtual_size.max(section.raw_size);
+        let relative = rva.checked_sub(section.virtual_address)?;
+        (relative < size && relative < section.raw_size)
+            .then(|| section.raw_pointer as u64 + relative as u64)
+    })
+}
+
+/// 找到版本资源并读取它的原始数据
+fn read_version_resource<R: Read + Seek>(reader: &mut R) -> Option<Vec<u8>> {
+    // DOS 头
+    let dos_header = read_at(reader, 0, 64)?;
+    if &dos_header[0..2] != b"MZ" {
+        return None;
+    }
+    let pe_offset = u32_at(&dos_header, 0x3C)? as u64;
+
+    // PE 签名与 COFF 头
+    let pe_header = read_at(reader, pe_offset, 24)?;
+    if &pe_header[0..4] != b"PE\0\0" {
+        return None;
+    }
+    let section_count = u16_at(&pe_header, 6)? as usize;
+    let optional_header_size = u16_at(&pe_header, 20)? as usize;
+
+    // 可选头，32 位与 64 位程序的数据目录位置不同
+    let optional_header_offset = pe_offset + 24;
+    let optional_header = read_at(reader, optional_header_offset, optional_header_size)?;
+    let (rva_count_offset, directory_offset) = match u16_at(&optional_header, 0)? {
+        0x10b => (92, 96),
+        0x20b => (108, 112),
+        _ => return None,
+    };
+    if (u32_at(&optional_header, rva_count_offset)? as usize) <= RESOURCE_DIRECTORY_INDEX {
+        return None;
+    }
+    let resource_rva = u32_at(
+        &optional_header,
+        directory_offset + RESOURCE_DIRECTORY_INDEX * 8,
+    )?;
+    if resource_rva == 0 {
+        return None;
+    }
+
+    // 节表
+    let section_table = read_at(
+        reader,
+        optional_header_offset + optional_header_size as u64,
+        section_count * 40,
+    )?;
+    let sections: Vec<Section> = section_table
+        .chunks_exact(40)
+        .filter_map(|entry| {
+            Some(Section {
+                virtual_size: u32_at(entry, 8)?,
+                virtual_address: u32_at(entry, 12)?,
+                raw_size: u32_at(entry, 16)?,
+                raw_pointer: u32_at(entry, 20)?,
+            })
+        })
+        .collect();
+    let resource_offset = rva_to_offset(&sections, resource_rva)?;
+
+    // 资源目录：类型 -> 名字 -> 语言 -> 数据
+    let name_directory = find_resource_entry(reader, resource_offset, 0, Some(RT_VERSION))?;
+    let language_directory = find_resource_entry(reader, resource_offset, name_directory, None)?;
+    let data_entry = find_resource_entry(reader, resource_offset, language_directory, None)?;
+    let data_entry = read_at(reader, resource_offset + data_entry as u64, 8)?;
+    let data_rva = u32_at(&data_entry, 0)?;
+    let data_size = u32_at(&data_entry, 4)?;
+    if data_size > MAX_VERSION_RESOURCE_SIZE {
+        return None;
+    }
+    read_at(
+        reader,
+        rva_to_offset(&sections, data_rva)?,
+        data_size as usize,
+    )
+}
+
+/// 在一个资源目录中查找条目，返回条目指向的位置（相对于资源段的开头）
+///
+/// `id` 为 `None` 时返回第一个条目
+fn find_resource_entry<R: Read + Seek>(
+    reader: &mut R,
+    resource_offset: u64,
+    directory: u32,
+    id: Option<u32>,
+) -> Option<u32> {
+    let header = read_at(reader, resource_offset + directory as u64, 16)?;
+    let entry_count = u16_at(&header, 12)? as usize + u16_at(&header, 14)? as usize;
+    let entries = read_at(
+        reader,
+        resource_offset + directory as u64 + 16,
+        entry_count * 8,
+    )?;
+    entries.chunks_exact(8).find_map(|entry| {
+        let name = u32_at(entry, 0)?;
+        let offset = u32_at(entry, 4)?;
+        match id {
+            Some(id) if name != id => None,
+            _ => Some(offset & 0x7FFF_FFFF),
+        }
+    })
+}
+
+/// 版本资源中的一个块
+struct VersionBlock {
+    key: String,
+    value: String,
+    children_start: usize,
+    end: usize,
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// 读取以 0 结尾的 UTF-16 字符串，返回字符串与它结束的位置（包括结尾的 0）
+fn read_utf16(data: &[u8], start: usize, end: usize) -> (String, usize) {
+    let mut units = Vec::new();
+    let mut offset = start;
+    while offset + 2 <= end {
+        let unit = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        offset += 2;
+        if unit == 0 {
+            break;
+        }
+        units.push(unit);
+    }
+    (String::from_utf16_lossy(&units), offset)
+}
+
+/// 解析位于 `start` 的块，`limit` 是它的父块结束的位置
+fn parse_block(data: &[u8], start: usize, limit: usize) -> Option<VersionBlock> {
+    let length = u16_at(data, start)? as usize;
+    let value_length = u16_at(data, start + 2)? as usize;
+    let value_type = u16_at(data, start + 4)?;
+    let end = (start + length).min(limit).min(data.len());
+    if length < 6 || end <= start + 6 {
+        return None;
+    }
+    let (key, key_end) = read_utf16(data, start + 6, end);
+    let value_start = align4(key_end).min(end);
+    let (value, value_end) = if value_type == 1 {
+        // 文本值，这里以结尾的 0 为准，有些程序的 wValueLength 写的是字节数
+        if value_length == 0 {
+            (String::new(), value_start)
+        } else {
+            read_utf16(data, value_start, end)
+        }
+    } else {
+        (String::new(), (value_start + value_length).min(end))
+    };
+    Some(VersionBlock {
+        key,
+        value,
+        children_start: align4(value_end),
+        end,
+    })
+}
+
+/// 依次解析 `[start, end)` 中的所有子块
+fn parse_children(data: &[u8], start: usize, end: usize) -> Vec<VersionBlock> {
+    let mut result = Vec::new();
+    let mut offset = start;
+    while offset < end {
+        let Some(block) = parse_block(data, offset, end) else {
+            break;
+        };
+        offset = align4(block.end);
+        result.push(block);
+    }
+    result
+}
+
+/// 字符串表的优先顺序，越小越优先：与 `language` 完全相同、主语言相同、美国英语、语言无关、其他语言
+///
+/// 字符串表的名字是 8 位十六进制数，前 4 位是 LANGID，后 4 位是代码页
+fn string_table_rank(table_key: &str, language: u16) -> u8 {
+    let Some(table_language) = table_key
+        .get(..4)
+        .and_then(|text| u16::from_str_radix(text, 16).ok())
+    else {
+        return 4;
+    };
+    // LANGID 的低 10 位是主语言
+    let primary_language = |id: u16| id & 0x3FF;
+    if table_language == language {
+        0
+    } else if primary_language(table_language) == primary_language(language) {
+        1
+    } else if table_language == LANG_EN_US {
+        2
+    } else if table_language == 0 {
+        3
+    } else {
+        4
+    }
+}
+
+/// 解析 `VS_VERSIONINFO`，按 `string_table_rank` 的顺序取每个字段第一个不为空的值
+fn parse_version_resource(data: &[u8], language: u16) -> PeVersionInfo {
+    let mut info = PeVersionInfo::default();
+    let Some(root) = parse_block(data, 0, data.len()) else {
+        return info;
+    };
+    if root.key != "VS_VERSION_INFO" {
+        return info;
+    }
+    let mut tables = Vec::new();
+    for file_info in parse_children(data, root.children_start, root.end) {
+        if file_info.key == "StringFileInfo" {
+            tables.extend(parse_children(
+                data,
+                file_info.children_start,
+                file_info.end,
+            ));
+        }
+    }
+    // 稳定排序，优先级相同的字符串表保持原来的顺序
+    tables.sort_by_key(|table| string_table_rank(&table.key, language));
+    for table in tables {
+        for string in parse_children(data, table.children_start, table.end) {
+            let value = string.value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            let field = match string.key.as_str() {
+                "FileDescription" => &mut info.file_description,
+                "ProductName" => &mut info.product_name,
+                "CompanyName" => &mut info.company_name,
+                _ => continue,
+            };
+            if field.is_none() {
+                *field = Some(value.to_string());
+            }
+        }
+    }
+    info
+}
+
+#[cfg(test)]
+/// 测试中用来生成带版本信息的可执行文件
+pub(crate) mod test_pe {
+    use super::*;
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    fn pad4(data: &mut Vec<u8>) {
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+    }
+
+    /// 按 `VS_VERSIONINFO` 的格式生成一个块
+    fn block(key: &str, value_type: u16, value: &[u8], children: &[Vec<u8>]) -> Vec<u8> {
+        let value_length = if value_type == 1 {
+            value.len() / 2
+        } else {
+            value.len()
+        };
+        let mut data = vec![0u8; 6];
+        data[2..4].copy_from_slice(&(value_length as u16).to_le_bytes());
+        data[4..6].copy_from_slice(&value_type.to_le_bytes());
+        data.extend(utf16(key));
+        pad4(&mut data);
+        data.extend_from_slice(value);
+        for child in children {
+            pad4(&mut data);
+            data.extend_from_slice(child);
+        }
+        let length = data.len() as u16;
+        data[0..2].copy_from_slice(&length.to_le_bytes());
+        data
+    }
+
+    /// 只有一个美国英语字符串表的版本资源
+    pub(crate) fn version_resource(strings: &[(&str, &str)]) -> Vec<u8> {
+        localized_version_resource(&[("040904b0", strings)])
+    }
+
+    /// 每种语言一个字符串表的版本资源，(字符串表的名字, 键值对)
+    pub(crate) fn localized_version_resource(tables: &[(&str, &[(&str, &str)])]) -> Vec<u8> {
+        let tables: Vec<Vec<u8>> = tables
+            .iter()
+            .map(|(table_key, strings)| {
+                let strings: Vec<Vec<u8>> = strings
+                    .iter()
+                    .map(|(key, value)| block(key, 1, &utf16(value), &[]))
+                    .collect();
+                block(table_key, 1, &[], &strings)
+            })
+            .collect();
+        let file_info = block("StringFileInfo", 1, &[], &tables);
+        block("VS_VERSION_INFO", 0, &[0u8; 52], &[file_info])
+    }
+
+    /// 生成一个只有 `.rsrc` 节的最小 PE 文件
+    pub(crate) fn build_pe(version: &[u8], is_64_bit: bool) -> Vec<u8> {
+        const SECTION_RVA: u32 = 0x1000;
+        const SECTION_OFFSET: usize = 0x200;
+        let optional_header_size: usize = if is_64_bit { 240 } else { 224 };
+
+        // 资源段：类型目录 -> 名字目录 -> 语言目录 -> 数据条目 -> 版本数据
+        let mut resource = Vec::new();
+        for (id, next) in [
+            (RT_VERSION, 24u32 | 0x8000_0000),
+            (1, 48 | 0x8000_0000),
+            (0x409, 72),
+        ] {
+            let mut directory = vec![0u8; 16];
+            directory[14..16].copy_from_slice(&1u16.to_le_bytes());
+            resource.extend(directory);
+            resource.extend(id.to_le_bytes());
+            resource.extend(next.to_le_bytes());
+        }
+        resource.extend((SECTION_RVA + 88).to_le_bytes());
+        resource.extend((version.len() as u32).to_le_bytes());
+        resource.extend([0u8; 8]);
+        resource.extend_from_slice(version);
+
+        let mut pe = vec![0u8; 0x40];
+        pe[0..2].copy_from_slice(b"MZ");
+        pe[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe.extend(b"PE\0\0");
+        let mut coff = vec![0u8; 20];
+        coff[2..4].copy_from_slice(&1u16.to_le_bytes());
+        coff[16..18].copy_from_slice(&(optional_header_size as u16).to_le_bytes());
+        pe.extend(coff);
+        let mut optional = vec![0u8; optional_header_size];
+        let (magic, rva_count_offset, directory_offset) = if is_64_bit {
+            (0x20bu16, 108, 112)
+        } else {
+            (0x10b, 92, 96)
+        };
+        optional[0..2].copy_from_slice(&magic.to_le_bytes());
+        optional[rva_count_offset..rva_count_offset + 4].copy_from_slice(&16u32.to_le_bytes());
+        let resource_entry = directory_offset + RESOURCE_DIRECTORY_INDEX * 8;
+        optional[resource_entry..resource_entry + 4].copy_from_slice(&SECTION_RVA.to_le_bytes());
+        optional[resource_entry + 4..resource_entry + 8]
+            .copy_from_slice(&(resource.len() as u32).to_le_bytes());
+        pe.extend(optional);
+        let mut section = vec![0u8; 40];
+        section[0..5].copy_from_slice(b".rsrc");
+        section[8..12].copy_from_slice(&(resource.len() as u32).to_le_bytes());
+        section[12..16].copy_from_slice(&SECTION_RVA.to_le_bytes());
+        section[16..20].copy_from_slice(&(resource.len() as u32).to_le_bytes());
+        section[20..24].copy_from_slice(&(SECTION_OFFSET as u32).to_le_bytes());
+        pe.extend(section);
+        pe.resize(SECTION_OFFSET, 0);
+        pe.extend(resource);
+        pe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_pe::*;
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_parse_pe_version_info() {
+        let version = version_resource(&[
+            ("CompanyName", "Microsoft Corporation"),
+            ("FileDescription", "Microsoft Word"),
+            ("FileVersion", "16.0.17928.20114"),
+            ("ProductName", "Microsoft Office"),
+        ]);
+        let expected = PeVersionInfo {
+            file_description: Some("Microsoft Word".to_string()),
+            product_name: Some("Microsoft Office".to_string()),
+            company_name: Some("Microsoft Corporation".to_string()),
+        };
+        for is_64_bit in [false, true] {
+            let pe = build_pe(&version, is_64_bit);
+            let info = parse_pe_version_info(&mut Cursor::new(pe), LANG_EN_US).unwrap();
+            assert_eq!(info, expected);
+        }
+
+        // 从文件中读取
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("WINWORD.EXE");
+        std::fs::write(&path, build_pe(&version, true)).unwrap();
+        let info = read_pe_version_info(&path, LANG_EN_US).unwrap();
+        assert_eq!(info.fields(), vec!["Microsoft Word", "Microsoft Office"]);
+    }
+
+    #[test]
+    fn test_parse_pe_version_info_locale() {
+        let version = localized_version_resource(&[
+            (
+                "040904b0",
+                &[
+                    ("FileDescription", "Microsoft Word"),
+                    ("ProductName", "Microsoft Office"),
+                ],
+            ),
+            ("080404b0", &[("FileDescription", "微软文字处理")]),
+            ("040404b0", &[("FileDescription", "微軟文書處理")]),
+        ]);
+        let pe = build_pe(&version, true);
+        let read = |language: u16| parse_pe_version_info(&mut Cursor::new(&pe), language).unwrap();
+
+        // 使用用户界面语言的字符串表，其中没有的字段再从其他字符串表中取
+        let info = read(0x0804);
+        assert_eq!(info.file_description.as_deref(), Some("微软文字处理"));
+        assert_eq!(info.product_name.as_deref(), Some("Microsoft Office"));
+        assert_eq!(
+            read(0x0404).file_description.as_deref(),
+            Some("微軟文書處理")
+        );
+        // 没有完全相同的语言时使用主语言相同的字符串表（新加坡华语）
+        assert_eq!(
+            read(0x1004).file_description.as_deref(),
+            Some("微软文字处理")
+        );
+        // 其他语言使用美国英语
+        assert_eq!(
+            read(0x0411).file_description.as_deref(),
+            Some("Microsoft Word")
+        );
+    }
+
+    #[test]
+    fn test_parse_pe_version_info_invalid() {
+        // 没有需要的字段
+        let version = version_resource(&[("FileVersion", "1.0.0")]);
+        let pe = build_pe(&version, false);
+        assert!(parse_pe_version_info(&mut Cursor::new(pe), LANG_EN_US).is_none());
+
+        // 被截断的文件
+        let version = version_resource(&[("ProductName", "Demo")]);
+        let pe = build_pe(&version, false);
+        assert!(
+            parse_pe_version_info(&mut Cursor::new(&pe[..pe.len() - 40]), LANG_EN_US).is_none()
+        );
+
+        // 不是可执行文件
+        assert!(
+            parse_pe_version_info(&mut Cursor::new(b"not a pe file".to_vec()), LANG_EN_US)
+                .is_none()
+        );
+        assert!(parse_pe_version_info(&mut Cursor::new(vec![0u8; 256]), LANG_EN_US).is_none());
+    }
+}
@@ -34,6 +34,11 @@ impl PinyinMapper {
         }
     }
 
+    /// 获得单个汉字的拼音，不是汉字时返回 `None`
+    pub fn get_pinyin(&self, c: char) -> Option<&str> {
+        self.pinyin.get(&c.to_string()).map(String::as_str)
+    }
+
     pub fn convert(&self, word: &str) -> String {
         let mut result = String::new();
         let mut prev_is_han = false; // 用于跟踪前一个字符是否为 ASCII
@@ -1,8 +1,15 @@
+use super::bookmark;
 use super::config::program_loader_config::DirectoryConfig;
+use super::desktop_entry;
 use super::localization_translation::parse_localized_names_from_dir;
+use super::pe_version::{read_pe_version_info, PeVersionInfo};
 use super::pinyin_mapper::PinyinMapper;
+use super::program_snapshot::SnapshotProgram;
+use super::program_source::{ProgramSource, ProgramSourceStat};
+use super::unit::indices_to_ranges;
 use super::LaunchMethod;
 use crate::core::image_processor::ImageIdentity;
+use crate::core::storage::utils::resolve_lnk_target_path;
 use crate::error::OptionExt;
 use crate::modules::config::default::APP_PIC_PATH;
 use crate::program_manager::config::program_loader_config::PartialProgramLoaderConfig;
@@ -12,6 +19,7 @@ use crate::program_manager::semantic_manager::SemanticManager;
 /// 这个类用于加载电脑上程序，通过扫描路径或使用系统调用接口
 use crate::program_manager::Program;
 use crate::utils::defer::defer;
+use crate::utils::locale::get_user_ui_language_id;
 use crate::utils::notify::notify;
 use crate::utils::windows::get_u16_vec;
 use crate::utils::{dashmap_to_hashmap, hashmap_to_dashmap};
@@ -22,15 +30,18 @@ use globset::GlobSetBuilder;
 use globset::{Glob, GlobSet};
 use image::ImageReader;
 use parking_lot::RwLock;
+use rayon::prelude::*;
 use regex::RegexSet;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicU64;
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Instant, SystemTime};
 use tracing::{debug, warn};
 use walkdir::WalkDir;
 use windows::Win32::Foundation::PROPERTYKEY;
@@ -157,6 +168,8 @@ pub struct ProgramLoaderInner {
     pinyin_mapper: PinyinMapper,
     /// 是否要扫描uwp
     is_scan_uwp_programs: bool,
+    /// 是否要扫描 .desktop 文件
+    is_scan_desktop_entries: bool,
     /// 索引的网页
     index_web_pages: Vec<(String, String)>,
     /// 添加的自定义命令
@@ -169,11 +182,29 @@ pub struct ProgramLoaderInner {
     program_alias: DashMap<String, Vec<String>>,
     /// 语义描述信息
     semantic_descriptions: HashMap<String, String>,
+    /// 用户隐藏的程序
+    hidden_programs: HashMap<String, String>,
+    /// 要索引书签的浏览器路径
+    bookmark_paths: Vec<String>,
+    /// 注册的程序来源，按顺序加载
+    sources: Vec<Box<dyn ProgramSource>>,
+    /// 被用户关闭的来源
+    disabled_sources: Vec<String>,
+    /// 每个来源自己的选项 (source id) => (options)
+    source_options: HashMap<String, serde_json::Value>,
+    /// 上一次加载时每个来源的情况
+    source_stats: Vec<ProgramSourceStat>,
+    /// 上一次扫描时每个根目录的情况
+    root_scan_stats: Vec<RootScanStat>,
     /// 语义管理器
     #[allow(dead_code)]
     semantic_manager: Arc<SemanticManager>,
     /// 是否在加载时生成/读取程序的embedding（仅 ai 构建有效）
     compute_embeddings: bool,
+    /// 读取版本信息时优先使用的语言（用户界面语言的 LANGID）
+    version_language: u16,
+    /// 可执行文件的版本信息 (路径) => (文件的修改时间, 版本信息)
+    version_info_cache: DashMap<PathBuf, (SystemTime, Option<PeVersionInfo>)>,
 }
 
 impl Default for ProgramLoaderInner {
@@ -192,14 +223,24 @@ impl ProgramLoaderInner {
             program_name_hash: DashSet::new(),
             pinyin_mapper: PinyinMapper::new(),
             is_scan_uwp_programs: true,
+            is_scan_desktop_entries: false,
             index_web_pages: Vec::new(),
             custom_command: Vec::new(),
             loading_time: None,
             forbidden_paths: Vec::new(),
             program_alias: DashMap::new(),
             semantic_descriptions: HashMap::new(),
+            hidden_programs: HashMap::new(),
+            bookmark_paths: Vec::new(),
+            sources: builtin_sources(),
+            disabled_sources: Vec::new(),
+            source_options: HashMap::new(),
+            source_stats: Vec::new(),
+            root_scan_stats: Vec::new(),
             semantic_manager,
             compute_embeddings: false,
+            version_language: get_user_ui_language_id(),
+            version_info_cache: DashMap::new(),
         }
     }
 
@@ -211,10 +252,15 @@ impl ProgramLoaderInner {
             forbidden_paths: Some(self.forbidden_paths.clone()),
             program_bias: Some(self.program_bias.clone()),
             is_scan_uwp_programs: Some(self.is_scan_uwp_programs),
+            is_scan_desktop_entries: Some(self.is_scan_desktop_entries),
             index_web_pages: Some(self.index_web_pages.clone()),
             custom_command: Some(self.custom_command.clone()),
             program_alias: Some(program_alias_hash_map),
             semantic_descriptions: Some(self.semantic_descriptions.clone()),
+            hidden_programs: Some(self.hidden_programs.clone()),
+            bookmark_paths: Some(self.bookmark_paths.clone()),
+            disabled_sources: Some(self.disabled_sources.clone()),
+            source_options: Some(self.source_options.clone()),
         }
     }
 
@@ -224,12 +270,25 @@ impl ProgramLoaderInner {
         self.forbidden_paths = config.get_forbidden_paths();
         self.program_bias = config.get_program_bias();
         self.is_scan_uwp_programs = config.get_is_scan_uwp_programs();
-        self.guid_generator = GuidGenerator::new();
+        self.is_scan_desktop_entries = config.get_is_scan_desktop_entries();
+        // guid生成器不重置：增量更新时旧程序的guid会被保留，新程序的guid不能与其冲突
         self.program_name_hash = DashSet::new();
         self.index_web_pages = config.get_index_web_pages();
         self.custom_command = config.get_custom_command();
         self.program_alias = hashmap_to_dashmap(&config.get_program_alias());
         self.semantic_descriptions = config.get_semantic_descriptions();
+        self.hidden_programs = config.get_hidden_programs();
+        self.bookmark_paths = config.get_bookmark_paths();
+        self.disabled_sources = config.get_disabled_sources();
+        self.source_options = config.get_source_options();
+        for source in self.sources.iter_mut() {
+            let options = self
+                .source_options
+                .get(source.id())
+                .cloned()
+                .unwrap_or_default();
+            source.configure(&options);
+        }
     }
     /// 设置是否生成程序embedding
     pub fn set_compute_embeddings(&mut self, enabled: bool) {
@@ -280,6 +339,86 @@ impl ProgramLoaderInner {
         let pinyin_name = remove_string_space(&split_name);
         vec![lower_name, pinyin_name, first_latter_name, uppercase_name]
     }
+    /// 将关键字中被命中的字符映射回 `show_name` 中的字符区间 [start, end)
+    ///
+    /// 关键字由 `convert_search_keywords` 生成，可能是小写名、拼音、首字母或大写字母缩写，
+    /// 这里按同样的规则重建这几种形式并记录每个字符来自 `show_name` 的哪个位置。
+    /// 用户自定义的别名无法映射，此时返回空（高亮信息中的 `keyword_ranges` 仍然可以使用）
+    pub fn map_match_to_show_name(
+        &self,
+        show_name: &str,
+        keyword: &str,
+        keyword_indices: &[usize],
+    ) -> Vec<(usize, usize)> {
+        let keyword_chars: Vec<char> = keyword.chars().collect();
+        if keyword_chars.is_empty() || keyword_indices.is_empty() {
+            return Vec::new();
+        }
+
+        // 每种形式都是 (字符, 在 show_name 中的下标) 的序列
+        let mut lower_view = Vec::new();
+        let mut pinyin_view = Vec::new();
+        let mut initials_view = Vec::new();
+        let mut uppercase_view = Vec::new();
+        let is_ascii_name = show_name.is_ascii();
+        let mut in_word = false;
+        for (index, c) in show_name.chars().enumerate() {
+            lower_view.extend(c.to_lowercase().map(|lc| (lc, index)));
+            if let Some(pinyin) = self.pinyin_mapper.get_pinyin(c) {
+                pinyin_view.extend(pinyin.chars().map(|pc| (pc, index)));
+                if let Some(first) = pinyin.chars().next() {
+                    initials_view.push((first, index));
+                }
+                in_word = false;
+            } else if c.is_whitespace() {
+                in_word = false;
+            } else {
+                pinyin_view.extend(c.to_lowercase().map(|lc| (lc, index)));
+                if !in_word {
+                    initials_view.extend(c.to_lowercase().map(|lc| (lc, index)));
+                }
+                in_word = true;
+            }
+            if is_ascii_name && c.is_ascii_uppercase() {
+                uppercase_view.push((c.to_ascii_lowercase(), index));
+            }
+        }
+
+        // 选择能完整容纳关键字且多余字符最少的形式
+        let best_alignment = [lower_view, pinyin_view, initials_view, uppercase_view]
+            .into_iter()
+            .filter_map(|view| {
+                let mut positions = Vec::with_capacity(keyword_chars.len());
+                let mut start = 0;
+                for c in &keyword_chars {
+                    let offset = view[start..].iter().position(|(vc, _)| vc == c)?;
+                    positions.push(view[start + offset].1);
+                    start += offset + 1;
+                }
+                Some((view.len() - keyword_chars.len(), positions))
+            })
+            .min_by_key(|(extra, _)| *extra);
+        let Some((_, positions)) = best_alignment else {
+            return Vec::new();
+        };
+
+        let matched: Vec<usize> = keyword_indices
+            .iter()
+            .filter_map(|index| positions.get(*index).copied())
+            .collect();
+        indices_to_ranges(matched)
+    }
+    /// 判断一个程序需不需要跳过：被用户隐藏了，或者同名的程序已经添加了
+    ///
+    /// 需要在创建程序之前调用，被隐藏的程序不会占用名字，同名的其他程序依然可以被添加
+    fn should_skip_program(&self, full_name: &str, launch_method: &LaunchMethod) -> bool {
+        if self.hidden_programs.contains_key(&launch_method.get_text()) {
+            debug!("跳过已隐藏的程序: {}", full_name);
+            return true;
+        }
+        self.check_program_is_exist(full_name)
+    }
+
     /// 判断一个程序是不是已经添加了
     fn check_program_is_exist(&self, full_name: &str) -> bool {
         // 用于判断的名字
@@ -293,7 +432,7 @@ impl ProgramLoaderInner {
         false
     }
 
-    /// 获取当前电脑上所有的程序
+    /// 获取当前电脑上所有的程序，按注册的顺序依次加载每个启用的来源
     pub fn load_program(&mut self) -> Vec<Arc<Program>> {
         use tracing::{debug, info};
 
@@ -302,30 +441,42 @@ impl ProgramLoaderInner {
         let start = Instant::now();
         let mut result = Vec::new();
 
-        if self.is_scan_uwp_programs {
-            info!("📱 开始扫描UWP程序");
-            let uwp_infos = self.load_uwp_program();
-            info!("📱 UWP程序扫描完成，找到 {} 个程序", uwp_infos.len());
-            result.extend(uwp_infos);
-        } else {
-            debug!("⏭️ 跳过UWP程序扫描（已禁用）");
+        // 来源在加载时需要可变地借用加载器，所以先把它们取出来
+        let mut sources = std::mem::take(&mut self.sources);
+        let mut source_stats = Vec::with_capacity(sources.len());
+        for source in sources.iter_mut() {
+            let id = source.id();
+            let is_disabled = self.disabled_sources.iter().any(|item| item == id);
+            let mut context = ProgramSourceContext { loader: self };
+            if is_disabled || !source.is_enabled(&context) {
+                debug!("⏭️ 跳过程序来源 {}（已禁用）", id);
+                source_stats.push(ProgramSourceStat {
+                    id: id.to_string(),
+                    enabled: false,
+                    item_count: 0,
+                    load_time: 0.0,
+                });
+                continue;
+            }
+            let source_start = Instant::now();
+            let programs = source.load(&mut context);
+            let load_time = source_start.elapsed().as_secs_f64() * 1000.0;
+            info!(
+                "📦 程序来源 {} 加载完成，找到 {} 个程序，耗时 {:.2} ms",
+                id,
+                programs.len(),
+                load_time
+            );
+            source_stats.push(ProgramSourceStat {
+                id: id.to_string(),
+                enabled: true,
+                item_count: programs.len(),
+                load_time,
+            });
+            result.extend(programs);
         }
-
-        // 添加普通的程序
-        info!("💻 开始扫描路径中的程序");
-        let program_infos = self.load_program_from_path();
-        info!("💻 路径程序扫描完成，找到 {} 个程序", program_infos.len());
-        result.extend(program_infos);
-
-        info!("🌐 开始加载网页程序");
-        let web_infos = self.load_web();
-        info!("🌐 网页程序加载完成，找到 {} 个程序", web_infos.len());
-        result.extend(web_infos);
-
-        info!("⚡ 开始加载自定义命令");
-        let command_infos = self.load_custom_command();
-        info!("⚡ 自定义命令加载完成，找到 {} 个命令", command_infos.len());
-        result.extend(command_infos);
+        self.sources = sources;
+        self.source_stats = source_stats;
 
         // 结束计时
         self.loading_time = Some(start.elapsed());
@@ -342,6 +493,52 @@ impl ProgramLoaderInner {
         result
     }
 
+    /// 使用快照中的程序创建 `Program`，guid 由当前的生成器重新分配，避免与之后扫描出来的程序冲突
+    pub fn restore_programs(&self, snapshot_programs: Vec<SnapshotProgram>) -> Vec<Arc<Program>> {
+        snapshot_programs
+            .into_iter()
+            .filter(|item| {
+                !self
+                    .hidden_programs
+                    .contains_key(&item.launch_method.get_text())
+            })
+            .map(|item| {
+                Arc::new(Program {
+                    program_guid: self.guid_generator.get_guid(),
+                    show_name: item.show_name,
+                    launch_method: item.launch_method,
+                    search_keywords: item.search_keywords,
+                    stable_bias: item.stable_bias,
+                    icon_path: item.icon_path,
+                    embedding: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// 注册一个程序来源，已经有相同 id 的来源时替换它
+    pub fn register_source(&mut self, mut source: Box<dyn ProgramSource>) {
+        let options = self
+            .source_options
+            .get(source.id())
+            .cloned()
+            .unwrap_or_default();
+        source.configure(&options);
+        match self
+            .sources
+            .iter()
+            .position(|item| item.id() == source.id())
+        {
+            Some(index) => self.sources[index] = source,
+            None => self.sources.push(source),
+        }
+    }
+
+    /// 获得每个来源在上一次加载时的数量与耗时
+    pub fn get_source_stats(&self) -> Vec<ProgramSourceStat> {
+        self.source_stats.clone()
+    }
+
     /// 检查用户有没有添加别名
     fn check_program_alias(&self, key: &LaunchMethod) -> Vec<String> {
         let key = key.get_text();
@@ -365,12 +562,32 @@ impl ProgramLoaderInner {
     /// 创建Program的辅助函数，消除重复代码
     /// 这个函数统一处理Program的创建逻辑，包括生成GUID、计算stable_bias等
     fn create_program(
+        &self,
+        show_name: String,
+        unique_name: String,
+        launch_method: LaunchMethod,
+        search_keywords: Vec<String>,
+        icon_path: ImageIdentity,
+    ) -> Arc<Program> {
+        self.create_program_with_description(
+            show_name,
+            unique_name,
+            launch_method,
+            search_keywords,
+            icon_path,
+            None,
+        )
+    }
+
+    /// 与 `create_program` 相同，`extra_description` 会追加在用户填写的语义描述之后
+    fn create_program_with_description(
         &self,
         show_name: String,
         unique_name: String,
         launch_method: LaunchMethod,
         mut search_keywords: Vec<String>,
         icon_path: ImageIdentity,
+        extra_description: Option<String>,
     ) -> Arc<Program> {
         let guid = self.guid_generator.get_guid();
         let stable_bias = self.get_program_bias(&unique_name);
@@ -380,9 +597,15 @@ impl ProgramLoaderInner {
         search_keywords.extend(alias_name_to_append);
 
         #[allow(unused_variables)]
-        let description = self
-            .get_program_semantic_description(&launch_method)
-            .unwrap_or_default();
+        let description = [
+            self.get_program_semantic_description(&launch_method),
+            extra_description,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>()
+        .join("，");
 
         // 生成或读取 embedding（仅当启用语义搜索时）
         let embedding = if self.compute_embeddings {
@@ -439,12 +662,12 @@ impl ProgramLoaderInner {
                 continue;
             }
             let check_name = "[网页]".to_string() + show_name;
-            if self.check_program_is_exist(&check_name) {
+            let launch_method = LaunchMethod::File(url.clone());
+            if self.should_skip_program(&check_name, &launch_method) {
                 continue;
             }
             let unique_name = check_name.to_lowercase();
             let alias_names: Vec<String> = self.convert_search_keywords(show_name);
-            let launch_method = LaunchMethod::File(url.clone());
 
             let program = self.create_program(
                 show_name.clone(),
@@ -458,113 +681,336 @@ impl ProgramLoaderInner {
         result
     }
 
-    /// 获取所有的程序
-    fn load_program_from_path(&mut self) -> Vec<Arc<Program>> {
-        let mut result: Vec<Arc<Program>> = Vec::new();
-        for directory in &self.target_paths {
-            let mut program_paths_str: Vec<String> = Vec::new();
-            let checker = PathChecker::new(
-                &directory.pattern,
-                &directory.pattern_type,
-                &directory.excluded_keywords,
+    /// 所有浏览器书签，同一个网址只添加一次，书签所在的文件夹名字也作为搜索关键字
+    fn load_bookmarks(&mut self) -> Vec<Arc<Program>> {
+        let mut bookmarks = Vec::new();
+        for path in &self.bookmark_paths {
+            if path.trim().is_empty() {
+                continue;
+            }
+            match bookmark::read_bookmarks(Path::new(path.trim())) {
+                Ok(items) => bookmarks.extend(items),
+                Err(e) => warn!("读取书签失败 {}: {}", path, e),
+            }
+        }
+
+        // 已经手动添加为网页的网址不再重复添加
+        let indexed_urls: HashSet<String> = self
+            .index_web_pages
+            .iter()
+            .map(|(_, url)| url.clone())
+            .collect();
+        let mut result = Vec::new();
+        for bookmark in bookmark::merge_bookmarks(bookmarks) {
+            if indexed_urls.contains(&bookmark.url) {
+                continue;
+            }
+            let check_name = "[书签]".to_string() + &bookmark.url;
+            let launch_method = LaunchMethod::File(bookmark.url.clone());
+            if self.should_skip_program(&check_name, &launch_method) {
+                continue;
+            }
+            let mut alias_names = self.convert_search_keywords(&bookmark.title);
+            for folder in &bookmark.folders {
+                alias_names.extend(self.convert_search_keywords(folder));
+            }
+
+            let program = self.create_program(
+                bookmark.title,
+                check_name.to_lowercase(),
+                launch_method,
+                alias_names,
+                ImageIdentity::Web(bookmark.url),
             );
-            let checker = match checker {
-                Ok(checker) => Arc::new(checker),
-                Err(message) => {
-                    warn!("遇到错误: {}", message);
-                    notify("ZeroLaunch-rs", &format!("遇到错误: {}", message));
-                    continue;
-                }
+            result.push(program);
+        }
+        result
+    }
+
+    /// 所有的 freedesktop 应用程序入口
+    fn load_desktop_entries(&mut self) -> Vec<Arc<Program>> {
+        let mut result = Vec::new();
+        let locale = desktop_entry::current_locale();
+        let current_desktops = desktop_entry::current_desktops();
+        let desktop_files =
+            desktop_entry::collect_desktop_files(&desktop_entry::application_dirs());
+        for (_id, path) in desktop_files {
+            if !self.is_valid_path(&path) {
+                continue;
+            }
+            let Some(entry) = desktop_entry::load_desktop_entry(&path, &locale) else {
+                continue;
             };
+            if !entry.should_show(&current_desktops) {
+                continue;
+            }
+            let check_name = "[desktop]".to_string() + &entry.name;
+            let launch_method = LaunchMethod::Command(entry.exec.clone());
+            if self.should_skip_program(&check_name, &launch_method) {
+                continue;
+            }
 
-            let paths = self.recursive_visit_dir(
-                Path::new(&directory.root_path),
-                directory.max_depth as usize,
-                checker,
-            );
+            let mut alias_names = self.convert_search_keywords(&entry.name);
+            for extra in std::iter::once(&entry.default_name)
+                .chain(std::iter::once(&entry.generic_name))
+                .chain(entry.keywords.iter())
+                .filter(|item| !item.is_empty())
+            {
+                alias_names.extend(self.convert_search_keywords(extra));
+            }
+            let icon_path = desktop_entry::lookup_icon(&entry.icon)
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_default();
 
-            let paths_count = paths.len();
-            program_paths_str.extend(paths);
-            debug!(
-                "成功扫描目录: {}, 找到 {} 个程序",
-                directory.root_path, paths_count
+            let program = self.create_program(
+                entry.name.clone(),
+                entry.name.to_lowercase(),
+                launch_method,
+                alias_names,
+                ImageIdentity::File(icon_path),
             );
+            result.push(program);
+        }
+        result
+    }
 
-            let mut grouped_paths: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
-            for path_str in program_paths_str {
-                let path = PathBuf::from(path_str);
-                if let Some(parent) = path.parent() {
-                    grouped_paths
-                        .entry(parent.to_path_buf())
-                        .or_default()
-                        .push(path);
-                }
-            }
-            for (dir_path, files_in_dir) in grouped_paths {
-                let localized_names = parse_localized_names_from_dir(&dir_path);
-                for target_path_buf in files_in_dir {
-                    let target_path = target_path_buf.as_path();
-                    let target_path_str = target_path.to_string_lossy().to_string();
-
-                    // 这个是本地的文件名，这个用于匹配会不会有翻译过的本地化名字
-                    let file_name = target_path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .map(String::from)
-                        .unwrap_or_default()
-                        .to_lowercase();
-                    // 这个是用于显示的名字（就是去除了后缀的）
-                    let show_name = target_path
-                        .file_stem()
-                        .and_then(|s| s.to_str())
-                        .map(String::from)
-                        .unwrap_or_default();
-
-                    if self.check_program_is_exist(&show_name) {
-                        continue;
+    /// 获取所有的程序
+    fn load_program_from_path(&mut self) -> Vec<Arc<Program>> {
+        // 各个根目录之间互不影响，并行扫描；创建程序时按配置的顺序进行，保证重名时保留的程序不变
+        let scanned: Vec<(Vec<String>, RootScanStat)> = self
+            .target_paths
+            .par_iter()
+            .map(|directory| {
+                let checker = match PathChecker::new(
+                    &directory.pattern,
+                    &directory.pattern_type,
+                    &directory.excluded_keywords,
+                ) {
+                    Ok(checker) => Arc::new(checker),
+                    Err(message) => {
+                        warn!("遇到错误: {}", message);
+                        notify("ZeroLaunch-rs", &format!("遇到错误: {}", message));
+                        let stat = RootScanStat {
+                            root_path: directory.root_path.clone(),
+                            failed: true,
+                            ..Default::default()
+                        };
+                        return (Vec::new(), stat);
                     }
+                };
+                let (paths, stat) = self.scan_root(directory, checker);
+                debug!(
+                    "成功扫描目录: {}, 找到 {} 个程序，遍历 {} 项，耗时 {:.2} ms",
+                    directory.root_path, stat.matched_files, stat.visited_entries, stat.scan_time
+                );
+                (paths, stat)
+            })
+            .collect();
 
-                    // 基础别名：来自文件名本身
-                    let mut alias_names: Vec<String> = self.convert_search_keywords(&show_name);
-                    let unique_name = show_name.to_lowercase();
-                    let launch_method = if let Some(ext) = target_path.extension() {
-                        if let Some(ext_str) = ext.to_str() {
-                            if ["url", "lnk", "exe"].contains(&ext_str) {
-                                LaunchMethod::Path(target_path_str.clone())
-                            } else {
-                                LaunchMethod::File(target_path_str.clone())
-                            }
+        let mut result: Vec<Arc<Program>> = Vec::new();
+        let mut root_scan_stats = Vec::with_capacity(scanned.len());
+        for (paths, stat) in scanned {
+            result.extend(self.create_programs_from_files(paths));
+            root_scan_stats.push(stat);
+        }
+        self.root_scan_stats = root_scan_stats;
+        result
+    }
+
+    /// 获得每个根目录在上一次扫描时的情况
+    pub fn get_root_scan_stats(&self) -> Vec<RootScanStat> {
+        self.root_scan_stats.clone()
+    }
+
+    /// 将扫描到的文件路径转换为程序（同一文件夹下的文件共享本地化名字的解析结果）
+    fn create_programs_from_files(&self, program_paths_str: Vec<String>) -> Vec<Arc<Program>> {
+        let mut result: Vec<Arc<Program>> = Vec::new();
+        let mut grouped_paths: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for path_str in program_paths_str {
+            let path = PathBuf::from(path_str);
+            if let Some(parent) = path.parent() {
+                grouped_paths
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(path);
+            }
+        }
+        for (dir_path, files_in_dir) in grouped_paths {
+            let localized_names = parse_localized_names_from_dir(&dir_path);
+            for target_path_buf in files_in_dir {
+                let target_path = target_path_buf.as_path();
+                let target_path_str = target_path.to_string_lossy().to_string();
+
+                // 这个是本地的文件名，这个用于匹配会不会有翻译过的本地化名字
+                let file_name = target_path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .map(String::from)
+                    .unwrap_or_default()
+                    .to_lowercase();
+                // 这个是用于显示的名字（就是去除了后缀的）
+                let show_name = target_path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .map(String::from)
+                    .unwrap_or_default();
+
+                let launch_method = if let Some(ext) = target_path.extension() {
+                    if let Some(ext_str) = ext.to_str() {
+                        if ["url", "lnk", "exe"].contains(&ext_str) {
+                            LaunchMethod::Path(target_path_str.clone())
                         } else {
                             LaunchMethod::File(target_path_str.clone())
                         }
                     } else {
                         LaunchMethod::File(target_path_str.clone())
-                    };
+                    }
+                } else {
+                    LaunchMethod::File(target_path_str.clone())
+                };
+                if self.should_skip_program(&show_name, &launch_method) {
+                    continue;
+                }
 
-                    // 再最后检查一下有没有本地化的名字
-                    let localized_name = localized_names.get(&file_name).cloned();
-                    if let Some(ref localized_name_str) = localized_name {
-                        let mut localized_alias = self.convert_search_keywords(localized_name_str);
-                        alias_names.append(&mut localized_alias);
+                // 基础别名：来自文件名本身
+                let mut alias_names: Vec<String> = self.convert_search_keywords(&show_name);
+                let unique_name = show_name.to_lowercase();
+
+                // 再最后检查一下有没有本地化的名字
+                let localized_name = localized_names.get(&file_name).cloned();
+                if let Some(ref localized_name_str) = localized_name {
+                    let mut localized_alias = self.convert_search_keywords(localized_name_str);
+                    alias_names.append(&mut localized_alias);
+                }
+                // 如果有本地化的名字，则使用本地化的名字
+                let show_name = localized_name.unwrap_or(show_name);
+
+                // 可执行文件版本信息中的名字，比如 `WINWORD.EXE` 的 "Microsoft Word"
+                let mut version_description = None;
+                if let Some(version_info) = self.read_program_version_info(target_path) {
+                    let fields = version_info.fields();
+                    for field in &fields {
+                        if field.eq_ignore_ascii_case(&show_name) {
+                            continue;
+                        }
+                        for keyword in self.convert_search_keywords(field) {
+                            if !alias_names.contains(&keyword) {
+                                alias_names.push(keyword);
+                            }
+                        }
                     }
-                    // 如果有本地化的名字，则使用本地化的名字
-                    let show_name = localized_name.unwrap_or(show_name);
-
-                    let program = self.create_program(
-                        show_name,
-                        unique_name,
-                        launch_method,
-                        alias_names,
-                        ImageIdentity::File(target_path_str),
-                    );
+                    version_description = Some(version_info.description());
+                }
+
+                let program = self.create_program_with_description(
+                    show_name,
+                    unique_name,
+                    launch_method,
+                    alias_names,
+                    ImageIdentity::File(target_path_str),
+                    version_description,
+                );
+
+                result.push(program);
+            }
+        }
+        result
+    }
 
-                    result.push(program);
+    /// 读取程序的版本信息，快捷方式读取它指向的文件
+    ///
+    /// 按可执行文件的路径与修改时间缓存，文件没有变化时重新扫描不会再次读取
+    fn read_program_version_info(&self, path: &Path) -> Option<PeVersionInfo> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        let target = match extension.as_str() {
+            "exe" => path.to_path_buf(),
+            "lnk" => {
+                // 损坏或者指向非文件目标的快捷方式很常见，不记录日志
+                let target = PathBuf::from(resolve_lnk_target_path(path.to_str()?).ok()?);
+                let is_exe = target
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+                if !is_exe {
+                    return None;
                 }
+                target
+            }
+            _ => return None,
+        };
+
+        let modified_time = fs::metadata(&target).and_then(|m| m.modified()).ok()?;
+        if let Some(cached) = self.version_info_cache.get(&target) {
+            if cached.0 == modified_time {
+                return cached.1.clone();
             }
         }
+        let version_info = read_pe_version_info(&target, self.version_language);
+        self.version_info_cache
+            .insert(target, (modified_time, version_info.clone()));
+        version_info
+    }
 
-        // 添加通过uwp找到的文件
-        result
+    /// 只处理文件监听器报告的变化路径，返回其中新出现的程序
+    ///
+    /// 路径需位于某个索引目录中，并满足该目录的深度、匹配模式、排除关键字以及全局禁止路径的要求。
+    /// 如果路径是一个文件夹（例如整个文件夹被移动进来），则在剩余深度内遍历它。
+    pub fn load_program_from_changed_paths(&self, paths: &[PathBuf]) -> Vec<Arc<Program>> {
+        let mut program_paths_str: Vec<String> = Vec::new();
+        for directory in &self.target_paths {
+            let root = Path::new(&directory.root_path);
+            if directory.root_path.is_empty() {
+                continue;
+            }
+            let checker = match PathChecker::new(
+                &directory.pattern,
+                &directory.pattern_type,
+                &directory.excluded_keywords,
+            ) {
+                Ok(checker) => Arc::new(checker),
+                Err(message) => {
+                    warn!("遇到错误: {}", message);
+                    continue;
+                }
+            };
+
+            for path in paths {
+                let Ok(relative) = path.strip_prefix(root) else {
+                    continue;
+                };
+                let depth = relative.components().count();
+                if depth == 0 || depth > directory.max_depth as usize {
+                    continue;
+                }
+                if !self.is_valid_path(path) {
+                    continue;
+                }
+                if path.is_dir() {
+                    let remaining_depth = directory.max_depth as usize - depth;
+                    let (new_paths, _) = self.recursive_visit_dir(
+                        path,
+                        remaining_depth,
+                        checker.clone(),
+                        ScanBudget::from_directory(directory),
+                    );
+                    program_paths_str.extend(new_paths);
+                } else if self.is_target_file(path, checker.clone()) {
+                    program_paths_str.push(path.to_string_lossy().into_owned());
+                }
+            }
+        }
+        program_paths_str.sort();
+        program_paths_str.dedup();
+        self.create_programs_from_files(program_paths_str)
+    }
+
+    /// 忘记这些程序文件的名字，使它们（或同名的文件）之后可以被重新加载
+    pub fn forget_program_paths(&self, paths: &[PathBuf]) {
+        for path in paths {
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                self.program_name_hash.remove(&stem.to_lowercase());
+            }
+        }
     }
 
     /// 添加所有的自定义命令
@@ -579,13 +1025,13 @@ impl ProgramLoaderInner {
             let show_name = key;
             // 不判断是不是被禁止的
             let check_name = "[命令]".to_string() + show_name;
-            if self.check_program_is_exist(&check_name) {
+            let launch_method = LaunchMethod::Command(command.clone());
+            if self.should_skip_program(&check_name, &launch_method) {
                 continue;
             }
 
             let unique_name = show_name.to_lowercase();
             let alias_names = self.convert_search_keywords(show_name);
-            let launch_method = LaunchMethod::Command(command.clone());
             let icon_path = match APP_PIC_PATH.get("terminal") {
                 Some(path) => path.value().clone(),
                 None => {
@@ -794,13 +1240,13 @@ impl ProgramLoaderInner {
                         let icon_path =
                             self.validate_icon_path(full_icon_path.to_string_lossy().into_owned());
 
-                        if self.check_program_is_exist(&short_name) {
+                        let launch_method = LaunchMethod::PackageFamilyName(app_id);
+                        if self.should_skip_program(&short_name, &launch_method) {
                             continue;
                         }
 
                         let unique_name = short_name.to_lowercase();
                         let alias_name = self.convert_search_keywords(&short_name);
-                        let launch_method = LaunchMethod::PackageFamilyName(app_id);
 
                         let program = self.create_program(
                             short_name,
@@ -938,76 +1384,373 @@ impl ProgramLoaderInner {
     /// 1. 路径本身有效
     /// 2. 没有被屏蔽
     fn is_valid_path(&self, path: &Path) -> bool {
-        if !path.exists() {
-            return false;
-        }
-
-        for str in &self.forbidden_paths {
-            if str.is_empty() {
-                continue;
-            }
-            let temp = Path::new(&str);
-            // 如果当前的路径以禁止路径开头
-            if path.starts_with(temp) {
-                return false;
-            }
-        }
-        true
+        is_valid_path(path, &self.forbidden_paths)
     }
 
     /// 判断一个目标文件是不是想要的
     fn is_target_file(&self, path: &Path, checker: Arc<PathChecker>) -> bool {
-        if !path.is_file() && !path.is_symlink() {
-            return false;
-        }
-
-        match path.file_name().and_then(|ext| ext.to_str()) {
-            Some(file_name) => checker.is_match(file_name),
-            None => {
-                warn!("无法获取文件名: {:?}", path);
-                false
-            }
-        }
+        is_target_file(path, &checker)
     }
 
     /// 递归遍历一个文件夹
-    /// 会自动跳过不可遍历的文件夹
-    /// 返回文件夹中所有的文件
+    /// 会自动跳过不可遍历的文件夹，超出预算后停止遍历
+    /// 返回文件夹中所有的目标文件与这次遍历的情况
     fn recursive_visit_dir(
         &self,
         dir: &Path,
         depth: usize,
         checker: Arc<PathChecker>,
-    ) -> Vec<String> {
-        // 注意：返回类型可以简化为 Vec<String>，因为 walkdir 的迭代器在内部处理错误
-        if !self.is_valid_path(dir) {
-            return Vec::new();
+        budget: ScanBudget,
+    ) -> (Vec<String>, RootScanStat) {
+        visit_dir(dir, depth, &checker, budget, &self.forbidden_paths)
+    }
+
+    /// 扫描一个根目录
+    ///
+    /// 设置了扫描时间时在单独的线程中遍历，超时后不再等待它（卡住的网络共享目录不会拖住索引过程），
+    /// 遍历线程也会在超时后自己停下来。这次遍历出来的部分结果不会使用，由注册表与排序器保留上一次扫描的结果，
+    /// 避免每次刷新得到不同的程序
+    fn scan_root(
+        &self,
+        directory: &DirectoryConfig,
+        checker: Arc<PathChecker>,
+    ) -> (Vec<String>, RootScanStat) {
+        let root = PathBuf::from(&directory.root_path);
+        let depth = directory.max_depth as usize;
+        let budget = ScanBudget::from_directory(directory);
+        let Some(timeout) = budget.timeout else {
+            return visit_dir(&root, depth, &checker, budget, &self.forbidden_paths);
+        };
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let forbidden_paths = self.forbidden_paths.clone();
+        std::thread::spawn(move || {
+            // 超时后接收端已经不在了，结果直接丢弃
+            let _ = sender.send(visit_dir(&root, depth, &checker, budget, &forbidden_paths));
+        });
+        match receiver.recv_timeout(timeout) {
+            // 遍历线程比这里先发现超时
+            Ok((_, stat)) if stat.timed_out => (Vec::new(), stat),
+            Ok(scanned) => scanned,
+            Err(e) => {
+                let timed_out = e == RecvTimeoutError::Timeout;
+                warn!(
+                    "扫描目录 {} 在 {} ms 内没有完成，保留上一次扫描的结果",
+                    directory.root_path,
+                    timeout.as_millis()
+                );
+                let stat = RootScanStat {
+                    root_path: directory.root_path.clone(),
+                    scan_time: timeout.as_secs_f64() * 1000.0,
+                    timed_out,
+                    failed: !timed_out,
+                    ..Default::default()
+                };
+                (Vec::new(), stat)
+            }
         }
+    }
+}
 
-        WalkDir::new(dir)
-            .max_depth(depth)
-            .into_iter()
-            // 使用 filter_entry 提前剪枝。如果目录无效，则不再深入
-            .filter_entry(|e| self.is_valid_path(e.path()))
-            // filter_map 用于处理 Result<DirEntry, Error>
-            .filter_map(|entry_result| {
-                match entry_result {
-                    Ok(entry) => Some(entry),
-                    Err(e) => {
-                        // 记录遍历过程中的错误，与原实现行为一致
-                        debug!("Error reading directory entry: {}", e);
-                        None
-                    }
+/// 判断是不是一个有效的路径：路径本身存在，并且没有被屏蔽
+fn is_valid_path(path: &Path, forbidden_paths: &[String]) -> bool {
+    if !path.exists() {
+        return false;
+    }
+
+    for str in forbidden_paths {
+        if str.is_empty() {
+            continue;
+        }
+        let temp = Path::new(&str);
+        // 如果当前的路径以禁止路径开头
+        if path.starts_with(temp) {
+            return false;
+        }
+    }
+    true
+}
+
+/// 判断一个目标文件是不是想要的
+fn is_target_file(path: &Path, checker: &PathChecker) -> bool {
+    if !path.is_file() && !path.is_symlink() {
+        return false;
+    }
+
+    match path.file_name().and_then(|ext| ext.to_str()) {
+        Some(file_name) => checker.is_match(file_name),
+        None => {
+            warn!("无法获取文件名: {:?}", path);
+            false
+        }
+    }
+}
+
+/// 递归遍历一个文件夹，按文件名的顺序遍历，所以超出遍历数量后得到的结果每次都相同
+fn visit_dir(
+    dir: &Path,
+    depth: usize,
+    checker: &PathChecker,
+    budget: ScanBudget,
+    forbidden_paths: &[String],
+) -> (Vec<String>, RootScanStat) {
+    let start = Instant::now();
+    let mut stat = RootScanStat {
+        root_path: dir.to_string_lossy().into_owned(),
+        ..Default::default()
+    };
+    let mut result = Vec::new();
+    if !is_valid_path(dir, forbidden_paths) {
+        // 被禁止的根目录本来就不会有程序，只有不存在的根目录才算扫描失败
+        stat.failed = !dir.exists();
+        return (result, stat);
+    }
+
+    // 超时后调用者已经不再等待这次扫描，遍历线程也要尽快停下来，不能一直在后台占用资源
+    let deadline = budget.timeout.map(|timeout| start + timeout);
+    let walker = WalkDir::new(dir)
+        .max_depth(depth)
+        .sort_by_file_name()
+        .into_iter()
+        // 使用 filter_entry 提前剪枝。如果目录无效，则不再深入
+        .filter_entry(|e| is_valid_path(e.path(), forbidden_paths));
+    for entry_result in walker {
+        if budget.is_exceeded(stat.visited_entries) {
+            warn!(
+                "扫描目录 {} 超出预算，已遍历 {} 项，停止扫描",
+                stat.root_path, stat.visited_entries
+            );
+            stat.exceeded_budget = true;
+            break;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            debug!(
+                "扫描目录 {} 超时，已遍历 {} 项，停止扫描",
+                stat.root_path, stat.visited_entries
+            );
+            stat.timed_out = true;
+            break;
+        }
+        stat.visited_entries += 1;
+        let entry = match entry_result {
+            Ok(entry) => entry,
+            Err(e) => {
+                // 记录遍历过程中的错误，与原实现行为一致
+                debug!("Error reading directory entry: {}", e);
+                // 根目录本身读取失败时，这次扫描的结果不能代表这个根目录中的程序
+                if e.depth() == 0 {
+                    stat.failed = true;
                 }
-            })
-            // 筛选出我们想要的目标文件
-            .filter(|entry| self.is_target_file(entry.path(), checker.clone()))
-            // 将路径转换为字符串
-            .map(|entry| entry.path().to_string_lossy().into_owned())
-            // 收集所有结果
-            .collect()
+                continue;
+            }
+        };
+        // 筛选出我们想要的目标文件
+        if is_target_file(entry.path(), checker) {
+            result.push(entry.path().to_string_lossy().into_owned());
+        }
+    }
+    stat.matched_files = result.len();
+    stat.scan_time = start.elapsed().as_secs_f64() * 1000.0;
+    (result, stat)
+}
+
+/// 扫描一个文件夹时的预算，避免一个巨大的网络共享目录拖住整个索引过程
+#[derive(Debug, Clone, Copy, Default)]
+struct ScanBudget {
+    /// 最多等待的时间，超时后放弃这次扫描
+    timeout: Option<Duration>,
+    /// 最多遍历的文件与文件夹数量，超出后停止遍历，保留已经找到的文件
+    max_entries: Option<u64>,
+}
+
+impl ScanBudget {
+    fn from_directory(directory: &DirectoryConfig) -> Self {
+        ScanBudget {
+            timeout: (directory.max_scan_millis > 0)
+                .then(|| Duration::from_millis(directory.max_scan_millis)),
+            max_entries: (directory.max_scan_entries > 0).then_some(directory.max_scan_entries),
+        }
+    }
+
+    fn is_exceeded(&self, visited_entries: u64) -> bool {
+        self.max_entries.is_some_and(|max| visited_entries >= max)
+    }
+}
+
+/// 一个根目录在上一次扫描时的情况
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RootScanStat {
+    pub root_path: String,
+    /// 遍历过的文件与文件夹数量
+    pub visited_entries: u64,
+    /// 符合条件的文件数量
+    pub matched_files: usize,
+    /// 扫描耗时，单位为毫秒
+    pub scan_time: f64,
+    /// 是否因为遍历的数量超出了预算而提前停止
+    pub exceeded_budget: bool,
+    /// 是否因为超时而放弃了这次扫描
+    pub timed_out: bool,
+    /// 根目录是否不存在、无法读取或者匹配规则无效
+    pub failed: bool,
+}
+
+impl RootScanStat {
+    /// 这次扫描有没有完整地遍历整个根目录
+    pub fn is_complete(&self) -> bool {
+        !self.failed && !self.is_truncated()
+    }
+
+    /// 这次扫描是不是因为超出预算而没有扫描完，根目录中没有扫描到的程序依然存在
+    pub fn is_truncated(&self) -> bool {
+        self.exceeded_budget || self.timed_out
+    }
+}
+
+/// 路径中的程序（开始菜单、桌面等用户配置的目录）
+/// 来源在加载时可以使用的加载器功能：生成关键字、跳过重复或被隐藏的程序、创建 `Program`
+pub struct ProgramSourceContext<'a> {
+    /// 内置的来源直接调用加载器中的扫描函数，其他来源只能通过下面的方法使用加载器
+    loader: &'a mut ProgramLoaderInner,
+}
+
+impl ProgramSourceContext<'_> {
+    /// 把名字转换为搜索关键字
+    pub fn convert_search_keywords(&self, name: &str) -> Vec<String> {
+        self.loader.convert_search_keywords(name)
+    }
+
+    /// 判断一个程序需不需要跳过（被用户隐藏了，或者同名的程序已经添加了），需要在创建程序之前调用
+    pub fn should_skip_program(&self, full_name: &str, launch_method: &LaunchMethod) -> bool {
+        self.loader.should_skip_program(full_name, launch_method)
+    }
+
+    /// 创建 `Program`，会分配 guid、添加用户设置的别名与固定权重
+    pub fn create_program(
+        &self,
+        show_name: String,
+        unique_name: String,
+        launch_method: LaunchMethod,
+        search_keywords: Vec<String>,
+        icon_path: ImageIdentity,
+    ) -> Arc<Program> {
+        self.loader.create_program(
+            show_name,
+            unique_name,
+            launch_method,
+            search_keywords,
+            icon_path,
+        )
+    }
+}
+
+#[derive(Debug)]
+struct PathSource;
+
+impl ProgramSource for PathSource {
+    fn id(&self) -> &'static str {
+        "path"
+    }
+
+    fn load(&mut self, context: &mut ProgramSourceContext) -> Vec<Arc<Program>> {
+        context.loader.load_program_from_path()
+    }
+}
+
+/// UWP 应用
+#[derive(Debug)]
+struct UwpSource;
+
+impl ProgramSource for UwpSource {
+    fn id(&self) -> &'static str {
+        "uwp"
+    }
+
+    fn is_enabled(&self, context: &ProgramSourceContext) -> bool {
+        context.loader.is_scan_uwp_programs
+    }
+
+    fn load(&mut self, context: &mut ProgramSourceContext) -> Vec<Arc<Program>> {
+        context.loader.load_uwp_program()
+    }
+}
+
+/// freedesktop 的 `.desktop` 文件
+#[derive(Debug)]
+struct DesktopEntrySource;
+
+impl ProgramSource for DesktopEntrySource {
+    fn id(&self) -> &'static str {
+        "desktop_entry"
+    }
+
+    fn is_enabled(&self, context: &ProgramSourceContext) -> bool {
+        context.loader.is_scan_desktop_entries
+    }
+
+    fn load(&mut self, context: &mut ProgramSourceContext) -> Vec<Arc<Program>> {
+        context.loader.load_desktop_entries()
+    }
+}
+
+/// 用户添加的网页
+#[derive(Debug)]
+struct WebPageSource;
+
+impl ProgramSource for WebPageSource {
+    fn id(&self) -> &'static str {
+        "web_page"
+    }
+
+    fn load(&mut self, context: &mut ProgramSourceContext) -> Vec<Arc<Program>> {
+        context.loader.load_web()
+    }
+}
+
+/// 浏览器书签
+#[derive(Debug)]
+struct BookmarkSource;
+
+impl ProgramSource for BookmarkSource {
+    fn id(&self) -> &'static str {
+        "bookmark"
+    }
+
+    fn is_enabled(&self, context: &ProgramSourceContext) -> bool {
+        !context.loader.bookmark_paths.is_empty()
+    }
+
+    fn load(&mut self, context: &mut ProgramSourceContext) -> Vec<Arc<Program>> {
+        context.loader.load_bookmarks()
     }
 }
+
+/// 用户添加的自定义命令
+#[derive(Debug)]
+struct CustomCommandSource;
+
+impl ProgramSource for CustomCommandSource {
+    fn id(&self) -> &'static str {
+        "custom_command"
+    }
+
+    fn load(&mut self, context: &mut ProgramSourceContext) -> Vec<Arc<Program>> {
+        context.loader.load_custom_command()
+    }
+}
+
+/// 内置的程序来源，顺序决定了名字相同时保留哪一个
+fn builtin_sources() -> Vec<Box<dyn ProgramSource>> {
+    vec![
+        Box::new(UwpSource),
+        Box::new(PathSource),
+        Box::new(DesktopEntrySource),
+        Box::new(WebPageSource),
+        Box::new(BookmarkSource),
+        Box::new(CustomCommandSource),
+    ]
+}
+
 #[derive(Debug)]
 pub struct ProgramLoader {
     inner: RwLock<ProgramLoaderInner>,
@@ -1062,11 +1805,73 @@ impl ProgramLoader {
         self.inner.write().load_program()
     }
 
+    /// 只加载文件监听器报告的变化路径中的程序
+    pub fn load_program_from_changed_paths(&self, paths: &[PathBuf]) -> Vec<Arc<Program>> {
+        self.inner.read().load_program_from_changed_paths(paths)
+    }
+
+    /// 使用快照中的程序创建 `Program`
+    pub fn restore_programs(&self, snapshot_programs: Vec<SnapshotProgram>) -> Vec<Arc<Program>> {
+        self.inner.read().restore_programs(snapshot_programs)
+    }
+
+    /// 忘记这些程序文件的名字
+    pub fn forget_program_paths(&self, paths: &[PathBuf]) {
+        self.inner.read().forget_program_paths(paths);
+    }
+
+    /// 隐藏一个程序，之后加载程序时会跳过它
+    pub fn hide_program(&self, launch_method: String, show_name: String) {
+        self.inner
+            .write()
+            .hidden_programs
+            .insert(launch_method, show_name);
+    }
+
+    /// 取消隐藏一个程序，返回它之前是否被隐藏
+    pub fn unhide_program(&self, launch_method: &str) -> bool {
+        self.inner
+            .write()
+            .hidden_programs
+            .remove(launch_method)
+            .is_some()
+    }
+
+    /// 获取被隐藏的程序: "launch_method.get_text()" -> 名字
+    pub fn get_hidden_programs(&self) -> HashMap<String, String> {
+        self.inner.read().hidden_programs.clone()
+    }
+
+    /// 获取所有索引目录的根路径
+    pub fn get_target_roots(&self) -> Vec<PathBuf> {
+        self.inner
+            .read()
+            .target_paths
+            .iter()
+            .map(|directory| PathBuf::from(&directory.root_path))
+            .collect()
+    }
+
     /// 获得加载时间
     pub fn get_loading_time(&self) -> f64 {
         self.inner.read().get_loading_time()
     }
 
+    /// 注册一个程序来源，在下一次加载程序时生效
+    pub fn register_source(&self, source: Box<dyn ProgramSource>) {
+        self.inner.write().register_source(source);
+    }
+
+    /// 获得每个来源在上一次加载时的数量与耗时
+    pub fn get_source_stats(&self) -> Vec<ProgramSourceStat> {
+        self.inner.read().get_source_stats()
+    }
+
+    /// 获得每个根目录在上一次扫描时的情况
+    pub fn get_root_scan_stats(&self) -> Vec<RootScanStat> {
+        self.inner.read().get_root_scan_stats()
+    }
+
     /// 将 `ProgramLoaderInner` 转换为 `PartialProgramLoaderConfig`
     pub fn to_partial(&self) -> PartialProgramLoaderConfig {
         self.inner.read().to_partial()
@@ -1076,4 +1881,355 @@ impl ProgramLoader {
     pub fn convert_search_keywords(&self, show_name: &str) -> Vec<String> {
         self.inner.write().convert_search_keywords(show_name)
     }
+
+    /// 将关键字中被命中的字符映射回 `show_name` 中的字符区间
+    pub fn map_match_to_show_name(
+        &self,
+        show_name: &str,
+        keyword: &str,
+        keyword_indices: &[usize],
+    ) -> Vec<(usize, usize)> {
+        self.inner
+            .read()
+            .map_match_to_show_name(show_name, keyword, keyword_indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_match_to_show_name() {
+        let loader = ProgramLoaderInner::new(Arc::new(SemanticManager::new(None, HashMap::new())));
+        let show_name = "Visual Studio Code";
+        let keywords = loader.convert_search_keywords(show_name);
+
+        // 小写名上的连续匹配
+        assert_eq!(
+            loader.map_match_to_show_name(show_name, &keywords[0], &[7, 8, 9]),
+            vec![(7, 10)]
+        );
+        // 大写字母缩写 "vsc"
+        assert_eq!(
+            loader.map_match_to_show_name(show_name, &keywords[3], &[0, 1, 2]),
+            vec![(0, 1), (7, 8), (14, 15)]
+        );
+
+        // 拼音与首字母都应映射回对应的汉字
+        let show_name = "微信";
+        let keywords = loader.convert_search_keywords(show_name);
+        assert_eq!(keywords[1], "weixin");
+        assert_eq!(
+            loader.map_match_to_show_name(show_name, &keywords[1], &[3, 4, 5]),
+            vec![(1, 2)]
+        );
+        assert_eq!(
+            loader.map_match_to_show_name(show_name, &keywords[2], &[0, 1]),
+            vec![(0, 2)]
+        );
+
+        // 无法映射的别名
+        assert!(loader
+            .map_match_to_show_name(show_name, "wechat", &[0, 1])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_scan_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.exe", "b.exe", "c.txt", "sub/d.exe"] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+        let loader = ProgramLoaderInner::new(Arc::new(SemanticManager::new(None, HashMap::new())));
+        let checker = Arc::new(
+            PathChecker::new(&vec!["*.exe".to_string()], &"Wildcard".to_string(), &[]).unwrap(),
+        );
+
+        let (paths, stat) =
+            loader.recursive_visit_dir(dir.path(), 2, checker.clone(), ScanBudget::default());
+        assert_eq!(paths.len(), 3);
+        assert_eq!(stat.matched_files, 3);
+        // 根目录本身、4 个文件与 1 个子文件夹
+        assert_eq!(stat.visited_entries, 6);
+        assert!(!stat.exceeded_budget);
+
+        let mut directory = DirectoryConfig::new(String::new(), 2);
+        directory.max_scan_entries = 2;
+        let (paths, stat) = loader.recursive_visit_dir(
+            dir.path(),
+            2,
+            checker.clone(),
+            ScanBudget::from_directory(&directory),
+        );
+        // 按文件名的顺序遍历，超出数量后得到的结果每次都相同
+        assert_eq!(stat.visited_entries, 2);
+        assert_eq!(
+            paths,
+            vec![dir.path().join("a.exe").to_string_lossy().to_string()]
+        );
+        assert!(stat.exceeded_budget);
+        assert!(stat.is_truncated());
+        assert!(!stat.is_complete());
+
+        // 在时间预算内扫描完成时与不限制时间的结果相同
+        let mut directory = DirectoryConfig::new(dir.path().to_string_lossy().to_string(), 2);
+        directory.pattern = vec!["*.exe".to_string()];
+        directory.max_scan_millis = 60_000;
+        let (paths, stat) = loader.scan_root(&directory, checker.clone());
+        assert_eq!(paths.len(), 3);
+        assert!(stat.is_complete());
+
+        // 超过时间预算后遍历自己停下来，不会在后台一直扫描
+        let (paths, stat) = loader.recursive_visit_dir(
+            dir.path(),
+            2,
+            checker.clone(),
+            ScanBudget {
+                timeout: Some(Duration::ZERO),
+                max_entries: None,
+            },
+        );
+        assert!(paths.is_empty());
+        assert_eq!(stat.visited_entries, 0);
+        assert!(stat.timed_out);
+        assert!(stat.is_truncated());
+
+        // 不存在的根目录算作扫描失败
+        let (paths, stat) = loader.recursive_visit_dir(
+            &dir.path().join("missing"),
+            2,
+            checker,
+            ScanBudget::default(),
+        );
+        assert!(paths.is_empty());
+        assert!(stat.failed);
+    }
+
+    #[test]
+    fn test_root_scan_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["full/a.exe", "full/b.exe", "large/c.exe", "large/d.exe"] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+        let root = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        let mut loader =
+            ProgramLoaderInner::new(Arc::new(SemanticManager::new(None, HashMap::new())));
+        loader.add_target_path(DirectoryConfig::new(root("full"), 2));
+        let mut large = DirectoryConfig::new(root("large"), 2);
+        large.max_scan_entries = 2;
+        loader.add_target_path(large);
+        loader.add_target_path(DirectoryConfig::new(root("missing"), 2));
+
+        let names: Vec<String> = loader
+            .load_program_from_path()
+            .iter()
+            .map(|program| program.show_name.clone())
+            .collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+
+        // 每个根目录按配置的顺序各自记录扫描的情况
+        let stats = loader.get_root_scan_stats();
+        assert_eq!(
+            stats
+                .iter()
+                .map(|stat| stat.root_path.clone())
+                .collect::<Vec<_>>(),
+            vec![root("full"), root("large"), root("missing")]
+        );
+        assert_eq!(stats[0].matched_files, 2);
+        assert_eq!(stats[0].visited_entries, 3);
+        assert!(stats[0].is_complete());
+        assert_eq!(stats[1].matched_files, 1);
+        assert!(stats[1].exceeded_budget);
+        assert!(!stats[1].failed);
+        assert!(stats[2].failed);
+        assert!(!stats[2].is_truncated());
+    }
+
+    #[test]
+    fn test_version_info_keywords() {
+        use super::super::pe_version::test_pe::{build_pe, version_resource};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("WINWORD.EXE");
+        let write_pe = |description: &str| {
+            let version = version_resource(&[
+                ("CompanyName", "Microsoft Corporation"),
+                ("FileDescription", description),
+                ("ProductName", "Microsoft Office"),
+            ]);
+            fs::write(&path, build_pe(&version, true)).unwrap();
+        };
+        write_pe("Microsoft Word");
+
+        let mut loader =
+            ProgramLoaderInner::new(Arc::new(SemanticManager::new(None, HashMap::new())));
+        loader.add_target_path(DirectoryConfig::new(
+            dir.path().to_string_lossy().to_string(),
+            1,
+        ));
+        let programs = loader.load_program_from_path();
+        assert_eq!(programs.len(), 1);
+        let keywords = &programs[0].search_keywords;
+        assert_eq!(programs[0].show_name, "WINWORD");
+        assert!(keywords.contains(&"microsoft word".to_string()));
+        assert!(keywords.contains(&"microsoft office".to_string()));
+        // 公司名不作为关键字，只出现在语义描述中
+        assert!(!keywords
+            .iter()
+            .any(|keyword| keyword.contains("corporation")));
+        assert_eq!(
+            loader
+                .read_program_version_info(&path)
+                .unwrap()
+                .description(),
+            "Microsoft Word，Microsoft Office，Microsoft Corporation"
+        );
+
+        // 文件没有变化时使用缓存的版本信息，修改时间变化后重新读取
+        let modified_time = fs::metadata(&path).unwrap().modified().unwrap();
+        write_pe("Word");
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified_time).unwrap();
+        let cached = loader.read_program_version_info(&path).unwrap();
+        assert_eq!(cached.file_description.as_deref(), Some("Microsoft Word"));
+        file.set_modified(modified_time + Duration::from_secs(60))
+            .unwrap();
+        let reloaded = loader.read_program_version_info(&path).unwrap();
+        assert_eq!(reloaded.file_description.as_deref(), Some("Word"));
+        assert_eq!(loader.version_info_cache.len(), 1);
+    }
+
+    #[test]
+    fn test_hidden_program_does_not_take_name() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a/tool.exe", "b/tool.exe"] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+        let hidden_path = dir.path().join("a").join("tool.exe");
+        let visible_path = dir.path().join("b").join("tool.exe");
+        let config = ProgramLoaderConfig::default();
+        config.update(PartialProgramLoaderConfig {
+            target_paths: Some(vec![DirectoryConfig::new(
+                dir.path().to_string_lossy().to_string(),
+                3,
+            )]),
+            is_scan_uwp_programs: Some(false),
+            is_scan_desktop_entries: Some(false),
+            index_web_pages: Some(Vec::new()),
+            custom_command: Some(Vec::new()),
+            hidden_programs: Some(HashMap::from([(
+                hidden_path.to_string_lossy().to_string(),
+                "tool".to_string(),
+            )])),
+            ..Default::default()
+        });
+
+        let loader = ProgramLoader::new(Arc::new(SemanticManager::new(None, HashMap::new())));
+        loader.load_from_config(&config);
+        // 被隐藏的程序在创建之前就被跳过，同名的另一个程序不会因为重名被丢掉
+        let programs = loader.load_program();
+        assert_eq!(programs.len(), 1);
+        assert_eq!(
+            programs[0].launch_method,
+            LaunchMethod::Path(visible_path.to_string_lossy().to_string())
+        );
+        // 名字空出来之后，被隐藏的程序依然不会被加载
+        loader.forget_program_paths(std::slice::from_ref(&visible_path));
+        assert!(loader
+            .load_program_from_changed_paths(&[hidden_path])
+            .is_empty());
+    }
+
+    /// 测试用的来源：把选项中的名字变成自定义命令
+    #[derive(Debug, Default)]
+    struct NameListSource {
+        names: Vec<String>,
+    }
+
+    impl ProgramSource for NameListSource {
+        fn id(&self) -> &'static str {
+            "name_list"
+        }
+
+        fn configure(&mut self, options: &serde_json::Value) {
+            self.names = serde_json::from_value(options["names"].clone()).unwrap_or_default();
+        }
+
+        fn load(&mut self, context: &mut ProgramSourceContext) -> Vec<Arc<Program>> {
+            self.names
+                .iter()
+                .filter(|name| {
+                    !context.should_skip_program(
+                        &format!("[name_list]{}", name),
+                        &LaunchMethod::Command(name.to_string()),
+                    )
+                })
+                .map(|name| {
+                    let keywords = context.convert_search_keywords(name);
+                    context.create_program(
+                        name.clone(),
+                        name.to_lowercase(),
+                        LaunchMethod::Command(name.clone()),
+                        keywords,
+                        ImageIdentity::File(String::new()),
+                    )
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_register_program_source() {
+        let config = ProgramLoaderConfig::default();
+        config.update(PartialProgramLoaderConfig {
+            target_paths: Some(Vec::new()),
+            is_scan_uwp_programs: Some(false),
+            is_scan_desktop_entries: Some(false),
+            index_web_pages: Some(Vec::new()),
+            custom_command: Some(vec![("记事本".to_string(), "notepad".to_string())]),
+            source_options: Some(HashMap::from([(
+                "name_list".to_string(),
+                serde_json::json!({ "names": ["alpha", "beta", "alpha"] }),
+            )])),
+            ..Default::default()
+        });
+
+        let loader = ProgramLoader::new(Arc::new(SemanticManager::new(None, HashMap::new())));
+        loader.load_from_config(&config);
+        loader.register_source(Box::<NameListSource>::default());
+        let names: Vec<String> = loader
+            .load_program()
+            .iter()
+            .map(|program| program.show_name.clone())
+            .collect();
+        assert_eq!(names, vec!["记事本", "alpha", "beta"]);
+
+        let stats = loader.get_source_stats();
+        let stat = |id: &str| stats.iter().find(|stat| stat.id == id).unwrap().clone();
+        assert!(!stat("uwp").enabled);
+        assert_eq!(stat("custom_command").item_count, 1);
+        assert_eq!(stat("name_list").item_count, 2);
+
+        // 用户关闭的来源不会被加载
+        config.update(PartialProgramLoaderConfig {
+            disabled_sources: Some(vec!["custom_command".to_string()]),
+            ..Default::default()
+        });
+        loader.load_from_config(&config);
+        let names: Vec<String> = loader
+            .load_program()
+            .iter()
+            .map(|program| program.show_name.clone())
+            .collect();
+        assert_eq!(names, vec!["alpha", "beta"]);
+        assert!(!loader.get_source_stats()[5].enabled);
+    }
 }
@@ -2,22 +2,190 @@ use crate::error::OptionExt;
 use crate::program_manager::config::program_ranker_config::PartialProgramRankerConfig;
 use crate::program_manager::config::program_ranker_config::ProgramRankerConfig;
 use crate::program_manager::config::program_ranker_config::QueryAffinityData;
+use crate::program_manager::config::program_ranker_config::QueryPin;
+use crate::program_manager::config::program_ranker_config::UsageImportState;
+use crate::program_manager::search_model::remove_repeated_space;
 use crate::program_manager::LaunchMethod;
 use crate::utils::dashmap_to_hashmap;
+use crate::utils::get_current_hour_of_week;
 use crate::utils::hashmap_to_dashmap;
 use crate::utils::is_date_current;
 use crate::utils::{generate_current_date, get_current_time};
 use dashmap::DashMap;
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
-use std::collections::{HashMap, VecDeque};
-use tracing::debug;
+use std::collections::{HashMap, HashSet, VecDeque};
+use tracing::{debug, info};
+
+/// 一周中的小时数，使用时段的统计按小时分桶
+const HOURS_PER_WEEK: usize = 168;
+
+/// 一个程序的使用时段统计的总次数超过这个值时，所有桶减半，让新的使用习惯可以逐渐取代旧的
+const HOUR_OF_WEEK_USAGE_CAP: u64 = 400;
+
+/// 查询是已记录的查询的前缀时，每少一个字符，亲和度乘以这个系数
+const PREFIX_AFFINITY_DECAY: f64 = 0.8;
+
+/// 查询与已记录的查询只差一个字符（输错、漏打、多打）时的亲和度系数
+const NEAR_QUERY_AFFINITY_FACTOR: f64 = 0.7;
+
+/// 近似匹配要求两个查询都至少有这么多字符，太短的查询差一个字符就是另一个意思了
+const NEAR_QUERY_MIN_CHARS: usize = 3;
+
+/// 每个程序最多保留的查询数量
+const MAX_QUERIES_PER_PROGRAM: usize = 16;
+
+/// 超过这么多天没有在这个查询下启动过的记录会在整理时被清理（与可以调整的衰减常数无关）
+const MAX_AFFINITY_AGE_DAYS: i64 = 90;
+
+/// 一天的秒数
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 从其他启动器导入的一条记录最多计入这么多次启动，避免在别处长年累积的次数压过之后真实的使用
+const MAX_IMPORTED_LAUNCHES: u64 = 30;
+
+/// 每个程序最多记录这么多个“下一个启动的程序”，超出时丢弃次数最少的
+const MAX_TRANSITION_TARGETS: usize = 8;
+
+/// 一个程序之后的启动次数总和超过这个值时，把所有次数减半，让旧的习惯逐渐淡出
+const TRANSITION_COUNT_CAP: u64 = 200;
+
+/// 至少先后启动过这么多次才会作为建议，避免偶然的一次就被推荐
+const MIN_SUGGESTION_TRANSITIONS: u64 = 2;
+
+/// 智能排序对一个程序的各项加分（已乘以对应的权重）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RankerScoreComponents {
+    /// 历史启动次数
+    pub history: f64,
+    /// 近期使用习惯
+    pub recent_habit: f64,
+    /// 短期热度
+    pub temporal: f64,
+    /// 查询亲和度
+    pub query_affinity: f64,
+    /// 使用时段
+    pub time_of_day: f64,
+}
+
+impl RankerScoreComponents {
+    /// 所有加分的总和
+    pub fn total(&self) -> f64 {
+        self.history + self.recent_habit + self.temporal + self.query_affinity + self.time_of_day
+    }
+
+    /// 将未加权的各项分数乘以对应的权重
+    pub fn weighted(&self, weights: &RankerWeights) -> RankerScoreComponents {
+        RankerScoreComponents {
+            history: self.history * weights.history,
+            recent_habit: self.recent_habit * weights.recent_habit,
+            temporal: self.temporal * weights.temporal,
+            query_affinity: self.query_affinity * weights.query_affinity,
+            time_of_day: self.time_of_day * weights.time_of_day,
+        }
+    }
+}
+
+/// 智能排序各项加分的权重
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RankerWeights {
+    pub history: f64,
+    pub recent_habit: f64,
+    pub temporal: f64,
+    pub query_affinity: f64,
+    pub time_of_day: f64,
+}
+
+impl RankerWeights {
+    /// 只包含权重的部分配置，用于把拟合出来的权重写回配置
+    pub fn to_partial_config(&self) -> PartialProgramRankerConfig {
+        PartialProgramRankerConfig {
+            history_weight: Some(self.history),
+            recent_habit_weight: Some(self.recent_habit),
+            temporal_weight: Some(self.temporal),
+            query_affinity_weight: Some(self.query_affinity),
+            time_of_day_weight: Some(self.time_of_day),
+            ..Default::default()
+        }
+    }
+}
+
+/// 一条查询-启动记录：用户输入 `query` 之后启动了 `program_guid`，累计 `launch_count` 次
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryLaunchRecord {
+    pub query: String,
+    pub program_guid: u64,
+    pub launch_count: u64,
+    /// 最近一次启动的时间（时间戳），未知时为 0
+    pub last_launch_time: i64,
+}
+
+/// 一次清理排序器统计数据的结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RankerGcReport {
+    /// 清理的时间（时间戳）
+    pub time: i64,
+    /// 连续 `prune_after_absent_refreshes` 次刷新都不在程序注册表中而被清理的启动方式
+    pub absent: Vec<String>,
+    /// 太久没有启动而被清理的启动方式
+    pub expired: Vec<String>,
+    /// 超过数量上限而被清理的启动方式（优先清理不在注册表中的、最久没有启动的）
+    pub over_limit: Vec<String>,
+    /// 清理后仍然保存着统计数据的启动方式的数量
+    pub remaining: usize,
+}
+
+impl RankerGcReport {
+    /// 被清理的启动方式的总数
+    pub fn pruned_count(&self) -> usize {
+        self.absent.len() + self.expired.len() + self.over_limit.len()
+    }
+}
+
+/// 这次刷新没有办法确认是否还存在的启动方式，它们不在注册表中时不算缺席
+#[derive(Debug, Clone, Default)]
+pub struct UnverifiedPrograms {
+    /// 被用户隐藏的启动方式
+    pub launch_methods: HashSet<String>,
+    /// 扫描失败或者被截断的根目录
+    pub roots: Vec<String>,
+}
+
+impl UnverifiedPrograms {
+    /// 一个启动方式是否无法确认
+    pub fn contains(&self, method_text: &str) -> bool {
+        self.launch_methods.contains(method_text) || self.is_in_roots(method_text)
+    }
+
+    /// 一个启动方式是否在这些根目录中
+    pub fn is_in_roots(&self, method_text: &str) -> bool {
+        let method_path = normalize_path_text(method_text);
+        self.roots.iter().any(|root| {
+            let root = normalize_path_text(root);
+            !root.is_empty()
+                && method_path
+                    .strip_prefix(&root)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('\\'))
+        })
+    }
+}
+
+/// 统一路径的大小写与分隔符，去掉末尾的分隔符
+fn normalize_path_text(path: &str) -> String {
+    path.to_lowercase()
+        .replace('/', "\\")
+        .trim_end_matches('\\')
+        .to_string()
+}
 
 /// 程序排序器内部实现
 #[derive(Debug)]
 struct ProgramRankerInner {
     /// 程序映射表（用于获取启动方式的文本）
     launch_store: DashMap<u64, LaunchMethod>,
+    /// `launch_store` 的反向索引: launch_method_text -> 程序的guid
+    guid_by_method: HashMap<String, u64>,
     /// 最近7天的启动次数记录
     launch_time: VecDeque<DashMap<String, u64>>,
     /// 历史总启动次数
@@ -28,17 +196,42 @@ struct ProgramRankerInner {
     latest_launch_time: DashMap<String, i64>,
     /// 运行时的启动时间排序集合: (上一次启动的时间, 目标程序的guid)
     runtime_latest_launch_time: BTreeSet<(i64, u64)>,
-    /// 查询亲和度映射: (查询词, launch_method_text) -> QueryAffinityData
-    query_affinity_map: DashMap<(String, String), QueryAffinityData>,
+    /// 查询亲和度映射: launch_method_text -> { 规范化后的查询词 -> QueryAffinityData }
+    ///
+    /// 按程序分组，这样计算一个程序的亲和度时只需要遍历这个程序的少量查询
+    query_affinity_map: DashMap<String, HashMap<String, QueryAffinityData>>,
+    /// 按一周中的小时统计的启动次数: launch_method_text -> [HOURS_PER_WEEK 个桶]
+    hour_of_week_usage: DashMap<String, Vec<u64>>,
+    /// 从其他启动器导入过的使用记录: 来源 -> 导入的情况
+    usage_imports: HashMap<String, UsageImportState>,
+    /// 启动顺序统计: 先启动的 launch_method_text -> { 紧接着启动的 launch_method_text -> 次数 }
+    launch_transitions: HashMap<String, HashMap<String, u64>>,
+    /// 由 `launch_transitions` 得到的建议顺序: 先启动的 launch_method_text -> [可以建议的 launch_method_text]
+    ///
+    /// 记录启动时更新，打开搜索栏时直接使用，不需要每次重新排序
+    suggested_transitions: HashMap<String, Vec<String>>,
+    /// 两次启动间隔在这个时间(秒)以内才算作先后启动
+    transition_window: i64,
+    /// 固定在查询结果最前面的程序
+    query_pins: Vec<QueryPin>,
     /// 权重配置
     history_weight: f64,
     recent_habit_weight: f64,
     temporal_weight: f64,
     query_affinity_weight: f64,
+    time_of_day_weight: f64,
     query_affinity_time_decay: i64,
     temporal_decay: i64,
     /// 是否启用排序算法
     is_enable: bool,
+    /// 不在程序注册表中的启动方式已经连续缺席了多少次刷新
+    absent_refreshes: HashMap<String, u32>,
+    /// 统计数据的保留策略，为 0 时表示不启用对应的规则
+    max_tracked_programs: usize,
+    max_launch_age_days: u32,
+    prune_after_absent_refreshes: u32,
+    /// 上一次清理的结果
+    last_gc_report: RankerGcReport,
 }
 
 impl ProgramRankerInner {
@@ -47,25 +240,38 @@ impl ProgramRankerInner {
         deque.push_front(DashMap::new());
         ProgramRankerInner {
             launch_store: DashMap::new(),
+            guid_by_method: HashMap::new(),
             launch_time: deque,
             history_launch_time: DashMap::new(),
             last_update_data: generate_current_date(),
             latest_launch_time: DashMap::new(),
             runtime_latest_launch_time: BTreeSet::new(),
             query_affinity_map: DashMap::new(),
+            hour_of_week_usage: DashMap::new(),
+            usage_imports: HashMap::new(),
+            launch_transitions: HashMap::new(),
+            suggested_transitions: HashMap::new(),
+            transition_window: 900,
+            query_pins: Vec::new(),
             history_weight: 1.2,
             recent_habit_weight: 2.5,
             temporal_weight: 0.8,
             query_affinity_weight: 3.5,
+            time_of_day_weight: 1.0,
             query_affinity_time_decay: 259200,
             temporal_decay: 10800,
             is_enable: true,
+            absent_refreshes: HashMap::new(),
+            max_tracked_programs: 3000,
+            max_launch_age_days: 365,
+            prune_after_absent_refreshes: 100,
+            last_gc_report: RankerGcReport::default(),
         }
     }
 
+    /// 加载配置数据，已注册的程序保持不变
     fn load_from_config(&mut self, config: &ProgramRankerConfig) {
         self.launch_time.clear();
-        self.launch_store.clear();
         let launch_info = config.get_launch_info();
         launch_info.iter().for_each(|k| {
             let dash_map = hashmap_to_dashmap(k);
@@ -80,26 +286,62 @@ impl ProgramRankerInner {
         self.latest_launch_time.clear();
         self.latest_launch_time = hashmap_to_dashmap(&config.get_latest_launch_time());
 
-        self.runtime_latest_launch_time.clear();
-
-        // 加载查询亲和度数据
+        // 加载查询亲和度数据，旧版本按原样记录的查询在这里规范化并合并
         self.query_affinity_map.clear();
         let query_affinity_store = config.get_query_affinity_store();
         for (query, method_map) in query_affinity_store {
+            let query = normalize_affinity_query(&query);
+            if query.is_empty() {
+                continue;
+            }
             for (method_text, data) in method_map {
-                self.query_affinity_map
-                    .insert((query.clone(), method_text), data);
+                let mut entries = self.query_affinity_map.entry(method_text).or_default();
+                match entries.get_mut(&query) {
+                    Some(existing) => {
+                        existing.total_launch_count += data.total_launch_count;
+                        existing.last_launch_time =
+                            existing.last_launch_time.max(data.last_launch_time);
+                    }
+                    None => {
+                        entries.insert(query.clone(), data);
+                    }
+                }
             }
         }
 
+        // 加载使用时段统计，丢弃桶数不对的数据
+        self.hour_of_week_usage.clear();
+        for (method_text, usage) in config.get_hour_of_week_usage() {
+            if usage.len() == HOURS_PER_WEEK {
+                self.hour_of_week_usage.insert(method_text, usage);
+            }
+        }
+
+        self.launch_transitions = config.get_launch_transitions();
+        self.suggested_transitions = self
+            .launch_transitions
+            .iter()
+            .map(|(from, targets)| (from.clone(), rank_transition_targets(targets)))
+            .collect();
+        self.usage_imports = config.get_usage_imports();
+        self.transition_window = config.get_transition_window();
+        self.query_pins = config.get_query_pins();
+
         // 加载权重配置
         self.history_weight = config.get_history_weight();
         self.recent_habit_weight = config.get_recent_habit_weight();
         self.temporal_weight = config.get_temporal_weight();
         self.query_affinity_weight = config.get_query_affinity_weight();
+        self.time_of_day_weight = config.get_time_of_day_weight();
         self.query_affinity_time_decay = config.get_query_affinity_time_decay();
         self.temporal_decay = config.get_temporal_decay();
         self.is_enable = config.get_is_enable();
+
+        // 加载保留策略
+        self.absent_refreshes = config.get_absent_refreshes();
+        self.max_tracked_programs = config.get_max_tracked_programs();
+        self.max_launch_age_days = config.get_max_launch_age_days();
+        self.prune_after_absent_refreshes = config.get_prune_after_absent_refreshes();
     }
 
     fn get_runtime_data(&mut self) -> PartialProgramRankerConfig {
@@ -110,16 +352,17 @@ impl ProgramRankerInner {
             launch_info_data.push_back(dashmap_to_hashmap(item));
         }
 
-        // 导出查询亲和度数据
+        // 导出查询亲和度数据（保存的格式仍然以查询词为第一层）
         let mut query_affinity_store: HashMap<String, HashMap<String, QueryAffinityData>> =
             HashMap::new();
         for entry in self.query_affinity_map.iter() {
-            let (query, method_text) = entry.key();
-            let data = entry.value();
-            query_affinity_store
-                .entry(query.clone())
-                .or_default()
-                .insert(method_text.clone(), data.clone());
+            let method_text = entry.key();
+            for (query, data) in entry.value() {
+                query_affinity_store
+                    .entry(query.clone())
+                    .or_default()
+                    .insert(method_text.clone(), data.clone());
+            }
         }
 
         PartialProgramRankerConfig {
@@ -128,13 +371,23 @@ impl ProgramRankerInner {
             last_update_data: Some(generate_current_date()),
             latest_launch_time: Some(dashmap_to_hashmap(&self.latest_launch_time)),
             query_affinity_store: Some(query_affinity_store),
+            hour_of_week_usage: Some(dashmap_to_hashmap(&self.hour_of_week_usage)),
+            absent_refreshes: Some(self.absent_refreshes.clone()),
+            launch_transitions: Some(self.launch_transitions.clone()),
+            usage_imports: Some(self.usage_imports.clone()),
+            query_pins: None,
             history_weight: None,
             recent_habit_weight: None,
             temporal_weight: None,
             query_affinity_weight: None,
+            time_of_day_weight: None,
             query_affinity_time_decay: None,
             temporal_decay: None,
+            transition_window: None,
             is_enable: None,
+            max_tracked_programs: None,
+            max_launch_age_days: None,
+            prune_after_absent_refreshes: None,
         }
     }
 
@@ -142,6 +395,7 @@ impl ProgramRankerInner {
         debug!("register: {} {}", program_guid, launch_method.get_text());
         let key = launch_method.get_text();
         self.launch_store.insert(program_guid, launch_method);
+        self.guid_by_method.insert(key.clone(), program_guid);
 
         self.latest_launch_time.entry(key.clone()).or_insert(0);
 
@@ -153,6 +407,28 @@ impl ProgramRankerInner {
             });
     }
 
+    /// 注销一个程序
+    fn unregister_program(&mut self, program_guid: u64) {
+        if let Some((_, launch_method)) = self.launch_store.remove(&program_guid) {
+            let key = launch_method.get_text();
+            debug!("unregister: {} {}", program_guid, key);
+            if self.guid_by_method.get(&key) == Some(&program_guid) {
+                self.guid_by_method.remove(&key);
+            }
+        }
+    }
+
+    /// 根据当前已注册的程序重建运行时的启动时间排序集合
+    fn rebuild_runtime_latest_launch_time(&mut self) {
+        self.runtime_latest_launch_time.clear();
+        for entry in self.launch_store.iter() {
+            let key = entry.value().get_text();
+            let latest_launch_time = *self.latest_launch_time.entry(key).or_insert(0);
+            self.runtime_latest_launch_time
+                .insert((latest_launch_time, *entry.key()));
+        }
+    }
+
     /// 记录程序启动，更新所有统计数据
     fn record_launch(&mut self, program_guid: u64) {
         let launch_method = self
@@ -161,6 +437,23 @@ impl ProgramRankerInner {
             .expect_programming("Program GUID should exist in launch store");
 
         let method_text = launch_method.get_text();
+        drop(launch_method);
+        let current_time = get_current_time();
+
+        // 与上一个启动的程序间隔足够短时，记录为先后启动
+        let previous_text = self
+            .runtime_latest_launch_time
+            .last()
+            .filter(|(previous_time, previous_guid)| {
+                *previous_guid != program_guid
+                    && *previous_time > 0
+                    && current_time - *previous_time <= self.transition_window
+            })
+            .and_then(|(_, previous_guid)| self.launch_store.get(previous_guid))
+            .map(|previous_method| previous_method.get_text());
+        if let Some(previous_text) = previous_text {
+            self.record_transition(&previous_text, &method_text);
+        }
 
         // 更新今日启动次数
         self.launch_time[0]
@@ -174,6 +467,17 @@ impl ProgramRankerInner {
             .and_modify(|count| *count += 1)
             .or_insert(1);
 
+        // 更新使用时段统计
+        let mut usage = self
+            .hour_of_week_usage
+            .entry(method_text.clone())
+            .or_insert_with(|| vec![0; HOURS_PER_WEEK]);
+        usage[get_current_hour_of_week() % HOURS_PER_WEEK] += 1;
+        if usage.iter().sum::<u64>() > HOUR_OF_WEEK_USAGE_CAP {
+            usage.iter_mut().for_each(|count| *count /= 2);
+        }
+        drop(usage);
+
         // 更新启动的时间
         self.latest_launch_time
             .entry(method_text)
@@ -182,13 +486,244 @@ impl ProgramRankerInner {
                 assert!(self
                     .runtime_latest_launch_time
                     .remove(&(*last_launch_time, program_guid)));
-                let current_time = get_current_time();
                 *last_launch_time = current_time;
                 self.runtime_latest_launch_time
                     .insert((current_time, program_guid));
             });
     }
 
+    /// 记录一次先后启动
+    fn record_transition(&mut self, from: &str, to: &str) {
+        let targets = self.launch_transitions.entry(from.to_string()).or_default();
+        *targets.entry(to.to_string()).or_insert(0) += 1;
+
+        if targets.len() > MAX_TRANSITION_TARGETS {
+            if let Some(weakest) = targets
+                .iter()
+                .filter(|(target, _)| target.as_str() != to)
+                .min_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(target, _)| target.clone())
+            {
+                targets.remove(&weakest);
+            }
+        }
+        if targets.values().sum::<u64>() > TRANSITION_COUNT_CAP {
+            targets.values_mut().for_each(|count| *count /= 2);
+            targets.retain(|_, count| *count > 0);
+        }
+        let ranked = rank_transition_targets(targets);
+        self.suggested_transitions.insert(from.to_string(), ranked);
+    }
+
+    /// 根据上一个启动的程序建议接下来可能启动的程序
+    ///
+    /// 只有上一次启动还在 `transition_window` 以内时才有建议，按先后启动的次数降序排列
+    fn get_suggested_next_programs(&self, program_count: usize, current_time: i64) -> Vec<u64> {
+        if !self.is_enable || self.transition_window <= 0 {
+            return Vec::new();
+        }
+        let Some(&(last_time, last_guid)) = self.runtime_latest_launch_time.last() else {
+            return Vec::new();
+        };
+        if last_time == 0 || current_time - last_time > self.transition_window {
+            return Vec::new();
+        }
+        let Some(last_method) = self.launch_store.get(&last_guid) else {
+            return Vec::new();
+        };
+        let Some(targets) = self.suggested_transitions.get(&last_method.get_text()) else {
+            return Vec::new();
+        };
+
+        targets
+            .iter()
+            .filter_map(|target| self.guid_by_method.get(target).copied())
+            .filter(|guid| *guid != last_guid)
+            .take(program_count)
+            .collect()
+    }
+
+    /// 获取固定在这个查询最前面的程序
+    ///
+    /// 完全匹配的固定项排在前缀匹配的前面，同一类中按固定项在列表中的顺序排列
+    fn get_pinned_programs(&self, query: &str) -> Vec<u64> {
+        let query = normalize_affinity_query(query);
+        let mut pins: Vec<&QueryPin> = self
+            .query_pins
+            .iter()
+            .filter(|pin| pin_matches(pin, &query))
+            .collect();
+        if pins.is_empty() {
+            return Vec::new();
+        }
+        pins.sort_by_key(|pin| pin.query != query);
+
+        let mut result: Vec<u64> = Vec::new();
+        for pin in pins {
+            if let Some(guid) = self.guid_by_method.get(&pin.launch_method) {
+                if !result.contains(guid) {
+                    result.push(*guid);
+                }
+            }
+        }
+        result
+    }
+
+    /// 按保留策略清理统计数据，在每次刷新程序注册表之后调用
+    ///
+    /// 1. 连续 `prune_after_absent_refreshes` 次刷新都不在注册表中的启动方式（程序多半已经被卸载了）
+    /// 2. 超过 `max_launch_age_days` 天没有启动过的启动方式
+    /// 3. 剩下的启动方式超过 `max_tracked_programs` 个时，优先清理不在注册表中的、最久没有启动的
+    ///
+    /// `unverified` 中的启动方式（被隐藏的、所在的根目录没有扫描完整的）不在注册表中时不算缺席，
+    /// 也不会因为缺席而被清理
+    ///
+    /// 缺席按刷新的次数而不是天数计算：电脑关机或者很久没有运行时不会累积缺席，
+    /// 程序在下一次刷新中出现时计数清零
+    fn collect_garbage(
+        &mut self,
+        current_time: i64,
+        unverified: &UnverifiedPrograms,
+    ) -> RankerGcReport {
+        let registered: HashSet<String> = self
+            .launch_store
+            .iter()
+            .map(|entry| entry.value().get_text())
+            .collect();
+
+        // 保存着统计数据的启动方式 -> 最近一次启动的时间（未知时为 0）
+        let mut tracked: HashMap<String, i64> = HashMap::new();
+        let mut track = |method_text: &String| {
+            tracked.entry(method_text.clone()).or_insert(0);
+        };
+        for day in &self.launch_time {
+            day.iter().for_each(|entry| track(entry.key()));
+        }
+        self.history_launch_time
+            .iter()
+            .for_each(|entry| track(entry.key()));
+        self.query_affinity_map
+            .iter()
+            .for_each(|entry| track(entry.key()));
+        self.hour_of_week_usage
+            .iter()
+            .for_each(|entry| track(entry.key()));
+        self.launch_transitions.keys().for_each(&mut track);
+        for entry in self.latest_launch_time.iter() {
+            // 已注册但从没启动过的程序也有一条为 0 的记录，它不算统计数据
+            if *entry.value() > 0 || !registered.contains(entry.key()) {
+                tracked.insert(entry.key().clone(), *entry.value());
+            }
+        }
+
+        // 更新连续缺席的次数，无法确认的启动方式这一次不计数
+        self.absent_refreshes
+            .retain(|method_text, _| tracked.contains_key(method_text));
+        for method_text in tracked.keys() {
+            if registered.contains(method_text) {
+                self.absent_refreshes.remove(method_text);
+            } else if !unverified.contains(method_text) {
+                *self
+                    .absent_refreshes
+                    .entry(method_text.clone())
+                    .or_insert(0) += 1;
+            }
+        }
+
+        let mut report = RankerGcReport {
+            time: current_time,
+            ..Default::default()
+        };
+        if self.prune_after_absent_refreshes > 0 {
+            report.absent = self
+                .absent_refreshes
+                .iter()
+                .filter(|(method_text, count)| {
+                    **count >= self.prune_after_absent_refreshes
+                        && !unverified.contains(method_text)
+                })
+                .map(|(method_text, _)| method_text.clone())
+                .collect();
+        }
+        for method_text in &report.absent {
+            tracked.remove(method_text);
+        }
+
+        if self.max_launch_age_days > 0 {
+            let max_age = self.max_launch_age_days as i64 * SECONDS_PER_DAY;
+            report.expired = tracked
+                .iter()
+                .filter(|(_, latest)| **latest > 0 && current_time - **latest > max_age)
+                .map(|(method_text, _)| method_text.clone())
+                .collect();
+        }
+        for method_text in &report.expired {
+            tracked.remove(method_text);
+        }
+
+        if self.max_tracked_programs > 0 && tracked.len() > self.max_tracked_programs {
+            let mut candidates: Vec<(bool, i64, String)> = tracked
+                .iter()
+                .map(|(method_text, latest)| {
+                    (
+                        registered.contains(method_text),
+                        *latest,
+                        method_text.clone(),
+                    )
+                })
+                .collect();
+            candidates.sort();
+            let excess = tracked.len() - self.max_tracked_programs;
+            report.over_limit = candidates
+                .into_iter()
+                .take(excess)
+                .map(|(_, _, method_text)| method_text)
+                .collect();
+        }
+        for method_text in &report.over_limit {
+            tracked.remove(method_text);
+        }
+
+        for method_text in report
+            .absent
+            .iter()
+            .chain(&report.expired)
+            .chain(&report.over_limit)
+        {
+            self.forget_launch_method(method_text);
+        }
+        report.absent.sort();
+        report.expired.sort();
+        report.over_limit.sort();
+        report.remaining = tracked.len();
+        report
+    }
+
+    /// 删除一个启动方式的所有统计数据
+    fn forget_launch_method(&mut self, method_text: &str) {
+        for day in &self.launch_time {
+            day.remove(method_text);
+        }
+        self.history_launch_time.remove(method_text);
+        self.latest_launch_time.remove(method_text);
+        self.query_affinity_map.remove(method_text);
+        self.hour_of_week_usage.remove(method_text);
+        self.absent_refreshes.remove(method_text);
+        for state in self.usage_imports.values_mut() {
+            state.launch_counts.remove(method_text);
+        }
+        self.launch_transitions.remove(method_text);
+        self.launch_transitions.retain(|_, targets| {
+            targets.remove(method_text);
+            !targets.is_empty()
+        });
+        self.suggested_transitions.remove(method_text);
+        self.suggested_transitions.retain(|from, targets| {
+            targets.retain(|target| target != method_text);
+            self.launch_transitions.contains_key(from)
+        });
+    }
+
     /// 获得启动器维护的数据
     pub fn get_latest_launch_program(&self, program_count: u32) -> Vec<u64> {
         let mut result = Vec::new();
@@ -204,16 +739,21 @@ impl ProgramRankerInner {
     }
 
     /// 计算近期习惯分数 (基于最近7天的启动次数，带衰减)
-    fn calculate_recent_habit_score(&self, program_guid: u64) -> f64 {
+    ///
+    /// `excluded_launches` 次启动不计入，不知道它们发生在哪一天，所以从权重最高的最近几天扣除
+    fn calculate_recent_habit_score(&self, program_guid: u64, excluded_launches: u64) -> f64 {
         let program_string = self
             .launch_store
             .get(&program_guid)
             .expect_programming("Program GUID should exist in launch store");
         let mut result: f64 = 0.0;
         let mut k: f64 = 1.0;
+        let mut remaining_excluded = excluded_launches;
         self.launch_time.iter().for_each(|day| {
             if let Some(time) = day.get(&program_string.get_text()) {
-                result += (*time as f64) * k;
+                let excluded = remaining_excluded.min(*time);
+                remaining_excluded -= excluded;
+                result += ((*time - excluded) as f64) * k;
             }
             k /= 1.3
         });
@@ -234,16 +774,20 @@ impl ProgramRankerInner {
 
     /// 记录查询-程序启动关联
     fn record_query_launch(&mut self, query: &str, program_guid: u64) {
+        let query = normalize_affinity_query(query);
+        if query.is_empty() {
+            return;
+        }
         let current_time = get_current_time();
         let launch_method = self
             .launch_store
             .get(&program_guid)
             .expect_programming("Program GUID should exist in launch store");
         let method_text = launch_method.get_text();
-        let key = (query.to_string(), method_text);
 
-        self.query_affinity_map
-            .entry(key)
+        let mut entries = self.query_affinity_map.entry(method_text).or_default();
+        entries
+            .entry(query)
             .and_modify(|data| {
                 data.total_launch_count += 1;
                 data.last_launch_time = current_time;
@@ -252,40 +796,164 @@ impl ProgramRankerInner {
                 total_launch_count: 1,
                 last_launch_time: current_time,
             });
+        if entries.len() > MAX_QUERIES_PER_PROGRAM {
+            compact_query_entries(&mut entries, current_time);
+        }
+    }
+
+    /// 导入其他启动器的使用记录，`query` 为空的记录只累加历史启动次数
+    ///
+    /// 每条记录最多计入 `MAX_IMPORTED_LAUNCHES` 次启动，同一个来源之前已经计入的次数不会重复计入，
+    /// 所以重新导入同一个文件不会改变统计数据。查询亲和记录使用来源中记录的启动时间。
+    /// 返回实际计入的 (启动次数, 查询-程序关联数量)
+    fn import_launch_records(
+        &mut self,
+        source: &str,
+        records: &[QueryLaunchRecord],
+        current_time: i64,
+    ) -> (u64, usize) {
+        let mut state = self.usage_imports.remove(source).unwrap_or_default();
+        let mut imported_launches = 0;
+        let mut imported_queries = 0;
+        for record in records {
+            let Some(launch_method) = self.launch_store.get(&record.program_guid) else {
+                continue;
+            };
+            let method_text = launch_method.get_text();
+            drop(launch_method);
+            let query = normalize_affinity_query(&record.query);
+
+            let imported = state
+                .launch_counts
+                .entry(method_text.clone())
+                .or_default()
+                .entry(query.clone())
+                .or_insert(0);
+            let capped = record.launch_count.min(MAX_IMPORTED_LAUNCHES);
+            let added = capped.saturating_sub(*imported);
+            if added == 0 {
+                continue;
+            }
+            *imported = capped;
+            // 来源中没有记录时间时按现在计
+            let launch_time = if record.last_launch_time > 0 {
+                record.last_launch_time
+            } else {
+                current_time
+            };
+            state.period_start = match state.period_start {
+                0 => launch_time,
+                start => start.min(launch_time),
+            };
+            state.period_end = state.period_end.max(launch_time);
+
+            imported_launches += added;
+            *self
+                .history_launch_time
+                .entry(method_text.clone())
+                .or_insert(0) += added;
+            if query.is_empty() {
+                continue;
+            }
+            let mut entries = self.query_affinity_map.entry(method_text).or_default();
+            let data = entries.entry(query).or_insert(QueryAffinityData {
+                total_launch_count: 0,
+                last_launch_time: launch_time,
+            });
+            data.total_launch_count += added;
+            data.last_launch_time = data.last_launch_time.max(launch_time);
+            if entries.len() > MAX_QUERIES_PER_PROGRAM {
+                compact_query_entries(&mut entries, current_time);
+            }
+            imported_queries += 1;
+        }
+        state.imported_at = current_time;
+        self.usage_imports.insert(source.to_string(), state);
+        (imported_launches, imported_queries)
+    }
+
+    /// 整理所有程序的查询亲和记录，去掉已经没有记录的程序
+    ///
+    /// 只在日期变化时整理一次，加载配置（每次刷新）与保存时不整理，所以调整衰减常数不会丢失记录
+    fn compact_query_affinity(&mut self, current_time: i64) {
+        self.query_affinity_map.retain(|_, entries| {
+            compact_query_entries(entries, current_time);
+            !entries.is_empty()
+        });
     }
 
     /// 计算查询亲和分数
-    fn calculate_query_affinity_score(&self, query: &str, program_guid: u64) -> f64 {
+    ///
+    /// 除了完全相同的查询，这个程序在更长的查询（当前查询是它的前缀）与只差一个字符的查询下的启动记录
+    /// 也按相似度打折计入，取其中最高的一个
+    ///
+    /// `excluded_launches` 为完全相同的查询下不计入的启动次数（重放启动记录时去掉被重放的那一次）
+    fn calculate_query_affinity_score(
+        &self,
+        query: &str,
+        program_guid: u64,
+        excluded_launches: u64,
+    ) -> f64 {
+        let query = normalize_affinity_query(query);
+        if query.is_empty() {
+            return 0.0;
+        }
         let launch_method = self
             .launch_store
             .get(&program_guid)
             .expect_programming("Program GUID should exist in launch store");
-        let method_text = launch_method.get_text();
-        let key = (query.to_string(), method_text);
+        let Some(entries) = self.query_affinity_map.get(&launch_method.get_text()) else {
+            return 0.0;
+        };
 
-        if let Some(data) = self.query_affinity_map.get(&key) {
-            let base_score = data.total_launch_count as f64 * 5.0;
-            let current_time = get_current_time();
-            let time_diff = current_time - data.last_launch_time;
-
-            // 时间衰减因子: exp(-(时间差/时间常数))
-            let decay_factor =
-                (-(time_diff as f64) / (self.query_affinity_time_decay as f64 + 1.0)).exp();
-
-            base_score * decay_factor
-        } else {
-            0.0
-        }
+        let current_time = get_current_time();
+        entries
+            .iter()
+            .map(|(stored_query, data)| {
+                let similarity = query_similarity(&query, stored_query);
+                if similarity <= 0.0 {
+                    return 0.0;
+                }
+                let excluded = if *stored_query == query {
+                    excluded_launches
+                } else {
+                    0
+                };
+                let base_score = data.total_launch_count.saturating_sub(excluded) as f64 * 5.0;
+                let decay_factor = affinity_decay_factor(
+                    data.last_launch_time,
+                    current_time,
+                    self.query_affinity_time_decay,
+                );
+                base_score * decay_factor * similarity
+            })
+            .fold(0.0, f64::max)
     }
 
     /// 计算近期热度分数
-    fn calculate_temporal_score(&self, program_guid: u64) -> f64 {
+    ///
+    /// `excluded_query` 为 `Some` 时，如果最近一次启动就是在这个查询下发生的，这次启动不计入。
+    /// 不知道更早的启动发生在什么时候，所以此时没有热度分数
+    fn calculate_temporal_score(&self, program_guid: u64, excluded_query: Option<&str>) -> f64 {
         let program_string = self
             .launch_store
             .get(&program_guid)
             .expect_programming("Program GUID should exist in launch store");
 
         if let Some(last_launch_time) = self.latest_launch_time.get(&program_string.get_text()) {
+            let launched_by_query = excluded_query.is_some_and(|query| {
+                self.query_affinity_map
+                    .get(&program_string.get_text())
+                    .and_then(|entries| {
+                        entries
+                            .get(&normalize_affinity_query(query))
+                            .map(|data| data.last_launch_time >= *last_launch_time)
+                    })
+                    .unwrap_or(false)
+            });
+            if launched_by_query {
+                return 0.0;
+            }
             let current_time = get_current_time();
             let time_diff = current_time - *last_launch_time;
 
@@ -297,8 +965,87 @@ impl ProgramRankerInner {
         }
     }
 
+    /// 计算使用时段分数 (当前时段的启动次数占该程序总启动次数的比例)
+    ///
+    /// `excluded_launches` 次启动不计入，不知道它们发生在哪个时段，所以从对当前时段加分最多的时段扣除
+    fn calculate_time_of_day_score(&self, program_guid: u64, excluded_launches: u64) -> f64 {
+        let program_string = self
+            .launch_store
+            .get(&program_guid)
+            .expect_programming("Program GUID should exist in launch store");
+
+        let hour_of_week = get_current_hour_of_week();
+        match self.hour_of_week_usage.get(&program_string.get_text()) {
+            Some(usage) if excluded_launches > 0 => {
+                let mut usage = usage.clone();
+                Self::exclude_usage(&mut usage, hour_of_week, excluded_launches);
+                Self::time_of_day_score(&usage, hour_of_week)
+            }
+            Some(usage) => Self::time_of_day_score(&usage, hour_of_week),
+            None => 0.0,
+        }
+    }
+
+    /// 从使用时段统计中扣除启动次数，按对 `hour_of_week` 的加分从多到少的顺序扣除
+    fn exclude_usage(usage: &mut [u64], hour_of_week: usize, mut excluded_launches: u64) {
+        if usage.len() != HOURS_PER_WEEK {
+            return;
+        }
+        // 与 time_of_day_score 的平滑方式一致：同一时段计 1，前后一小时计 0.5
+        let closeness = |a: usize, b: usize, period: usize| {
+            let distance = (a % period + period - b % period) % period;
+            match distance.min(period - distance) {
+                0 => 1.0,
+                1 => 0.5,
+                _ => 0.0,
+            }
+        };
+        let mut buckets: Vec<(usize, f64)> = (0..HOURS_PER_WEEK)
+            .map(|bucket| {
+                let contribution = closeness(bucket, hour_of_week, HOURS_PER_WEEK)
+                    + closeness(bucket, hour_of_week, 24);
+                (bucket, contribution)
+            })
+            .collect();
+        buckets.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for (bucket, _) in buckets {
+            if excluded_launches == 0 {
+                break;
+            }
+            let excluded = excluded_launches.min(usage[bucket]);
+            usage[bucket] -= excluded;
+            excluded_launches -= excluded;
+        }
+    }
+
+    /// 使用时段分数: K * (本周这个时段的占比 + 每天这个时段的占比) / 2 * 置信度
+    ///
+    /// 前后一小时按一半计入，容忍每天的使用时间略有偏差；
+    /// 按天统计的占比让只在周一早上用过几次的程序在周二早上也能得到加分
+    fn time_of_day_score(usage: &[u64], hour_of_week: usize) -> f64 {
+        let total: u64 = usage.iter().sum();
+        if usage.len() != HOURS_PER_WEEK || total == 0 {
+            return 0.0;
+        }
+        let smoothed = |hour: usize| {
+            let count = |offset: usize| usage[(hour + offset) % HOURS_PER_WEEK] as f64;
+            count(0) + 0.5 * (count(HOURS_PER_WEEK - 1) + count(1))
+        };
+        let hour_of_day = hour_of_week % 24;
+        let week_share = smoothed(hour_of_week % HOURS_PER_WEEK) / total as f64;
+        let day_share = (0..7)
+            .map(|day| smoothed(day * 24 + hour_of_day))
+            .sum::<f64>()
+            / total as f64;
+        // 启动次数很少时占比没有说服力
+        let confidence = total as f64 / (total as f64 + 5.0);
+
+        let k = 6.0;
+        k * (week_share + day_share) / 2.0 * confidence
+    }
+
     /// 计算历史总分 (基于所有历史启动次数)
-    fn calculate_history_score(&self, program_guid: u64) -> f64 {
+    fn calculate_history_score(&self, program_guid: u64, excluded_launches: u64) -> f64 {
         let program_string = self
             .launch_store
             .get(&program_guid)
@@ -306,7 +1053,7 @@ impl ProgramRankerInner {
 
         if let Some(count) = self.history_launch_time.get(&program_string.get_text()) {
             // 使用对数函数避免历史数据过大，log(1 + count) 提供递减收益
-            (*count as f64).ln_1p()
+            (count.saturating_sub(excluded_launches) as f64).ln_1p()
         } else {
             0.0
         }
@@ -314,21 +1061,75 @@ impl ProgramRankerInner {
 
     /// 计算最终排序分数（基础分数 + 智能增强）
     fn calculate_final_score(&self, base_score: f64, program_guid: u64, query: &str) -> f64 {
-        // 如果排序算法被禁用，直接返回基础分数
+        base_score + self.calculate_score_components(program_guid, query).total()
+    }
+
+    /// 计算智能增强的各项加分，排序算法被禁用时都为 0
+    fn calculate_score_components(&self, program_guid: u64, query: &str) -> RankerScoreComponents {
         if !self.is_enable {
-            return base_score;
+            return RankerScoreComponents::default();
         }
 
-        let history_score = self.calculate_history_score(program_guid);
-        let recent_habit_score = self.calculate_recent_habit_score(program_guid);
-        let temporal_score = self.calculate_temporal_score(program_guid);
-        let query_affinity = self.calculate_query_affinity_score(query, program_guid);
+        self.calculate_raw_score_components(program_guid, query, 0)
+            .weighted(&self.get_weights())
+    }
 
-        base_score
-            + self.history_weight * history_score
-            + self.recent_habit_weight * recent_habit_score
-            + self.temporal_weight * temporal_score
-            + self.query_affinity_weight * query_affinity
+    /// 计算未加权的各项分数（不受是否启用排序算法的影响）
+    ///
+    /// `excluded_launches` 次在 `query` 下的启动不计入任何一项分数（重放启动记录时去掉被重放的启动）
+    fn calculate_raw_score_components(
+        &self,
+        program_guid: u64,
+        query: &str,
+        excluded_launches: u64,
+    ) -> RankerScoreComponents {
+        RankerScoreComponents {
+            history: self.calculate_history_score(program_guid, excluded_launches),
+            recent_habit: self.calculate_recent_habit_score(program_guid, excluded_launches),
+            temporal: self
+                .calculate_temporal_score(program_guid, (excluded_launches > 0).then_some(query)),
+            query_affinity: self.calculate_query_affinity_score(
+                query,
+                program_guid,
+                excluded_launches,
+            ),
+            time_of_day: self.calculate_time_of_day_score(program_guid, excluded_launches),
+        }
+    }
+
+    /// 获取所有的权重系数
+    fn get_weights(&self) -> RankerWeights {
+        RankerWeights {
+            history: self.history_weight,
+            recent_habit: self.recent_habit_weight,
+            temporal: self.temporal_weight,
+            query_affinity: self.query_affinity_weight,
+            time_of_day: self.time_of_day_weight,
+        }
+    }
+
+    /// 导出已注册的程序的查询-启动记录
+    fn query_launch_records(&self) -> Vec<QueryLaunchRecord> {
+        let program_guids: HashMap<String, u64> = self
+            .launch_store
+            .iter()
+            .map(|entry| (entry.value().get_text(), *entry.key()))
+            .collect();
+        let mut records = Vec::new();
+        for entry in self.query_affinity_map.iter() {
+            let Some(&program_guid) = program_guids.get(entry.key()) else {
+                continue;
+            };
+            for (query, data) in entry.value() {
+                records.push(QueryLaunchRecord {
+                    query: query.clone(),
+                    program_guid,
+                    launch_count: data.total_launch_count,
+                    last_launch_time: data.last_launch_time,
+                });
+            }
+        }
+        records
     }
 
     /// 获取历史权重系数
@@ -351,6 +1152,11 @@ impl ProgramRankerInner {
         self.query_affinity_weight
     }
 
+    /// 获取使用时段权重系数
+    fn get_time_of_day_weight(&self) -> f64 {
+        self.time_of_day_weight
+    }
+
     fn update_launch_info(&mut self) {
         if !is_date_current(&self.last_update_data) {
             self.launch_time.push_front(DashMap::new());
@@ -358,6 +1164,114 @@ impl ProgramRankerInner {
                 self.launch_time.pop_back();
             }
             self.last_update_data = generate_current_date();
+            self.compact_query_affinity(get_current_time());
+        }
+    }
+}
+
+/// 规范化查询亲和记录与固定项使用的查询：小写、合并连续的空格、去掉首尾的空格
+pub fn normalize_affinity_query(query: &str) -> String {
+    remove_repeated_space(&query.to_lowercase())
+        .trim()
+        .to_string()
+}
+
+/// 固定项是否对这个（已规范化的）查询生效，空查询不匹配任何固定项
+///
+/// 前缀固定项在输入以它的查询词开头时生效，比查询词短的输入（例如只输入了一个字母）不会触发
+pub fn pin_matches(pin: &QueryPin, query: &str) -> bool {
+    if query.is_empty() {
+        return false;
+    }
+    pin.query == query || (pin.is_prefix && query.starts_with(&pin.query))
+}
+
+/// 按先后启动的次数降序排列可以建议的程序，次数太少的不建议
+fn rank_transition_targets(targets: &HashMap<String, u64>) -> Vec<String> {
+    let mut ranked: Vec<(&String, u64)> = targets
+        .iter()
+        .filter(|(_, count)| **count >= MIN_SUGGESTION_TRANSITIONS)
+        .map(|(target, count)| (target, *count))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    ranked
+        .into_iter()
+        .map(|(target, _)| target.clone())
+        .collect()
+}
+
+/// 时间衰减因子: exp(-(时间差/时间常数))
+fn affinity_decay_factor(last_launch_time: i64, current_time: i64, time_decay: i64) -> f64 {
+    let time_diff = current_time - last_launch_time;
+    (-(time_diff as f64) / (time_decay as f64 + 1.0)).exp()
+}
+
+/// 当前查询与一个已记录的查询的相似度，为 0 时表示不相关
+///
+/// - 完全相同：1
+/// - 当前查询是已记录的查询的前缀（例如记录的是 "vsc"，现在输入了 "vs"）：每少一个字符乘以 `PREFIX_AFFINITY_DECAY`
+/// - 只差一个字符：`NEAR_QUERY_AFFINITY_FACTOR`
+fn query_similarity(query: &str, stored_query: &str) -> f64 {
+    if query == stored_query {
+        return 1.0;
+    }
+    let query_len = query.chars().count();
+    let stored_len = stored_query.chars().count();
+    if stored_query.starts_with(query) {
+        return PREFIX_AFFINITY_DECAY.powi((stored_len - query_len) as i32);
+    }
+    if query_len >= NEAR_QUERY_MIN_CHARS
+        && stored_len >= NEAR_QUERY_MIN_CHARS
+        && is_one_edit_apart(query, stored_query)
+    {
+        return NEAR_QUERY_AFFINITY_FACTOR;
+    }
+    0.0
+}
+
+/// 两个不同的字符串是否只需要替换、插入或删除一个字符就能相同
+fn is_one_edit_apart(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (short, long) = if a.len() <= b.len() {
+        (&a, &b)
+    } else {
+        (&b, &a)
+    };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let common_prefix = short
+        .iter()
+        .zip(long.iter())
+        .take_while(|(x, y)| x == y)
+        .count();
+    if short.len() == long.len() {
+        common_prefix < short.len() && short[common_prefix + 1..] == long[common_prefix + 1..]
+    } else {
+        short[common_prefix..] == long[common_prefix + 1..]
+    }
+}
+
+/// 整理一个程序的查询亲和记录：清理超过 `MAX_AFFINITY_AGE_DAYS` 天没有使用的查询，
+/// 并只保留最近使用的 `MAX_QUERIES_PER_PROGRAM` 个（同时使用的按启动次数）
+fn compact_query_entries(entries: &mut HashMap<String, QueryAffinityData>, current_time: i64) {
+    let max_age = MAX_AFFINITY_AGE_DAYS * SECONDS_PER_DAY;
+    entries.retain(|_, data| current_time - data.last_launch_time <= max_age);
+    if entries.len() > MAX_QUERIES_PER_PROGRAM {
+        let mut ranked: Vec<(String, i64, u64)> = entries
+            .iter()
+            .map(|(query, data)| {
+                (
+                    query.clone(),
+                    data.last_launch_time,
+                    data.total_launch_count,
+                )
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)));
+        for (query, _, _) in ranked.into_iter().skip(MAX_QUERIES_PER_PROGRAM) {
+            entries.remove(&query);
         }
     }
 }
@@ -402,7 +1316,9 @@ impl ProgramRanker {
 
     /// 计算近期习惯分数 (基于最近7天的启动次数，带衰减)
     pub fn calculate_recent_habit_score(&self, program_guid: u64) -> f64 {
-        self.inner.read().calculate_recent_habit_score(program_guid)
+        self.inner
+            .read()
+            .calculate_recent_habit_score(program_guid, 0)
     }
 
     pub fn program_history_launch_time(&self, program_guid: u64) -> u64 {
@@ -414,21 +1330,41 @@ impl ProgramRanker {
         self.inner.write().record_query_launch(query, program_guid);
     }
 
+    /// 导入其他启动器的使用记录，返回实际计入的 (启动次数, 查询-程序关联数量)
+    pub fn import_launch_records(
+        &self,
+        source: &str,
+        records: &[QueryLaunchRecord],
+    ) -> (u64, usize) {
+        self.inner
+            .write()
+            .import_launch_records(source, records, get_current_time())
+    }
+
     /// 计算查询亲和分数
     pub fn calculate_query_affinity_score(&self, query: &str, program_guid: u64) -> f64 {
         self.inner
             .read()
-            .calculate_query_affinity_score(query, program_guid)
+            .calculate_query_affinity_score(query, program_guid, 0)
     }
 
     /// 计算瞬时分数
     pub fn calculate_temporal_score(&self, program_guid: u64) -> f64 {
-        self.inner.read().calculate_temporal_score(program_guid)
+        self.inner
+            .read()
+            .calculate_temporal_score(program_guid, None)
     }
 
     /// 计算历史总分
     pub fn calculate_history_score(&self, program_guid: u64) -> f64 {
-        self.inner.read().calculate_history_score(program_guid)
+        self.inner.read().calculate_history_score(program_guid, 0)
+    }
+
+    /// 计算使用时段分数
+    pub fn calculate_time_of_day_score(&self, program_guid: u64) -> f64 {
+        self.inner
+            .read()
+            .calculate_time_of_day_score(program_guid, 0)
     }
 
     /// 计算最终排序分数
@@ -438,6 +1374,39 @@ impl ProgramRanker {
             .calculate_final_score(base_score, program_guid, query)
     }
 
+    /// 计算智能增强的各项加分（用于调试页面解释排序结果）
+    pub fn calculate_score_components(
+        &self,
+        program_guid: u64,
+        query: &str,
+    ) -> RankerScoreComponents {
+        self.inner
+            .read()
+            .calculate_score_components(program_guid, query)
+    }
+
+    /// 计算未加权的各项分数，`excluded_launches` 次在 `query` 下的启动不计入（用于拟合权重）
+    pub fn calculate_raw_score_components(
+        &self,
+        program_guid: u64,
+        query: &str,
+        excluded_launches: u64,
+    ) -> RankerScoreComponents {
+        self.inner
+            .read()
+            .calculate_raw_score_components(program_guid, query, excluded_launches)
+    }
+
+    /// 获取所有的权重系数
+    pub fn get_weights(&self) -> RankerWeights {
+        self.inner.read().get_weights()
+    }
+
+    /// 导出已注册的程序的查询-启动记录
+    pub fn query_launch_records(&self) -> Vec<QueryLaunchRecord> {
+        self.inner.read().query_launch_records()
+    }
+
     /// 获取历史权重系数
     pub fn get_history_weight(&self) -> f64 {
         self.inner.read().get_history_weight()
@@ -458,19 +1427,459 @@ impl ProgramRanker {
         self.inner.read().get_query_affinity_weight()
     }
 
+    /// 获取使用时段权重系数
+    pub fn get_time_of_day_weight(&self) -> f64 {
+        self.inner.read().get_time_of_day_weight()
+    }
+
     pub fn get_latest_launch_program(&self, program_count: u32) -> Vec<u64> {
         self.inner.read().get_latest_launch_program(program_count)
     }
 
-    pub fn load_and_register_programs(
+    pub fn get_pinned_programs(&self, query: &str) -> Vec<u64> {
+        self.inner.read().get_pinned_programs(query)
+    }
+
+    /// 替换所有的固定项，不影响其他的统计数据
+    pub fn set_query_pins(&self, query_pins: Vec<QueryPin>) {
+        self.inner.write().query_pins = query_pins;
+    }
+
+    pub fn get_suggested_next_programs(&self, program_count: usize) -> Vec<u64> {
+        self.inner
+            .read()
+            .get_suggested_next_programs(program_count, get_current_time())
+    }
+
+    /// 加载配置，并增量更新已注册的程序（注销被移除的程序，注册新增的程序）
+    ///
+    /// 注册表刷新之后按保留策略清理一次统计数据，并把清理后的数据写回配置，
+    /// 这样开始缺席的时间在没有保存配置的刷新之间也能保留
+    pub fn load_and_update_programs(
         &self,
         config: &ProgramRankerConfig,
-        programs: &[(u64, LaunchMethod)],
+        added_programs: &[(u64, LaunchMethod)],
+        removed_programs: &[u64],
+        unverified: &UnverifiedPrograms,
     ) {
         let mut inner = self.inner.write(); // 获取一次写锁
         inner.load_from_config(config); // 加载配置
-        for (program_guid, launch_method) in programs {
+        for program_guid in removed_programs {
+            inner.unregister_program(*program_guid); // 注销程序
+        }
+        for (program_guid, launch_method) in added_programs {
             inner.register_program(*program_guid, launch_method.clone()); // 注册程序
         }
+
+        let report = inner.collect_garbage(get_current_time(), unverified);
+        if report.pruned_count() > 0 {
+            info!(
+                "清理排序统计数据: 缺席 {}，过期 {}，超出上限 {}，剩余 {}",
+                report.absent.len(),
+                report.expired.len(),
+                report.over_limit.len(),
+                report.remaining
+            );
+        }
+        inner.last_gc_report = report;
+        config.update(inner.get_runtime_data());
+
+        // 配置中的启动时间可能已被更新，需要重建运行时排序集合
+        inner.rebuild_runtime_latest_launch_time();
+    }
+
+    /// 加载配置并注册程序，但不清理统计数据
+    ///
+    /// 用于从快照恢复注册表：紧接着的重新扫描会再调用 `load_and_update_programs`，
+    /// 在这里清理会让缺席的次数在一次启动中被累计两次
+    pub fn load_and_register_programs(
+        &self,
+        config: &ProgramRankerConfig,
+        added_programs: &[(u64, LaunchMethod)],
+    ) {
+        let mut inner = self.inner.write();
+        inner.load_from_config(config);
+        for (program_guid, launch_method) in added_programs {
+            inner.register_program(*program_guid, launch_method.clone());
+        }
+        inner.rebuild_runtime_latest_launch_time();
+    }
+
+    /// 获取上一次清理统计数据的结果
+    pub fn get_last_gc_report(&self) -> RankerGcReport {
+        self.inner.read().last_gc_report.clone()
+    }
+
+    /// 不重新加载配置，只增量更新已注册的程序
+    pub fn update_programs(
+        &self,
+        added_programs: &[(u64, LaunchMethod)],
+        removed_programs: &[u64],
+    ) {
+        let mut inner = self.inner.write();
+        for program_guid in removed_programs {
+            inner.unregister_program(*program_guid);
+        }
+        for (program_guid, launch_method) in added_programs {
+            inner.register_program(*program_guid, launch_method.clone());
+        }
+        inner.rebuild_runtime_latest_launch_time();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_similarity() {
+        assert_eq!(
+            normalize_affinity_query("  Visual   Studio "),
+            "visual studio"
+        );
+        assert_eq!(query_similarity("vsc", "vsc"), 1.0);
+        assert_eq!(query_similarity("vs", "vsc"), PREFIX_AFFINITY_DECAY);
+        assert!(query_similarity("v", "vsc") < query_similarity("vs", "vsc"));
+        // 更长的查询不继承较短的查询的亲和度，除非只多了一个字符
+        assert_eq!(query_similarity("vscode", "vsc"), 0.0);
+        assert_eq!(query_similarity("vsco", "vsc"), NEAR_QUERY_AFFINITY_FACTOR);
+        assert_eq!(query_similarity("chorme", "chrome"), 0.0);
+        assert_eq!(
+            query_similarity("chrme", "chrome"),
+            NEAR_QUERY_AFFINITY_FACTOR
+        );
+        assert_eq!(
+            query_similarity("chrone", "chrome"),
+            NEAR_QUERY_AFFINITY_FACTOR
+        );
+        assert_eq!(query_similarity("ab", "ac"), 0.0);
+        assert_eq!(query_similarity("微信", "微博"), 0.0);
+    }
+
+    #[test]
+    fn test_collect_garbage() {
+        let day = SECONDS_PER_DAY;
+        let now = 1000 * day;
+        let mut inner = ProgramRankerInner::new();
+        inner.prune_after_absent_refreshes = 3;
+        inner.max_launch_age_days = 365;
+        inner.max_tracked_programs = 0;
+        inner.register_program(1, LaunchMethod::Path("installed".to_string()));
+        inner.register_program(2, LaunchMethod::Path("never".to_string()));
+        for (method_text, latest) in [
+            ("installed", now),
+            ("uninstalled", now - day),
+            ("ancient", now - 400 * day),
+            ("hidden", now - day),
+            ("D:\\Share\\tool.exe", now - day),
+        ] {
+            inner.history_launch_time.insert(method_text.to_string(), 3);
+            inner
+                .latest_launch_time
+                .insert(method_text.to_string(), latest);
+        }
+        // 被隐藏的程序与没有扫描完整的根目录中的程序都无法确认是否还存在
+        let unverified = UnverifiedPrograms {
+            launch_methods: HashSet::from(["hidden".to_string()]),
+            roots: vec!["d:/share/".to_string()],
+        };
+
+        let report = inner.collect_garbage(now, &unverified);
+        assert_eq!(report.expired, vec!["ancient"]);
+        assert!(report.absent.is_empty());
+        assert_eq!(report.remaining, 4);
+        assert_eq!(inner.absent_refreshes.get("uninstalled"), Some(&1));
+        assert!(!inner.absent_refreshes.contains_key("hidden"));
+        assert!(!inner.absent_refreshes.contains_key("D:\\Share\\tool.exe"));
+        assert!(!inner.history_launch_time.contains_key("ancient"));
+
+        // 缺席按刷新的次数计算，与经过了多少天无关
+        let report = inner.collect_garbage(now + 30 * day, &unverified);
+        assert!(report.absent.is_empty());
+        assert_eq!(inner.absent_refreshes.get("uninstalled"), Some(&2));
+
+        // 在某次刷新中重新出现时计数清零
+        inner.register_program(3, LaunchMethod::Path("uninstalled".to_string()));
+        inner.collect_garbage(now, &unverified);
+        assert!(!inner.absent_refreshes.contains_key("uninstalled"));
+        inner.unregister_program(3);
+        for count in 1..3 {
+            let report = inner.collect_garbage(now, &unverified);
+            assert!(report.absent.is_empty());
+            assert_eq!(inner.absent_refreshes.get("uninstalled"), Some(&count));
+        }
+
+        let report = inner.collect_garbage(now, &UnverifiedPrograms::default());
+        assert_eq!(report.absent, vec!["uninstalled"]);
+        assert_eq!(report.remaining, 3);
+        assert!(!inner.absent_refreshes.contains_key("uninstalled"));
+        assert!(!inner.latest_launch_time.contains_key("uninstalled"));
+        // 不再无法确认之后才开始计数
+        assert_eq!(inner.absent_refreshes.get("hidden"), Some(&1));
+
+        // 超出上限时优先清理不在注册表中的
+        inner.forget_launch_method("hidden");
+        inner.forget_launch_method("D:\\Share\\tool.exe");
+        inner.max_tracked_programs = 1;
+        inner.history_launch_time.insert("other".to_string(), 1);
+        let report = inner.collect_garbage(now, &UnverifiedPrograms::default());
+        assert_eq!(report.over_limit, vec!["other"]);
+        assert!(inner.history_launch_time.contains_key("installed"));
+    }
+
+    #[test]
+    fn test_suggested_next_programs() {
+        let now = 1_000_000;
+        let mut inner = ProgramRankerInner::new();
+        inner.register_program(1, LaunchMethod::Path("editor".to_string()));
+        inner.register_program(2, LaunchMethod::Path("terminal".to_string()));
+        inner.register_program(3, LaunchMethod::Path("browser".to_string()));
+        for _ in 0..3 {
+            inner.record_transition("editor", "terminal");
+        }
+        inner.record_transition("editor", "browser");
+        inner.record_transition("editor", "uninstalled");
+        inner.record_transition("editor", "uninstalled");
+
+        // 没有启动过任何程序时没有建议
+        assert!(inner.get_suggested_next_programs(5, now).is_empty());
+
+        inner
+            .latest_launch_time
+            .insert("editor".to_string(), now - 60);
+        inner.rebuild_runtime_latest_launch_time();
+        // 只启动过一次的不建议，不在注册表中的也不建议
+        assert_eq!(inner.get_suggested_next_programs(5, now), vec![2]);
+        // 上一次启动已经超出时间窗口
+        let later = now + inner.transition_window;
+        assert!(inner.get_suggested_next_programs(5, later).is_empty());
+
+        // 次数过多时减半，旧的习惯逐渐淡出
+        for _ in 0..TRANSITION_COUNT_CAP {
+            inner.record_transition("editor", "browser");
+        }
+        assert_eq!(inner.get_suggested_next_programs(1, now), vec![3]);
+        let total: u64 = inner.launch_transitions["editor"].values().sum();
+        assert!(total <= TRANSITION_COUNT_CAP);
+
+        inner.forget_launch_method("terminal");
+        assert!(!inner.launch_transitions["editor"].contains_key("terminal"));
+        assert_eq!(inner.suggested_transitions["editor"], vec!["browser"]);
+        inner.forget_launch_method("editor");
+        assert!(inner.launch_transitions.is_empty());
+        assert!(inner.suggested_transitions.is_empty());
+    }
+
+    #[test]
+    fn test_pinned_programs() {
+        let pin = |query: &str, is_prefix: bool, launch_method: &str| QueryPin {
+            query: query.to_string(),
+            is_prefix,
+            launch_method: launch_method.to_string(),
+        };
+        let mut inner = ProgramRankerInner::new();
+        inner.register_program(1, LaunchMethod::Path("terminal".to_string()));
+        inner.register_program(2, LaunchMethod::Path("powershell".to_string()));
+        inner.query_pins = vec![
+            pin("term", true, "terminal"),
+            pin("te", false, "powershell"),
+            pin("te", false, "uninstalled"),
+        ];
+
+        assert_eq!(inner.get_pinned_programs("Term"), vec![1]);
+        // 完全匹配的固定项排在前缀匹配的前面
+        inner.query_pins.push(pin("t", true, "powershell"));
+        assert_eq!(inner.get_pinned_programs("term"), vec![1, 2]);
+        assert_eq!(inner.get_pinned_programs("te"), vec![2]);
+        // 只输入一个字母时不会触发更长的前缀固定项
+        assert_eq!(inner.get_pinned_programs("t"), vec![2]);
+        inner.query_pins.pop();
+        assert!(inner.get_pinned_programs("t").is_empty());
+        // 输入以前缀固定项的查询词开头时生效
+        assert_eq!(inner.get_pinned_programs("terminal"), vec![1]);
+        assert!(inner.get_pinned_programs("tex").is_empty());
+        assert!(inner.get_pinned_programs("  ").is_empty());
+    }
+
+    #[test]
+    fn test_import_launch_records() {
+        let now = 100 * SECONDS_PER_DAY;
+        let imported_time = now - 20 * SECONDS_PER_DAY;
+        let record = |query: &str, launch_count: u64| QueryLaunchRecord {
+            query: query.to_string(),
+            program_guid: 1,
+            launch_count,
+            last_launch_time: imported_time,
+        };
+        let mut inner = ProgramRankerInner::new();
+        inner.register_program(1, LaunchMethod::Path("terminal".to_string()));
+
+        // 启动次数有上限，查询亲和记录使用来源中的时间
+        let records = vec![record("", 500), record("term", 4)];
+        assert_eq!(
+            inner.import_launch_records("launchy", &records, now),
+            (MAX_IMPORTED_LAUNCHES + 4, 1)
+        );
+        assert_eq!(
+            *inner.history_launch_time.get("terminal").unwrap(),
+            MAX_IMPORTED_LAUNCHES + 4
+        );
+        let data = inner.query_affinity_map.get("terminal").unwrap()["term"].clone();
+        assert_eq!(data.total_launch_count, 4);
+        assert_eq!(data.last_launch_time, imported_time);
+
+        // 重新导入同一个文件不改变统计数据，来源中的次数增加时只计入增加的部分
+        assert_eq!(
+            inner.import_launch_records("launchy", &records, now),
+            (0, 0)
+        );
+        let records = vec![record("", 500), record("term", 6)];
+        assert_eq!(
+            inner.import_launch_records("launchy", &records, now),
+            (2, 1)
+        );
+        assert_eq!(
+            *inner.history_launch_time.get("terminal").unwrap(),
+            MAX_IMPORTED_LAUNCHES + 6
+        );
+
+        let state = &inner.usage_imports["launchy"];
+        assert_eq!(state.imported_at, now);
+        assert_eq!(state.period_start, imported_time);
+        assert_eq!(state.period_end, imported_time);
+    }
+
+    #[test]
+    fn test_compact_query_entries() {
+        let day = 86400;
+        let now = 100 * day;
+        let mut entries: HashMap<String, QueryAffinityData> = (0..20)
+            .map(|index| {
+                (
+                    format!("query{}", index),
+                    QueryAffinityData {
+                        total_launch_count: index + 1,
+                        last_launch_time: now,
+                    },
+                )
+            })
+            .collect();
+        entries.insert(
+            "stale".to_string(),
+            QueryAffinityData {
+                total_launch_count: 1000,
+                last_launch_time: now - 60 * day,
+            },
+        );
+        compact_query_entries(&mut entries, now);
+        assert_eq!(entries.len(), MAX_QUERIES_PER_PROGRAM);
+        assert!(!entries.contains_key("stale"));
+        assert!(entries.contains_key("query19"));
+        assert!(!entries.contains_key("query0"));
+
+        // 数量不多时只按时间清理，与衰减常数无关
+        let mut entries: HashMap<String, QueryAffinityData> = HashMap::from([
+            (
+                "recent".to_string(),
+                QueryAffinityData {
+                    total_launch_count: 1,
+                    last_launch_time: now - 30 * day,
+                },
+            ),
+            (
+                "expired".to_string(),
+                QueryAffinityData {
+                    total_launch_count: 1000,
+                    last_launch_time: now - (MAX_AFFINITY_AGE_DAYS + 1) * day,
+                },
+            ),
+        ]);
+        compact_query_entries(&mut entries, now);
+        assert!(entries.contains_key("recent"));
+        assert!(!entries.contains_key("expired"));
+    }
+
+    #[test]
+    fn test_load_from_config_keeps_query_affinity() {
+        let now = get_current_time();
+        let config = ProgramRankerConfig::default();
+        config.update(PartialProgramRankerConfig {
+            query_affinity_store: Some(HashMap::from([(
+                "edit".to_string(),
+                HashMap::from([(
+                    "editor".to_string(),
+                    QueryAffinityData {
+                        total_launch_count: 3,
+                        last_launch_time: now - 10 * SECONDS_PER_DAY,
+                    },
+                )]),
+            )])),
+            // 衰减常数很小，旧的记录几乎没有亲和度了，但依然要保留
+            query_affinity_time_decay: Some(60),
+            last_update_data: Some(generate_current_date()),
+            ..Default::default()
+        });
+
+        let mut inner = ProgramRankerInner::new();
+        inner.load_from_config(&config);
+        inner.load_from_config(&config);
+        assert!(
+            inner.get_runtime_data().query_affinity_store.unwrap()["edit"].contains_key("editor")
+        );
+    }
+
+    #[test]
+    fn test_time_of_day_score() {
+        // 周一 9 点用过 10 次，周三 9 点用过 10 次
+        let mut usage = vec![0; HOURS_PER_WEEK];
+        usage[9] = 10;
+        usage[2 * 24 + 9] = 10;
+
+        let monday_morning = ProgramRankerInner::time_of_day_score(&usage, 9);
+        let tuesday_morning = ProgramRankerInner::time_of_day_score(&usage, 24 + 9);
+        let monday_nearby = ProgramRankerInner::time_of_day_score(&usage, 10);
+        let monday_evening = ProgramRankerInner::time_of_day_score(&usage, 20);
+        assert!(monday_morning > tuesday_morning);
+        assert!(tuesday_morning > monday_nearby);
+        assert!(monday_nearby > 0.0);
+        assert_eq!(monday_evening, 0.0);
+        assert!(monday_morning <= 6.0);
+
+        // 周日 23 点与周一 0 点相邻
+        let mut usage = vec![0; HOURS_PER_WEEK];
+        usage[HOURS_PER_WEEK - 1] = 4;
+        assert!(ProgramRankerInner::time_of_day_score(&usage, 0) > 0.0);
+        assert_eq!(ProgramRankerInner::time_of_day_score(&[], 0), 0.0);
+    }
+
+    #[test]
+    fn test_raw_score_components_exclude_replayed_launches() {
+        let mut inner = ProgramRankerInner::new();
+        inner.register_program(1, LaunchMethod::Path("editor".to_string()));
+        for _ in 0..3 {
+            inner.record_launch(1);
+            inner.record_query_launch("edit", 1);
+        }
+
+        let leaked = inner.calculate_raw_score_components(1, "edit", 0);
+        assert!(leaked.history > 0.0);
+        assert!(leaked.recent_habit > 0.0);
+        assert!(leaked.temporal > 0.0);
+        assert!(leaked.query_affinity > 0.0);
+        assert!(leaked.time_of_day > 0.0);
+        // 所有的启动都在这个查询下，全部去掉后没有任何加分
+        assert_eq!(
+            inner.calculate_raw_score_components(1, "edit", 3),
+            RankerScoreComponents::default()
+        );
+
+        // 在其他查询下的一次启动依然计入历史、近期习惯与使用时段
+        inner.record_launch(1);
+        inner.record_query_launch("editor", 1);
+        let replayed = inner.calculate_raw_score_components(1, "edit", 3);
+        assert!(replayed.history > 0.0);
+        assert!(replayed.recent_habit > 0.0);
+        assert!(replayed.time_of_day > 0.0);
+        assert!(replayed.history < leaked.history);
     }
 }
@@ -0,0 +1,93 @@
+/// 这个文件用于把程序注册表保存为二进制快照
+///
+/// 启动时先从快照中恢复注册表，这样第一次搜索不需要等待磁盘扫描完成，之后再在后台重新扫描并增量更新注册表。
+/// 快照只保存扫描的结果（名字、关键字、启动方式、图标、固定偏移），guid 与 embedding 在恢复时重新生成
+use crate::core::image_processor::ImageIdentity;
+use crate::program_manager::{LaunchMethod, Program};
+use bincode::{Decode, Encode};
+use std::sync::Arc;
+
+/// 快照格式的版本，修改 `SnapshotProgram` 的字段后需要增加，旧版本的快照会被忽略
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// 快照中的一个程序
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct SnapshotProgram {
+    pub show_name: String,
+    pub launch_method: LaunchMethod,
+    pub search_keywords: Vec<String>,
+    pub stable_bias: f64,
+    pub icon_path: ImageIdentity,
+}
+
+#[derive(Debug, Encode, Decode)]
+struct ProgramSnapshot {
+    version: u32,
+    programs: Vec<SnapshotProgram>,
+}
+
+/// 把注册表中的程序编码为快照
+pub fn encode_snapshot(programs: &[Arc<Program>]) -> Vec<u8> {
+    let snapshot = ProgramSnapshot {
+        version: SNAPSHOT_VERSION,
+        programs: programs
+            .iter()
+            .map(|program| SnapshotProgram {
+                show_name: program.show_name.clone(),
+                launch_method: program.launch_method.clone(),
+                search_keywords: program.search_keywords.clone(),
+                stable_bias: program.stable_bias,
+                icon_path: program.icon_path.clone(),
+            })
+            .collect(),
+    };
+    bincode::encode_to_vec(&snapshot, bincode::config::standard()).unwrap_or_default()
+}
+
+/// 解码快照，数据损坏或者版本不一致时返回 `None`
+pub fn decode_snapshot(bytes: &[u8]) -> Option<Vec<SnapshotProgram>> {
+    let (snapshot, _): (ProgramSnapshot, usize) =
+        bincode::decode_from_slice(bytes, bincode::config::standard()).ok()?;
+    (snapshot.version == SNAPSHOT_VERSION).then_some(snapshot.programs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let program = Program {
+            program_guid: 42,
+            show_name: "Visual Studio Code".to_string(),
+            launch_method: LaunchMethod::Path("C:\\Code.exe".to_string()),
+            search_keywords: vec!["visual studio code".to_string(), "vsc".to_string()],
+            stable_bias: 1.5,
+            icon_path: ImageIdentity::File("C:\\Code.exe".to_string()),
+            embedding: vec![0.5; 4],
+        };
+        let bytes = encode_snapshot(&[Arc::new(program.clone())]);
+        let programs = decode_snapshot(&bytes).unwrap();
+        assert_eq!(
+            programs,
+            vec![SnapshotProgram {
+                show_name: program.show_name,
+                launch_method: program.launch_method,
+                search_keywords: program.search_keywords,
+                stable_bias: 1.5,
+                icon_path: program.icon_path,
+            }]
+        );
+
+        assert!(decode_snapshot(&bytes[..bytes.len() / 2]).is_none());
+        let old_version = bincode::encode_to_vec(
+            &ProgramSnapshot {
+                version: SNAPSHOT_VERSION + 1,
+                programs: Vec::new(),
+            },
+            bincode::config::standard(),
+        )
+        .unwrap();
+        assert!(decode_snapshot(&old_version).is_none());
+    }
+}
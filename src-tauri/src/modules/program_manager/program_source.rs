@@ -0,0 +1,41 @@
+/// 这个文件定义了程序的来源
+///
+/// 每个来源负责把某一类东西（路径中的程序、UWP 应用、网页、书签等）转换为 `Program`，
+/// `ProgramLoader` 只负责按注册的顺序调用它们并记录各自的耗时。
+/// 添加新的来源只需要实现 `ProgramSource`，再通过 `ProgramLoader::register_source` 注册，不需要修改加载器的核心逻辑
+use crate::program_manager::program_loader::ProgramSourceContext;
+use crate::program_manager::Program;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+pub trait ProgramSource: Debug + Send + Sync {
+    /// 来源的唯一标识，用于配置中的开关与选项，也会显示在调试页面中
+    fn id(&self) -> &'static str;
+
+    /// 读取这个来源自己的选项，即配置中 `source_options[id]`，没有配置时为 `Null`
+    fn configure(&mut self, _options: &serde_json::Value) {}
+
+    /// 用户没有在 `disabled_sources` 中关闭这个来源时，它是否要加载
+    ///
+    /// 用于兼容 `is_scan_uwp_programs` 这类来源自己的开关
+    fn is_enabled(&self, _context: &ProgramSourceContext) -> bool {
+        true
+    }
+
+    /// 加载这个来源中所有的程序
+    ///
+    /// 通过 `context` 生成关键字、跳过重复或被隐藏的程序与创建 `Program`，按注册顺序先加载的来源优先
+    fn load(&mut self, context: &mut ProgramSourceContext) -> Vec<Arc<Program>>;
+}
+
+/// 一个来源在上一次加载时的情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramSourceStat {
+    pub id: String,
+    pub enabled: bool,
+    /// 加载出来的程序数量（被隐藏的程序不会被加载）
+    pub item_count: usize,
+    /// 加载耗时，单位为毫秒
+    pub load_time: f64,
+}
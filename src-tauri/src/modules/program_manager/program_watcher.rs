@@ -0,0 +1,244 @@
+/// 这个类用于监听索引目录中的文件变化，使新安装/删除的程序无需等待定时刷新即可生效
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// 一批经过防抖合并后的文件变化
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WatchBatch {
+    /// 新建、修改或重命名后出现的路径
+    pub changed: Vec<PathBuf>,
+    /// 被删除或重命名前的路径
+    pub removed: Vec<PathBuf>,
+}
+
+impl WatchBatch {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// 文件监听器
+pub struct ProgramWatcher {
+    /// 防抖时间：在这段时间内没有新的事件时，才会把积累的变化一并交给回调
+    debounce: Duration,
+    /// 最长延迟：事件持续不断时，从第一个事件起最多等待这么久就交给回调
+    max_latency: Duration,
+    /// 当前正在工作的监听器，丢弃它即可停止监听
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl std::fmt::Debug for ProgramWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgramWatcher")
+            .field("debounce", &self.debounce)
+            .field("max_latency", &self.max_latency)
+            .field("is_watching", &self.watcher.lock().is_some())
+            .finish()
+    }
+}
+
+impl Default for ProgramWatcher {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(800), Duration::from_secs(5))
+    }
+}
+
+impl ProgramWatcher {
+    pub fn new(debounce: Duration, max_latency: Duration) -> Self {
+        ProgramWatcher {
+            debounce,
+            max_latency,
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// 开始监听给定的根目录（递归），之前的监听会被停止
+    ///
+    /// `on_change` 会在后台线程中被调用，每次收到一批防抖后的变化
+    pub fn watch<F>(&self, roots: &[PathBuf], on_change: F)
+    where
+        F: Fn(WatchBatch) + Send + 'static,
+    {
+        self.stop();
+
+        let (sender, receiver) = channel::<notify::Result<Event>>();
+        let mut watcher = match notify::recommended_watcher(sender) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("创建文件监听器失败: {}", e);
+                return;
+            }
+        };
+
+        let mut watched_count = 0;
+        for root in roots {
+            if root.as_os_str().is_empty() || !root.is_dir() {
+                continue;
+            }
+            match watcher.watch(root, RecursiveMode::Recursive) {
+                Ok(()) => watched_count += 1,
+                Err(e) => warn!("监听目录失败: {:?}, {}", root, e),
+            }
+        }
+        debug!("文件监听器已启动，监听 {} 个目录", watched_count);
+
+        let debounce = self.debounce;
+        let max_latency = self.max_latency;
+        std::thread::spawn(move || Self::debounce_loop(receiver, debounce, max_latency, on_change));
+
+        *self.watcher.lock() = Some(watcher);
+    }
+
+    /// 停止监听
+    pub fn stop(&self) {
+        // 监听器被丢弃后，事件通道随之关闭，防抖线程会在处理完剩余事件后退出
+        self.watcher.lock().take();
+    }
+
+    /// 收集事件直到静默 `debounce` 时长（但最多等待 `max_latency`），然后按路径当前是否存在划分为新增/删除
+    fn debounce_loop<F>(
+        receiver: Receiver<notify::Result<Event>>,
+        debounce: Duration,
+        max_latency: Duration,
+        on_change: F,
+    ) where
+        F: Fn(WatchBatch),
+    {
+        let mut pending: HashSet<PathBuf> = HashSet::new();
+        // 当前这批变化中第一个事件到达的时间
+        let mut batch_start: Option<Instant> = None;
+        loop {
+            let timeout = match batch_start {
+                Some(start) => debounce.min(max_latency.saturating_sub(start.elapsed())),
+                None => debounce,
+            };
+            match receiver.recv_timeout(timeout) {
+                Ok(Ok(event)) => {
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        continue;
+                    }
+                    pending.extend(event.paths);
+                    if pending.is_empty() {
+                        continue;
+                    }
+                    let start = *batch_start.get_or_insert_with(Instant::now);
+                    if start.elapsed() >= max_latency {
+                        Self::flush(&mut pending, &on_change);
+                        batch_start = None;
+                    }
+                }
+                Ok(Err(e)) => {
+                    debug!("文件监听事件错误: {}", e);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    Self::flush(&mut pending, &on_change);
+                    batch_start = None;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    Self::flush(&mut pending, &on_change);
+                    debug!("文件监听器已停止");
+                    return;
+                }
+            }
+        }
+    }
+
+    fn flush<F>(pending: &mut HashSet<PathBuf>, on_change: &F)
+    where
+        F: Fn(WatchBatch),
+    {
+        if pending.is_empty() {
+            return;
+        }
+        let mut batch = WatchBatch::default();
+        for path in pending.drain() {
+            if path.exists() {
+                batch.changed.push(path);
+            } else {
+                batch.removed.push(path);
+            }
+        }
+        debug!(
+            "文件变化: 新增/修改 {} 个，删除 {} 个",
+            batch.changed.len(),
+            batch.removed.len()
+        );
+        on_change(batch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn wait_for(receiver: &Receiver<WatchBatch>, predicate: impl Fn(&WatchBatch) -> bool) -> bool {
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while std::time::Instant::now() < deadline {
+            if let Ok(batch) = receiver.recv_timeout(Duration::from_millis(200)) {
+                if predicate(&batch) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn test_watch_create_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let (sender, receiver) = channel::<WatchBatch>();
+
+        let watcher = ProgramWatcher::new(Duration::from_millis(100), Duration::from_secs(1));
+        watcher.watch(std::slice::from_ref(&root), move |batch| {
+            let _ = sender.send(batch);
+        });
+
+        let target = root.join("demo.lnk");
+        fs::write(&target, b"").unwrap();
+        assert!(wait_for(&receiver, |batch| batch.changed.contains(&target)));
+
+        fs::remove_file(&target).unwrap();
+        assert!(wait_for(&receiver, |batch| batch.removed.contains(&target)));
+
+        watcher.stop();
+    }
+
+    #[test]
+    fn test_flush_under_continuous_events() {
+        let (event_sender, event_receiver) = channel::<notify::Result<Event>>();
+        let (sender, receiver) = channel::<WatchBatch>();
+        std::thread::spawn(move || {
+            ProgramWatcher::debounce_loop(
+                event_receiver,
+                Duration::from_millis(100),
+                Duration::from_millis(300),
+                move |batch| {
+                    let _ = sender.send(batch);
+                },
+            )
+        });
+
+        // 事件间隔比防抖时间短，只有最长延迟能让变化被交出去
+        let start = Instant::now();
+        let mut flushed_while_busy = false;
+        for i in 0..100 {
+            let event = Event::new(EventKind::Create(notify::event::CreateKind::File))
+                .add_path(PathBuf::from(format!("missing-{}.lnk", i)));
+            event_sender.send(Ok(event)).unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+            if receiver.try_recv().is_ok() {
+                flushed_while_busy = true;
+                break;
+            }
+        }
+        assert!(flushed_while_busy);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,266 @@
+/// 这个文件实现了搜索框中的查询语法
+///
+/// - `kind:uwp` / `kind:web` / `kind:cmd` / `kind:file`：按启动方式筛选
+/// - `in:desktop` / `in:startmenu` / `in:<路径片段>`：按程序所在的位置筛选
+/// - `!word`：排除名字或关键字中含有 word 的程序
+/// - `"some phrase"`：名字或关键字中必须完整地含有这个短语
+///
+/// 其余的内容作为普通文本交给评分器
+use crate::program_manager::{LaunchMethodKind, Program};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// `kind:` 过滤条件可选的值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryKind {
+    /// UWP 应用
+    Uwp,
+    /// 网页
+    Web,
+    /// 自定义命令
+    Command,
+    /// 本地的程序或文件
+    File,
+}
+
+impl QueryKind {
+    fn parse(text: &str) -> Option<QueryKind> {
+        match text {
+            "uwp" => Some(QueryKind::Uwp),
+            "web" | "url" => Some(QueryKind::Web),
+            "cmd" | "command" => Some(QueryKind::Command),
+            "file" | "path" => Some(QueryKind::File),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            QueryKind::Uwp => "uwp",
+            QueryKind::Web => "web",
+            QueryKind::Command => "cmd",
+            QueryKind::File => "file",
+        }
+    }
+
+    fn matches(&self, program: &Program) -> bool {
+        let target = program.launch_method.get_text();
+        let is_url = target.contains("://");
+        match (self, program.launch_method.kind()) {
+            (QueryKind::Uwp, LaunchMethodKind::PackageFamilyName) => true,
+            (QueryKind::Web, LaunchMethodKind::File) => is_url,
+            (QueryKind::Command, LaunchMethodKind::Command) => true,
+            (QueryKind::File, LaunchMethodKind::Path) => true,
+            (QueryKind::File, LaunchMethodKind::File) => !is_url,
+            _ => false,
+        }
+    }
+}
+
+/// 从查询中解析出来的过滤条件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryFilters {
+    /// 启动方式需要满足其中之一
+    pub kinds: Vec<QueryKind>,
+    /// 所在位置需要满足其中之一
+    pub locations: Vec<String>,
+    /// 排除的词
+    pub excludes: Vec<String>,
+    /// 必须完整出现的短语
+    pub phrases: Vec<String>,
+}
+
+impl QueryFilters {
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+            && self.locations.is_empty()
+            && self.excludes.is_empty()
+            && self.phrases.is_empty()
+    }
+
+    /// 判断一个程序是否满足所有的过滤条件
+    ///
+    /// `known_locations` 为位置别名（如 "desktop"）到实际目录的映射
+    pub fn matches(
+        &self,
+        program: &Program,
+        known_locations: &HashMap<String, Vec<String>>,
+    ) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|kind| kind.matches(program)) {
+            return false;
+        }
+
+        if !self.locations.is_empty() {
+            let target = program.launch_method.get_text().to_lowercase();
+            let in_location =
+                self.locations
+                    .iter()
+                    .any(|location| match known_locations.get(location) {
+                        Some(dirs) => dirs
+                            .iter()
+                            .filter(|dir| !dir.is_empty())
+                            .any(|dir| target.starts_with(&dir.to_lowercase())),
+                        None => target.contains(location.as_str()),
+                    });
+            if !in_location {
+                return false;
+            }
+        }
+
+        let show_name = program.show_name.to_lowercase();
+        let contains = |term: &str| {
+            show_name.contains(term)
+                || program
+                    .search_keywords
+                    .iter()
+                    .any(|keyword| keyword.contains(term))
+        };
+        if self.excludes.iter().any(|term| contains(term)) {
+            return false;
+        }
+        self.phrases.iter().all(|phrase| contains(phrase))
+    }
+
+    /// 以查询语法的形式描述过滤条件（用于调试页面展示）
+    pub fn describe(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        result.extend(
+            self.kinds
+                .iter()
+                .map(|kind| format!("kind:{}", kind.name())),
+        );
+        result.extend(
+            self.locations
+                .iter()
+                .map(|location| format!("in:{}", location)),
+        );
+        result.extend(self.excludes.iter().map(|term| format!("!{}", term)));
+        result.extend(self.phrases.iter().map(|phrase| format!("\"{}\"", phrase)));
+        result
+    }
+}
+
+/// 解析后的查询
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedQuery {
+    /// 交给评分器的文本（包括引号中的短语）
+    pub text: String,
+    /// 过滤条件
+    pub filters: QueryFilters,
+}
+
+/// 解析用户输入，`user_input` 应已转为小写
+pub fn parse_query(user_input: &str) -> ParsedQuery {
+    let mut parsed = ParsedQuery::default();
+    let mut text_parts: Vec<String> = Vec::new();
+
+    let mut rest = user_input;
+    while !rest.is_empty() {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+
+        // 引号中的短语，没有闭合时一直到输入的末尾
+        if let Some(after_quote) = rest.strip_prefix('"') {
+            let (phrase, remaining) = match after_quote.find('"') {
+                Some(end) => (&after_quote[..end], &after_quote[end + 1..]),
+                None => (after_quote, ""),
+            };
+            let phrase = phrase.trim();
+            if !phrase.is_empty() {
+                parsed.filters.phrases.push(phrase.to_string());
+                text_parts.push(phrase.to_string());
+            }
+            rest = remaining;
+            continue;
+        }
+
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let token = &rest[..end];
+        rest = &rest[end..];
+
+        if let Some(term) = token.strip_prefix('!').filter(|term| !term.is_empty()) {
+            parsed.filters.excludes.push(term.to_string());
+        } else if let Some(kind) = token.strip_prefix("kind:").and_then(QueryKind::parse) {
+            if !parsed.filters.kinds.contains(&kind) {
+                parsed.filters.kinds.push(kind);
+            }
+        } else if let Some(location) = token.strip_prefix("in:").filter(|value| !value.is_empty()) {
+            parsed.filters.locations.push(location.to_string());
+        } else {
+            // 不认识的前缀（例如正在输入的 "kind:u"）按普通文本处理
+            text_parts.push(token.to_string());
+        }
+    }
+
+    parsed.text = text_parts.join(" ");
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program_manager::test_support::test_program;
+    use crate::program_manager::LaunchMethod;
+
+    #[test]
+    fn test_parse_query() {
+        let parsed = parse_query("kind:uwp  vis !preview \"studio code\" in:desktop kind:x");
+        assert_eq!(parsed.text, "vis studio code kind:x");
+        assert_eq!(parsed.filters.kinds, vec![QueryKind::Uwp]);
+        assert_eq!(parsed.filters.locations, vec!["desktop"]);
+        assert_eq!(parsed.filters.excludes, vec!["preview"]);
+        assert_eq!(parsed.filters.phrases, vec!["studio code"]);
+        assert_eq!(
+            parsed.filters.describe(),
+            vec!["kind:uwp", "in:desktop", "!preview", "\"studio code\""]
+        );
+
+        let plain = parse_query("chrome");
+        assert_eq!(plain.text, "chrome");
+        assert!(plain.filters.is_empty());
+
+        // 未闭合的引号与单独的 "!"
+        let parsed = parse_query("! \"visual stu");
+        assert_eq!(parsed.text, "! visual stu");
+        assert_eq!(parsed.filters.phrases, vec!["visual stu"]);
+    }
+
+    #[test]
+    fn test_filters_match() {
+        let desktop = r"C:\Users\demo\Desktop";
+        let known_locations = HashMap::from([("desktop".to_string(), vec![desktop.to_string()])]);
+        let code = Program {
+            launch_method: LaunchMethod::Path(format!(r"{}\Visual Studio Code.lnk", desktop)),
+            ..test_program(0, "Visual Studio Code", &["visual studio code"])
+        };
+        let bing = Program {
+            launch_method: LaunchMethod::File("https://www.bing.com/search?q={}".to_string()),
+            ..test_program(0, "Bing", &["bing"])
+        };
+        let shutdown = Program {
+            launch_method: LaunchMethod::Command("shutdown /s /t 0".to_string()),
+            ..test_program(0, "关机", &["关机"])
+        };
+
+        let filters = parse_query("kind:file in:desktop").filters;
+        assert!(filters.matches(&code, &known_locations));
+        assert!(!filters.matches(&bing, &known_locations));
+
+        let filters = parse_query("kind:web kind:cmd").filters;
+        assert!(filters.matches(&bing, &known_locations));
+        assert!(filters.matches(&shutdown, &known_locations));
+        assert!(!filters.matches(&code, &known_locations));
+
+        assert!(!parse_query("!code")
+            .filters
+            .matches(&code, &known_locations));
+        assert!(parse_query("\"studio code\"")
+            .filters
+            .matches(&code, &known_locations));
+        assert!(parse_query("in:bing.com")
+            .filters
+            .matches(&bing, &known_locations));
+    }
+}
@@ -0,0 +1,132 @@
+/// 这个文件用于把不同搜索模型的基础分数映射到同一个尺度上
+///
+/// 各个搜索模型的分数尺度相差很大（LaunchyQT 模型在十万左右，skim 是整数，语义搜索是相似度），
+/// 而智能排序加上的分数与搜索模型无关，如果不做归一化，同样的排序权重在不同的搜索模型下效果完全不同。
+///
+/// 归一化只作用于搜索模型的匹配分数，程序的固定偏移量与智能排序的加分都在归一化之后才加上
+use crate::program_manager::SearchMatchResult;
+use serde::{Deserialize, Serialize};
+
+/// 归一化后匹配的程序的分数在 [0, NORMALIZED_SCORE_SCALE] 之间
+///
+/// 取值与标准搜索模型常见的分数范围相当，这样原有的排序权重不需要重新调整
+pub const NORMALIZED_SCORE_SCALE: f64 = 20.0;
+
+/// 归一化后不匹配的程序的分数，保证智能排序的加分不会让它们排到匹配的程序前面
+pub const NO_MATCH_SCORE: f64 = -10000.0;
+
+/// z-score 的截断范围（以标准差为单位）
+const Z_SCORE_CLAMP: f64 = 3.0;
+
+/// 基础分数的归一化方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ScoreNormalization {
+    /// 不做归一化，直接使用搜索模型给出的分数
+    #[serde(rename = "none")]
+    #[default]
+    None,
+    /// 按本次查询中匹配的程序的最低分与最高分线性映射
+    #[serde(rename = "min_max")]
+    MinMax,
+    /// 按本次查询中匹配的程序的均值与标准差映射，不容易被个别极端的分数影响
+    #[serde(rename = "z_score")]
+    ZScore,
+}
+
+impl ScoreNormalization {
+    /// 对一次查询的匹配分数做归一化，只有 `is_hit` 的结果参与统计
+    pub(crate) fn normalize(&self, results: &mut [SearchMatchResult]) {
+        let hit_scores: Vec<f64> = results
+            .iter()
+            .filter(|result| result.is_hit)
+            .map(|result| result.score)
+            .collect();
+        let map_score: Box<dyn Fn(f64) -> f64> = match self {
+            ScoreNormalization::None => return,
+            ScoreNormalization::MinMax => {
+                let min = hit_scores.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = hit_scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                Box::new(move |score| {
+                    if max > min {
+                        NORMALIZED_SCORE_SCALE * (score - min) / (max - min)
+                    } else {
+                        NORMALIZED_SCORE_SCALE
+                    }
+                })
+            }
+            ScoreNormalization::ZScore => {
+                let count = hit_scores.len().max(1) as f64;
+                let mean = hit_scores.iter().sum::<f64>() / count;
+                let variance = hit_scores
+                    .iter()
+                    .map(|score| (score - mean).powi(2))
+                    .sum::<f64>()
+                    / count;
+                let std_dev = variance.sqrt();
+                Box::new(move |score| {
+                    if std_dev > 0.0 {
+                        let z = ((score - mean) / std_dev).clamp(-Z_SCORE_CLAMP, Z_SCORE_CLAMP);
+                        NORMALIZED_SCORE_SCALE * (z + Z_SCORE_CLAMP) / (2.0 * Z_SCORE_CLAMP)
+                    } else {
+                        NORMALIZED_SCORE_SCALE
+                    }
+                })
+            }
+        };
+
+        for result in results.iter_mut() {
+            result.score = if result.is_hit {
+                map_score(result.score)
+            } else {
+                NO_MATCH_SCORE
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(scores: &[(f64, bool)]) -> Vec<SearchMatchResult> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(index, (score, is_hit))| SearchMatchResult::new(index as u64, *score, *is_hit))
+            .collect()
+    }
+
+    fn scores(results: &[SearchMatchResult]) -> Vec<f64> {
+        results.iter().map(|result| result.score).collect()
+    }
+
+    #[test]
+    fn test_normalize_scales() {
+        // LaunchyQT 与标准模型的分数尺度不同，归一化后一致
+        let mut launchy = results(&[(100_010.0, true), (10_005.0, true), (-1.0, false)]);
+        let mut standard = results(&[(30.0, true), (12.0, true), (-10000.0, false)]);
+        ScoreNormalization::MinMax.normalize(&mut launchy);
+        ScoreNormalization::MinMax.normalize(&mut standard);
+        assert_eq!(
+            scores(&launchy),
+            vec![NORMALIZED_SCORE_SCALE, 0.0, NO_MATCH_SCORE]
+        );
+        assert_eq!(scores(&launchy), scores(&standard));
+
+        // 只有一个匹配时取最高分
+        let mut single = results(&[(0.3, true)]);
+        ScoreNormalization::ZScore.normalize(&mut single);
+        assert_eq!(scores(&single), vec![NORMALIZED_SCORE_SCALE]);
+
+        let mut z_score = results(&[(1.0, true), (2.0, true), (3.0, true)]);
+        ScoreNormalization::ZScore.normalize(&mut z_score);
+        let z_score = scores(&z_score);
+        assert_eq!(z_score[1], NORMALIZED_SCORE_SCALE / 2.0);
+        assert!(z_score[0] < z_score[1] && z_score[1] < z_score[2]);
+
+        // 默认不做归一化
+        let mut raw = results(&[(5.0, true), (-1.0, false)]);
+        ScoreNormalization::default().normalize(&mut raw);
+        assert_eq!(scores(&raw), vec![5.0, -1.0]);
+    }
+}
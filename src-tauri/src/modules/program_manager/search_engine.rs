@@ -1,6 +1,5 @@
 use crate::error::ResultExt;
-use crate::modules::program_manager::program_ranker::ProgramRanker;
-use crate::modules::program_manager::search_model::Scorer;
+use crate::modules::program_manager::search_model::{KeywordMatch, ScoreFusion, Scorer};
 use crate::modules::program_manager::semantic_manager::SemanticManager;
 use crate::program_manager::remove_repeated_space;
 use crate::program_manager::Program;
@@ -8,6 +7,14 @@ use crate::program_manager::SearchMatchResult;
 use crate::program_manager::SearchModel;
 use crate::Arc;
 use rayon::prelude::*;
+use std::collections::HashSet;
+use tracing::warn;
+
+/// 倒数排名融合的平滑常数，越大则名次靠后的结果与靠前的结果差距越小
+const RRF_K: f64 = 60.0;
+
+/// 混合搜索中，没有字面匹配的程序需要达到的最低语义相似度，低于它的程序不算命中
+const MIN_SEMANTIC_HIT_SIMILARITY: f64 = 0.5;
 
 pub(crate) trait SearchEngine: std::fmt::Debug + Send + Sync {
     /// 执行搜索操作
@@ -15,16 +22,49 @@ pub(crate) trait SearchEngine: std::fmt::Debug + Send + Sync {
     /// # Arguments
     /// * `user_input` - 用户输入的搜索字符串。
     /// * `programs` - 可供搜索的程序列表。
-    /// * `program_ranker` - 程序排序器实例，用于计算排序分数。
     ///
     /// # Returns
-    /// * 一个包含搜索结果的向量，按匹配度按原始数据排列（无排序）。
-    fn perform_search(
+    /// * 一个包含搜索结果的向量，按原始数据排列（无排序）。
+    ///   分数为搜索模型给出的匹配分数，归一化、固定偏移量与智能排序由调用者完成
+    fn perform_search(&self, user_input: &str, programs: &[Arc<Program>])
+        -> Vec<SearchMatchResult>;
+
+    /// 给出一个程序与用户输入的匹配细节（命中的关键字与字符位置），用于结果高亮
+    ///
+    /// 只会对最终展示的少量结果调用，`user_input` 已经过预处理
+    fn match_keyword(&self, _user_input: &str, _program: &Arc<Program>) -> Option<KeywordMatch> {
+        None
+    }
+
+    /// 给出每个关键字单独的得分，用于解释排序结果，`user_input` 已经过预处理
+    ///
+    /// 不以关键字评分的搜索引擎返回空列表
+    fn keyword_scores(&self, _user_input: &str, _program: &Arc<Program>) -> Vec<Option<f64>> {
+        Vec::new()
+    }
+
+    /// 程序注册表发生变化后调用，搜索引擎可以借此（重新）构建索引
+    fn on_registry_changed(&self, _programs: &[Arc<Program>]) {}
+
+    /// 在评分之前筛选出需要评分的程序，`user_input` 已经过预处理
+    ///
+    /// # Returns
+    /// * `None` 表示需要对所有程序评分
+    fn select_candidates(
         &self,
-        user_input: &str,
-        programs: &[Arc<Program>],
-        program_ranker: &ProgramRanker,
-    ) -> Vec<SearchMatchResult>;
+        _user_input: &str,
+        _programs: &[Arc<Program>],
+    ) -> Option<HashSet<u64>> {
+        None
+    }
+
+    /// 表示“不匹配”的基础分数，用于逐字输入时缩小搜索范围
+    ///
+    /// 返回 `Some` 时，对某个输入不匹配（`is_hit` 为 false）的程序，在输入变长后也一定不匹配，
+    /// 并且它的基础分数一定是这个值
+    fn no_match_score(&self) -> Option<f64> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -40,9 +80,7 @@ impl TraditionalSearchEngine {
 
 impl Default for TraditionalSearchEngine {
     fn default() -> Self {
-        Self {
-            search_model: Arc::new(SearchModel::default()),
-        }
+        Self::new(Arc::new(SearchModel::default()))
     }
 }
 
@@ -51,35 +89,65 @@ impl SearchEngine for TraditionalSearchEngine {
         &self,
         user_input: &str,
         programs: &[Arc<Program>],
-        program_ranker: &ProgramRanker,
     ) -> Vec<SearchMatchResult> {
         // 预处理用户输入
         let user_input = user_input.to_lowercase();
         let user_input = remove_repeated_space(&user_input);
 
         let search_model = self.search_model.clone();
+        // 只对筛选出来的程序评分
+        let candidates = self.select_candidates(&user_input, programs);
+        let no_match_score = search_model.no_match_score();
         // 计算所有程序的匹配分数
         programs
             .par_iter()
-            .map(|program| {
+            .filter_map(|program| {
+                if candidates
+                    .as_ref()
+                    .is_some_and(|candidates| !candidates.contains(&program.program_guid))
+                {
+                    // 被筛掉的程序一定不匹配，有固定的不匹配分数时照常给出，结果与不筛选时一致
+                    return no_match_score.map(|no_match_score| {
+                        SearchMatchResult::new(program.program_guid, no_match_score, false)
+                    });
+                }
                 // 基础匹配分数
-                let base_score =
-                    search_model.calculate_score(program, &user_input) + program.stable_bias;
+                let match_score = search_model.calculate_score(program, &user_input);
+                let is_hit = search_model.is_match(match_score);
 
-                // 应用智能排序增强评分
-                let score = program_ranker.calculate_final_score(
-                    base_score,
+                Some(SearchMatchResult::new(
                     program.program_guid,
-                    &user_input,
-                );
-
-                SearchMatchResult {
-                    score,
-                    program_guid: program.program_guid,
-                }
+                    match_score,
+                    is_hit,
+                ))
             })
             .collect()
     }
+
+    fn match_keyword(&self, user_input: &str, program: &Arc<Program>) -> Option<KeywordMatch> {
+        self.search_model.match_keyword(program, user_input)
+    }
+
+    fn keyword_scores(&self, user_input: &str, program: &Arc<Program>) -> Vec<Option<f64>> {
+        self.search_model.keyword_scores(program, user_input)
+    }
+
+    fn on_registry_changed(&self, programs: &[Arc<Program>]) {
+        self.search_model.build_index(programs);
+    }
+
+    fn select_candidates(
+        &self,
+        user_input: &str,
+        _programs: &[Arc<Program>],
+    ) -> Option<HashSet<u64>> {
+        // 只有搜索模型自己知道怎样筛选不会漏掉匹配（例如允许输错几个字符），输入很短或不提供筛选时对所有程序评分
+        self.search_model.prefilter(user_input)
+    }
+
+    fn no_match_score(&self) -> Option<f64> {
+        self.search_model.no_match_score()
+    }
 }
 
 #[derive(Debug)]
@@ -98,7 +166,6 @@ impl SearchEngine for SemanticSearchEngine {
         &self,
         user_input: &str,
         programs: &[Arc<Program>],
-        program_ranker: &ProgramRanker,
     ) -> Vec<SearchMatchResult> {
         let user_input = user_input.to_lowercase();
         let user_input = remove_repeated_space(&user_input);
@@ -112,23 +179,370 @@ impl SearchEngine for SemanticSearchEngine {
         programs
             .par_iter()
             .map(|program| {
-                let base_score = self
+                let score = self
                     .semantic_model
                     .compute_similarity(&user_embedding, &program.embedding)
                     as f64;
 
-                // 应用智能排序增强评分
-                let score = program_ranker.calculate_final_score(
-                    base_score,
-                    program.program_guid,
-                    &user_input,
-                );
+                SearchMatchResult::new(program.program_guid, score, true)
+            })
+            .collect()
+    }
+}
+
+/// 混合搜索引擎：同时计算传统搜索模型的字面匹配分数与语义相似度，再将两者融合
+#[derive(Debug)]
+pub struct HybridSearchEngine {
+    search_model: Arc<SearchModel>,
+    semantic_model: Arc<SemanticManager>,
+    fusion: ScoreFusion,
+    lexical_weight: f64,
+    semantic_weight: f64,
+}
+
+impl HybridSearchEngine {
+    pub fn new(
+        search_model: Arc<SearchModel>,
+        semantic_model: Arc<SemanticManager>,
+        fusion: ScoreFusion,
+        lexical_weight: f64,
+        semantic_weight: f64,
+    ) -> Self {
+        Self {
+            search_model,
+            semantic_model,
+            fusion,
+            lexical_weight: lexical_weight.max(0.0),
+            semantic_weight: semantic_weight.max(0.0),
+        }
+    }
 
-                SearchMatchResult {
-                    score,
-                    program_guid: program.program_guid,
+    /// 计算每个程序融合后的基础分数（不含智能排序）以及是否命中，顺序与 `programs` 一致
+    ///
+    /// 字面匹配，或者语义相似度不低于 [`MIN_SEMANTIC_HIT_SIMILARITY`] 的程序才算命中
+    fn fused_scores(&self, user_input: &str, programs: &[Arc<Program>]) -> Vec<(f64, bool)> {
+        // 字面匹配：不匹配的程序不参与这一路的排名，被搜索模型的索引筛掉的程序一定不匹配
+        let candidates = self.search_model.prefilter(user_input);
+        let lexical_scores: Vec<Option<f64>> = programs
+            .par_iter()
+            .map(|program| {
+                if candidates
+                    .as_ref()
+                    .is_some_and(|candidates| !candidates.contains(&program.program_guid))
+                {
+                    return None;
                 }
+                let match_score = self.search_model.calculate_score(program, user_input);
+                self.search_model
+                    .is_match(match_score)
+                    .then_some(match_score)
+            })
+            .collect();
+
+        // 语义相似度：没有 embedding 时只使用字面匹配的结果
+        let semantic_scores: Vec<Option<f64>> = match self
+            .semantic_model
+            .generate_embedding_for_manager(user_input)
+        {
+            Ok(user_embedding) if !user_embedding.is_empty() => programs
+                .par_iter()
+                .map(|program| {
+                    (!program.embedding.is_empty()).then(|| {
+                        self.semantic_model
+                            .compute_similarity(&user_embedding, &program.embedding)
+                            as f64
+                    })
+                })
+                .collect(),
+            Ok(_) => vec![None; programs.len()],
+            Err(e) => {
+                warn!("生成用户输入的embedding失败，只使用字面匹配: {}", e);
+                vec![None; programs.len()]
+            }
+        };
+
+        let is_hit = lexical_scores
+            .iter()
+            .zip(&semantic_scores)
+            .map(|(lexical, semantic)| {
+                lexical.is_some()
+                    || semantic.is_some_and(|similarity| similarity >= MIN_SEMANTIC_HIT_SIMILARITY)
+            });
+        fuse_scores(
+            self.fusion,
+            self.lexical_weight,
+            self.semantic_weight,
+            &lexical_scores,
+            &semantic_scores,
+        )
+        .into_iter()
+        .zip(is_hit)
+        .collect()
+    }
+}
+
+impl SearchEngine for HybridSearchEngine {
+    fn perform_search(
+        &self,
+        user_input: &str,
+        programs: &[Arc<Program>],
+    ) -> Vec<SearchMatchResult> {
+        let user_input = user_input.to_lowercase();
+        let user_input = remove_repeated_space(&user_input);
+
+        let fused_scores = self.fused_scores(&user_input, programs);
+        programs
+            .iter()
+            .zip(fused_scores)
+            .map(|(program, (score, is_hit))| {
+                SearchMatchResult::new(program.program_guid, score, is_hit)
             })
             .collect()
     }
+
+    fn match_keyword(&self, user_input: &str, program: &Arc<Program>) -> Option<KeywordMatch> {
+        self.search_model.match_keyword(program, user_input)
+    }
+
+    fn keyword_scores(&self, user_input: &str, program: &Arc<Program>) -> Vec<Option<f64>> {
+        self.search_model.keyword_scores(program, user_input)
+    }
+
+    fn on_registry_changed(&self, programs: &[Arc<Program>]) {
+        self.search_model.build_index(programs);
+    }
+}
+
+/// 融合两路分数，结果在 [0, 1] 之间（两路都排第一/都是最高分时为 1）
+///
+/// `None` 表示该程序在这一路中没有结果，不获得这一路的分数
+pub fn fuse_scores(
+    fusion: ScoreFusion,
+    lexical_weight: f64,
+    semantic_weight: f64,
+    lexical_scores: &[Option<f64>],
+    semantic_scores: &[Option<f64>],
+) -> Vec<f64> {
+    let (lexical, semantic) = match fusion {
+        ScoreFusion::ReciprocalRank => (
+            reciprocal_ranks(lexical_scores),
+            reciprocal_ranks(semantic_scores),
+        ),
+        ScoreFusion::WeightedSum => (
+            min_max_normalize(lexical_scores),
+            min_max_normalize(semantic_scores),
+        ),
+    };
+    let total_weight = (lexical_weight + semantic_weight).max(f64::EPSILON);
+    lexical
+        .iter()
+        .zip(semantic.iter())
+        .map(|(l, s)| (lexical_weight * l + semantic_weight * s) / total_weight)
+        .collect()
+}
+
+/// 按分数从高到低排名（并列的分数名次相同），返回 (K + 1) / (K + 名次)
+fn reciprocal_ranks(scores: &[Option<f64>]) -> Vec<f64> {
+    let mut order: Vec<(usize, f64)> = scores
+        .iter()
+        .enumerate()
+        .filter_map(|(index, score)| score.map(|score| (index, score)))
+        .collect();
+    order.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut result = vec![0.0; scores.len()];
+    let mut rank = 0;
+    let mut last_score = None;
+    for (position, (index, score)) in order.into_iter().enumerate() {
+        if last_score != Some(score) {
+            rank = position + 1;
+            last_score = Some(score);
+        }
+        result[index] = (RRF_K + 1.0) / (RRF_K + rank as f64);
+    }
+    result
+}
+
+/// 将分数线性映射到 [0, 1]，所有分数都相同时记为 1
+fn min_max_normalize(scores: &[Option<f64>]) -> Vec<f64> {
+    let present = scores.iter().flatten();
+    let min = present.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = present.copied().fold(f64::NEG_INFINITY, f64::max);
+    scores
+        .iter()
+        .map(|score| match score {
+            Some(score) if max > min => (score - min) / (max - min),
+            Some(_) => 1.0,
+            None => 0.0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppResult;
+    use crate::modules::program_manager::semantic_manager::EmbeddingBackend;
+    use crate::modules::program_manager::unit::EmbeddingVec;
+    use crate::program_manager::search_model::{SearchModelConfig, SearchModelFactory};
+    use crate::program_manager::test_support::test_program;
+    use crate::program_manager::LaunchMethod;
+    use std::collections::HashMap;
+
+    /// 不依赖模型文件的 embedding 后端：按几个固定的“主题词”生成向量
+    struct StubBackend;
+
+    const TOPICS: [&[&str]; 3] = [
+        &["browser", "web", "chrome", "浏览器"],
+        &["editor", "code", "text", "编辑"],
+        &["music", "audio", "音乐"],
+    ];
+
+    fn topic_embedding(text: &str) -> EmbeddingVec {
+        TOPICS
+            .iter()
+            .map(|words| words.iter().filter(|word| text.contains(*word)).count() as f32)
+            .collect()
+    }
+
+    impl EmbeddingBackend for StubBackend {
+        fn generate_embedding_for_loader(
+            &self,
+            show_name: &str,
+            search_keywords: &str,
+            _launch_method: &LaunchMethod,
+            description: &str,
+        ) -> AppResult<EmbeddingVec> {
+            Ok(topic_embedding(
+                &format!("{} {} {}", show_name, search_keywords, description).to_lowercase(),
+            ))
+        }
+
+        fn generate_embedding_for_manager(&self, user_input: &str) -> AppResult<EmbeddingVec> {
+            Ok(topic_embedding(user_input))
+        }
+
+        fn compute_similarity(&self, embedding1: &EmbeddingVec, embedding2: &EmbeddingVec) -> f32 {
+            let dot: f32 = embedding1.iter().zip(embedding2).map(|(a, b)| a * b).sum();
+            let norm = |v: &EmbeddingVec| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            let denominator = norm(embedding1) * norm(embedding2);
+            if denominator == 0.0 {
+                0.0
+            } else {
+                dot / denominator
+            }
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+    }
+
+    fn program(
+        guid: u64,
+        name: &str,
+        description: &str,
+        semantic_manager: &SemanticManager,
+    ) -> Arc<Program> {
+        let keyword = name.to_lowercase();
+        let mut program = test_program(guid, name, &[&keyword]);
+        program.embedding = semantic_manager
+            .generate_embedding_for_loader(name, &keyword, &program.launch_method, description)
+            .unwrap();
+        Arc::new(program)
+    }
+
+    /// 混合搜索的测试环境：三个主题不同的程序，以及使用给定字面匹配模型的混合搜索引擎
+    fn hybrid_fixture(lexical_model: SearchModelConfig) -> (HybridSearchEngine, Vec<Arc<Program>>) {
+        let semantic_manager = Arc::new(SemanticManager::new(
+            Some(Arc::new(StubBackend)),
+            HashMap::new(),
+        ));
+        let programs = vec![
+            program(0, "Google Chrome", "web browser", &semantic_manager),
+            program(1, "Visual Studio Code", "code editor", &semantic_manager),
+            program(2, "Spotify", "music player", &semantic_manager),
+        ];
+        let engine = HybridSearchEngine::new(
+            Arc::new(SearchModelFactory::create_scorer(Arc::new(lexical_model))),
+            semantic_manager,
+            ScoreFusion::ReciprocalRank,
+            0.5,
+            0.5,
+        );
+        engine.on_registry_changed(&programs);
+        (engine, programs)
+    }
+
+    #[test]
+    fn test_fuse_scores() {
+        let lexical = [Some(10.0), Some(5.0), None];
+        let semantic = [Some(0.1), Some(0.9), Some(0.5)];
+
+        let rrf = fuse_scores(ScoreFusion::ReciprocalRank, 1.0, 0.0, &lexical, &semantic);
+        assert_eq!(rrf[0], 1.0);
+        assert!(rrf[1] < 1.0);
+        assert_eq!(rrf[2], 0.0);
+
+        let weighted = fuse_scores(ScoreFusion::WeightedSum, 0.5, 0.5, &lexical, &semantic);
+        assert_eq!(weighted, vec![0.5, 0.5, 0.25]);
+
+        // 并列的分数名次相同
+        assert_eq!(reciprocal_ranks(&[Some(1.0), Some(1.0)]), vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_hybrid_search_with_stub_backend() {
+        let (engine, programs) = hybrid_fixture(SearchModelConfig::Standard);
+
+        let best = |input: &str| {
+            let scores = engine.fused_scores(input, &programs);
+            (0..programs.len())
+                .max_by(|a, b| scores[*a].0.total_cmp(&scores[*b].0))
+                .unwrap()
+        };
+        // 只有字面匹配能找到
+        assert_eq!(best("spot"), 2);
+        // 只有语义能找到
+        assert_eq!(best("browser"), 0);
+        // 两路一致时得分最高
+        assert_eq!(engine.fused_scores("code", &programs)[1], (1.0, true));
+    }
+
+    #[test]
+    fn test_hybrid_search_hits() {
+        let (engine, programs) = hybrid_fixture(SearchModelConfig::Typo);
+
+        let hits = |input: &str| -> Vec<u64> {
+            engine
+                .perform_search(input, &programs)
+                .into_iter()
+                .filter(|result| result.is_hit)
+                .map(|result| result.program_guid)
+                .collect()
+        };
+        // 字面匹配
+        assert_eq!(hits("spot"), vec![2]);
+        // 只有语义相似
+        assert_eq!(hits("web"), vec![0]);
+        // 两路都不匹配时没有命中
+        assert!(hits("zzzz").is_empty());
+    }
+
+    #[test]
+    fn test_hybrid_search_finds_two_typos() {
+        let (engine, programs) = hybrid_fixture(SearchModelConfig::Typo);
+
+        // "cyrzme" 与 "chrome" 相差两个字符，输入较短时不能因为缺少字符而被筛掉
+        let results = engine.perform_search("cyrzme", &programs);
+        let best = results
+            .iter()
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .unwrap();
+        assert_eq!(best.program_guid, 0);
+        assert!(results
+            .iter()
+            .filter(|result| result.program_guid != 0)
+            .all(|result| result.score < best.score));
+    }
 }
@@ -0,0 +1,356 @@
+/// 这个文件实现了离线的搜索质量评估
+///
+/// 评估数据集是一个 JSON 文件，包含一份合成的程序目录与一组“查询 => 期望启动的程序”。
+/// 每个传统搜索模型都会通过 `ProgramManager` 的搜索流程跑一遍所有查询（查询语法、候选筛选、归一化、
+/// 智能排序与固定项都与真实搜索一致），统计 MRR、top-1 与 top-5 命中率，
+/// 用于判断对评分器或排序权重的修改是否真的让结果变好。
+///
+/// 整个过程不依赖 Windows 与界面，可以在 Linux 上直接运行：`cargo test --lib search_eval`，
+/// 指标低于记录的基线时，测试失败并给出完整的报告。
+/// 设置环境变量 `ZEROLAUNCH_EVAL_DATASET` 可以改用其他的数据集文件（此时只检查指标是否自洽，不与基线比较）
+use crate::core::image_processor::ImageIdentity;
+use crate::error::AppResult;
+use crate::modules::program_manager::config::program_ranker_config::{
+    PartialProgramRankerConfig, ProgramRankerConfig,
+};
+use crate::modules::program_manager::program_loader::ProgramLoader;
+use crate::modules::program_manager::score_normalizer::ScoreNormalization;
+use crate::modules::program_manager::search_engine::TraditionalSearchEngine;
+use crate::modules::program_manager::semantic_manager::SemanticManager;
+use crate::program_manager::search_model::{SearchModelConfig, SearchModelFactory};
+use crate::program_manager::{LaunchMethod, Program, ProgramManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::warn;
+
+/// 统计 top-k 命中率时使用的 k
+const TOP_K: usize = 5;
+
+/// 合成目录中的一个程序
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalProgram {
+    /// 展示给用户看的名字，搜索关键字与真实加载时一样由它生成
+    pub show_name: String,
+    pub launch_method: LaunchMethod,
+    /// 额外的别名
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub stable_bias: f64,
+}
+
+/// 一条标注好的查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenQuery {
+    pub query: String,
+    /// 期望排在第一位的程序
+    pub expected: LaunchMethod,
+}
+
+/// 评估数据集
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalDataset {
+    pub programs: Vec<EvalProgram>,
+    pub queries: Vec<GoldenQuery>,
+    /// 排序器的配置（权重与启动历史），不提供时使用默认权重且没有历史
+    #[serde(default)]
+    pub ranker: Option<PartialProgramRankerConfig>,
+}
+
+impl EvalDataset {
+    pub fn from_json(text: &str) -> AppResult<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn load(path: &Path) -> AppResult<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// 一个搜索模型的评估结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalMetrics {
+    /// 搜索模型的名字
+    pub model: String,
+    pub query_count: usize,
+    /// 平均倒数排名 (Mean Reciprocal Rank)
+    pub mrr: f64,
+    /// 期望的程序排在第一位的比例
+    pub top1: f64,
+    /// 期望的程序排在前五位的比例
+    pub top5: f64,
+    /// 期望的程序没有排进前五的查询
+    pub misses: Vec<String>,
+}
+
+/// 参与评估的搜索模型
+pub fn traditional_models() -> Vec<SearchModelConfig> {
+    vec![
+        SearchModelConfig::Standard,
+        SearchModelConfig::Skim,
+        SearchModelConfig::Launchy,
+        SearchModelConfig::Typo,
+    ]
+}
+
+/// 搜索模型在报告中显示的名字
+fn model_name(model: &SearchModelConfig) -> String {
+    serde_json::to_value(model)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("{:?}", model))
+}
+
+/// 离线搜索评估器
+pub struct SearchEvaluator {
+    programs: Vec<Arc<Program>>,
+    ranker: Option<PartialProgramRankerConfig>,
+    score_normalization: ScoreNormalization,
+}
+
+impl SearchEvaluator {
+    /// 使用数据集中的程序目录与排序器配置构建评估环境
+    pub fn new(dataset: &EvalDataset, score_normalization: ScoreNormalization) -> Self {
+        let program_loader =
+            ProgramLoader::new(Arc::new(SemanticManager::new(None, HashMap::new())));
+        let programs: Vec<Arc<Program>> = dataset
+            .programs
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let mut search_keywords = program_loader.convert_search_keywords(&item.show_name);
+                for alias in &item.aliases {
+                    search_keywords.extend(program_loader.convert_search_keywords(alias));
+                }
+                Arc::new(Program {
+                    program_guid: index as u64,
+                    show_name: item.show_name.clone(),
+                    launch_method: item.launch_method.clone(),
+                    search_keywords,
+                    stable_bias: item.stable_bias,
+                    icon_path: ImageIdentity::File(String::new()),
+                    embedding: Vec::new(),
+                })
+            })
+            .collect();
+
+        SearchEvaluator {
+            programs,
+            ranker: dataset.ranker.clone(),
+            score_normalization,
+        }
+    }
+
+    /// 使用数据集中的程序目录与给定的搜索模型创建一个程序管理器
+    fn build_manager(&self, model: &SearchModelConfig) -> ProgramManager {
+        let search_model = SearchModelFactory::create_scorer(Arc::new(model.clone()));
+        let ranker_config = ProgramRankerConfig::default();
+        if let Some(partial) = &self.ranker {
+            ranker_config.update(partial.clone());
+        }
+        ProgramManager::with_programs(
+            self.programs.clone(),
+            Arc::new(TraditionalSearchEngine::new(Arc::new(search_model))),
+            &ranker_config,
+            self.score_normalization,
+        )
+    }
+
+    /// 按真实搜索的流程排序，返回所有程序按得分降序排列的启动方式
+    async fn rank(&self, program_manager: &ProgramManager, query: &str) -> Vec<&LaunchMethod> {
+        program_manager
+            .perform_search(query, self.programs.len() as u32)
+            .await
+            .iter()
+            .map(|result| &self.programs[result.program_guid as usize].launch_method)
+            .collect()
+    }
+
+    /// 评估一个搜索模型
+    pub async fn evaluate(
+        &self,
+        model: &SearchModelConfig,
+        queries: &[GoldenQuery],
+    ) -> EvalMetrics {
+        let program_manager = self.build_manager(model);
+
+        let mut reciprocal_rank_sum = 0.0;
+        let mut top1_count = 0;
+        let mut top5_count = 0;
+        let mut misses = Vec::new();
+        for golden in queries {
+            let ranked = self.rank(&program_manager, &golden.query).await;
+            match ranked.iter().position(|method| **method == golden.expected) {
+                Some(position) => {
+                    reciprocal_rank_sum += 1.0 / (position + 1) as f64;
+                    if position == 0 {
+                        top1_count += 1;
+                    }
+                    if position < TOP_K {
+                        top5_count += 1;
+                    } else {
+                        misses.push(golden.query.clone());
+                    }
+                }
+                None => {
+                    // 没有出现在结果中：被索引筛掉了，或者根本不在目录中（数据集写错了）
+                    if !self
+                        .programs
+                        .iter()
+                        .any(|program| program.launch_method == golden.expected)
+                    {
+                        warn!(
+                            "查询 {} 期望的程序不在目录中: {}",
+                            golden.query,
+                            golden.expected.get_text()
+                        );
+                    }
+                    misses.push(golden.query.clone());
+                }
+            }
+        }
+
+        let query_count = queries.len();
+        let ratio = |count: usize| count as f64 / query_count.max(1) as f64;
+        EvalMetrics {
+            model: model_name(model),
+            query_count,
+            mrr: reciprocal_rank_sum / query_count.max(1) as f64,
+            top1: ratio(top1_count),
+            top5: ratio(top5_count),
+            misses,
+        }
+    }
+
+    /// 评估所有给定的搜索模型
+    pub async fn evaluate_models(
+        &self,
+        models: &[SearchModelConfig],
+        queries: &[GoldenQuery],
+    ) -> Vec<EvalMetrics> {
+        let mut metrics = Vec::new();
+        for model in models {
+            metrics.push(self.evaluate(model, queries).await);
+        }
+        metrics
+    }
+}
+
+/// 将评估结果格式化为便于在终端中阅读的表格
+pub fn format_report(metrics: &[EvalMetrics]) -> String {
+    let mut report = format!(
+        "{:<10} {:>8} {:>8} {:>8} {:>8}\n",
+        "model", "queries", "mrr", "top1", "top5"
+    );
+    for item in metrics {
+        report.push_str(&format!(
+            "{:<10} {:>8} {:>8.3} {:>8.3} {:>8.3}\n",
+            item.model, item.query_count, item.mrr, item.top1, item.top5
+        ));
+        if !item.misses.is_empty() {
+            report.push_str(&format!(
+                "  top{} 未命中: {}\n",
+                TOP_K,
+                item.misses.join(", ")
+            ));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 仓库自带的评估数据集
+    const GOLDEN_QUERIES: &str = include_str!("golden_queries.json");
+
+    /// 各个搜索模型在自带数据集上测得的 (mrr, top1, top5)，改进了搜索质量后应该同步提高
+    const BASELINES: [(&str, f64, f64, f64); 4] = [
+        ("standard", 0.960, 0.943, 1.0),
+        ("skim", 0.944, 0.924, 0.962),
+        ("launchy", 0.962, 0.943, 0.981),
+        ("typo", 0.975, 0.962, 1.0),
+    ];
+
+    fn path(text: &str) -> LaunchMethod {
+        LaunchMethod::Path(text.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let dataset = EvalDataset {
+            programs: vec![
+                EvalProgram {
+                    show_name: "Notepad".to_string(),
+                    launch_method: path("notepad"),
+                    aliases: Vec::new(),
+                    stable_bias: 0.0,
+                },
+                EvalProgram {
+                    show_name: "Notepad++".to_string(),
+                    launch_method: path("notepad++"),
+                    aliases: Vec::new(),
+                    stable_bias: 0.0,
+                },
+            ],
+            queries: vec![
+                GoldenQuery {
+                    query: "notepad++".to_string(),
+                    expected: path("notepad++"),
+                },
+                GoldenQuery {
+                    query: "notepad".to_string(),
+                    expected: path("missing"),
+                },
+            ],
+            ranker: None,
+        };
+        let evaluator = SearchEvaluator::new(&dataset, ScoreNormalization::default());
+        let metrics = evaluator
+            .evaluate(&SearchModelConfig::Standard, &dataset.queries)
+            .await;
+        assert_eq!(metrics.model, "standard");
+        assert_eq!(metrics.query_count, 2);
+        assert_eq!(metrics.top1, 0.5);
+        assert_eq!(metrics.mrr, 0.5);
+        assert_eq!(metrics.misses, vec!["notepad"]);
+    }
+
+    /// 在黄金查询集上评估所有传统搜索模型，指标不能低于记录的基线
+    #[tokio::test]
+    async fn test_golden_queries() {
+        let custom_dataset = std::env::var("ZEROLAUNCH_EVAL_DATASET").ok();
+        let dataset = match &custom_dataset {
+            Some(path) => EvalDataset::load(Path::new(path)).unwrap(),
+            None => EvalDataset::from_json(GOLDEN_QUERIES).unwrap(),
+        };
+        let evaluator = SearchEvaluator::new(&dataset, ScoreNormalization::default());
+        let metrics = evaluator
+            .evaluate_models(&traditional_models(), &dataset.queries)
+            .await;
+        let report = format_report(&metrics);
+
+        assert_eq!(metrics.len(), traditional_models().len());
+        for item in &metrics {
+            assert_eq!(item.query_count, dataset.queries.len());
+            assert!(item.top1 <= item.top5);
+            assert!(item.top1 <= item.mrr && item.mrr <= 1.0);
+            // 自带的数据集上，任何一项指标低于基线都说明搜索质量退化了
+            if custom_dataset.is_none() {
+                let (_, mrr, top1, top5) = BASELINES
+                    .iter()
+                    .find(|(model, ..)| *model == item.model)
+                    .unwrap();
+                assert!(
+                    item.mrr >= *mrr && item.top1 >= *top1 && item.top5 >= *top5,
+                    "{} 的指标低于基线\n{}",
+                    item.model,
+                    report
+                );
+            }
+        }
+    }
+}
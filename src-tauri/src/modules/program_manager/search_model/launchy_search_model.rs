@@ -1,11 +1,14 @@
-use crate::program_manager::search_model::Scorer;
+use crate::program_manager::search_model::{
+    best_keyword_match, KeywordIndex, KeywordMatch, Scorer,
+};
 use crate::program_manager::Program;
 /// 这个文件是以LaunchyQT的搜索模型为基础进行的改造
 /// 项目地址如下：https://github.com/samsonwang/LaunchyQt
 /// 但是launchyqt是基于比较进行搜索的，而不是基于分数的
 /// 所以我对这个搜索算法做了一些修改，从而可以适应当前的搜索框架
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::Arc;
 /// `LaunchyScorer` 实现了模仿 LaunchyQT 搜索算法的评分策略。
@@ -23,7 +26,10 @@ use std::sync::Arc;
 /// 注意: Launchy 的 'usage' (使用频率) 动态权重部分没有在这里实现，
 /// 因为框架已在外部处理了动态分数（`program_dynamic_value_based_launch_time`）。
 #[derive(Serialize, Deserialize)]
-pub struct LaunchyScorer {}
+pub struct LaunchyScorer {
+    #[serde(skip)]
+    index: RwLock<KeywordIndex>,
+}
 
 impl Debug for LaunchyScorer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -39,7 +45,60 @@ impl Default for LaunchyScorer {
 
 impl LaunchyScorer {
     pub fn new() -> Self {
-        LaunchyScorer {}
+        LaunchyScorer {
+            index: RwLock::new(KeywordIndex::default()),
+        }
+    }
+
+    /// 计算单个关键字的得分，未匹配时返回 -1.0
+    fn keyword_score(&self, keyword: &str, user_input: &str) -> f64 {
+        let mut current_score = -1.0;
+
+        // Step 1: 精确匹配 (最高优先级)
+        if keyword.eq_ignore_ascii_case(user_input) {
+            const EXACT_MATCH_BASE_SCORE: f64 = 100_000.0;
+            current_score = EXACT_MATCH_BASE_SCORE;
+        } else {
+            // Step 2: 连续子串匹配 (次高优先级)
+            if let Some(start_index) = keyword.to_lowercase().find(user_input) {
+                const CONTIGUOUS_MATCH_BASE_SCORE: f64 = 10_000.0;
+                // 匹配位置越靠前，分数越高。每个字符的偏移惩罚10分。
+                let position_penalty = (start_index as f64) * 10.0;
+                current_score = CONTIGUOUS_MATCH_BASE_SCORE - position_penalty;
+            } else {
+                let mut compare_chars = HashMap::with_capacity(keyword.len());
+
+                // 统计 compare_name 中字符出现次数
+                for c in keyword.chars() {
+                    *compare_chars.entry(c).or_insert(0) += 1;
+                }
+
+                // 计算匹配的字符数
+                let mut result = 0;
+                for c in user_input.chars() {
+                    if let Some(count) = compare_chars.get_mut(&c) {
+                        if *count > 0 {
+                            result += 1;
+                            *count -= 1;
+                        }
+                    }
+                }
+
+                if result == user_input.len() {
+                    const SUBSET_MATCH_BASE_SCORE: f64 = 1_000.0;
+                    current_score = SUBSET_MATCH_BASE_SCORE;
+                }
+            }
+        }
+
+        if current_score > -1.0 {
+            // Step 4: 名称长度惩罚 (用于打破平局)
+            // 名称越长，加成越小。避免除以零。
+            let length_bonus = 10.0 / (keyword.len() as f64 + 1.0);
+            current_score += length_bonus;
+        }
+
+        current_score
     }
 }
 
@@ -53,56 +112,49 @@ impl Scorer for LaunchyScorer {
         let mut max_score = -1.0; // 使用一个负数作为未匹配的初始值
 
         for keyword in &program.search_keywords {
-            let mut current_score = -1.0;
-
-            // Step 1: 精确匹配 (最高优先级)
-            if keyword.eq_ignore_ascii_case(user_input) {
-                const EXACT_MATCH_BASE_SCORE: f64 = 100_000.0;
-                current_score = EXACT_MATCH_BASE_SCORE;
-            } else {
-                // Step 2: 连续子串匹配 (次高优先级)
-                if let Some(start_index) = keyword.to_lowercase().find(user_input) {
-                    const CONTIGUOUS_MATCH_BASE_SCORE: f64 = 10_000.0;
-                    // 匹配位置越靠前，分数越高。每个字符的偏移惩罚10分。
-                    let position_penalty = (start_index as f64) * 10.0;
-                    current_score = CONTIGUOUS_MATCH_BASE_SCORE - position_penalty;
-                } else {
-                    let mut compare_chars = HashMap::with_capacity(keyword.len());
-
-                    // 统计 compare_name 中字符出现次数
-                    for c in keyword.chars() {
-                        *compare_chars.entry(c).or_insert(0) += 1;
-                    }
-
-                    // 计算匹配的字符数
-                    let mut result = 0;
-                    for c in user_input.chars() {
-                        if let Some(count) = compare_chars.get_mut(&c) {
-                            if *count > 0 {
-                                result += 1;
-                                *count -= 1;
-                            }
-                        }
-                    }
-
-                    if result == user_input.len() {
-                        const SUBSET_MATCH_BASE_SCORE: f64 = 1_000.0;
-                        current_score = SUBSET_MATCH_BASE_SCORE;
-                    }
-                }
-            }
-
-            if current_score > -1.0 {
-                // Step 4: 名称长度惩罚 (用于打破平局)
-                // 名称越长，加成越小。避免除以零。
-                let length_bonus = 10.0 / (keyword.len() as f64 + 1.0);
-                current_score += length_bonus;
-            }
-
+            let current_score = self.keyword_score(keyword, user_input);
             if current_score > max_score {
                 max_score = current_score;
             }
         }
         max_score
     }
+
+    fn match_keyword(&self, program: &Arc<Program>, user_input: &str) -> Option<KeywordMatch> {
+        if user_input.is_empty() {
+            return None;
+        }
+        best_keyword_match(program, user_input, |keyword| {
+            let score = self.keyword_score(keyword, user_input);
+            (score > -1.0).then_some(score)
+        })
+    }
+
+    fn keyword_scores(&self, program: &Arc<Program>, user_input: &str) -> Vec<Option<f64>> {
+        program
+            .search_keywords
+            .iter()
+            .map(|keyword| {
+                if user_input.is_empty() {
+                    return None;
+                }
+                let score = self.keyword_score(keyword, user_input);
+                (score > -1.0).then_some(score)
+            })
+            .collect()
+    }
+
+    fn build_index(&self, programs: &[Arc<Program>]) {
+        *self.index.write() = KeywordIndex::build(programs);
+    }
+
+    fn prefilter(&self, user_input: &str) -> Option<HashSet<u64>> {
+        // 精确、连续子串、子集匹配都要求输入中的每个字符出现在关键字里
+        self.index.read().containing_all_chars(user_input)
+    }
+
+    fn no_match_score(&self) -> Option<f64> {
+        // 精确、连续子串、子集匹配在输入变长后都不会由不匹配变为匹配
+        Some(-1.0)
+    }
 }
@@ -2,12 +2,15 @@ pub mod ai_fuzzy_search_model;
 pub mod launchy_search_model;
 pub mod skim_search_model;
 pub mod standard_search_model;
+pub mod typo_search_model;
 use crate::program_manager::search_model::launchy_search_model::LaunchyScorer;
 use crate::program_manager::search_model::skim_search_model::SkimScorer;
 use crate::program_manager::search_model::standard_search_model::StandardScorer;
+use crate::program_manager::search_model::typo_search_model::TypoScorer;
 use crate::program_manager::Program;
 use core::f64;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 /// SearchModel 表示一个综合的搜索模型
 ///
 /// Preprocessor 表示一个预处理函数，会在加载程序，和预处理用户输入时使用。
@@ -28,6 +31,207 @@ pub trait Scorer: Send + Sync + std::fmt::Debug {
     /// # Returns
     /// * 一个 f64 类型的分数，分数越高表示匹配度越高。
     fn calculate_score(&self, program: &Arc<Program>, user_input: &str) -> f64;
+
+    /// 找出得分最高的关键字，以及用户输入命中了这个关键字中的哪些字符（用于高亮）
+    ///
+    /// # Returns
+    /// * 没有任何关键字可以匹配时返回 `None`
+    fn match_keyword(&self, program: &Arc<Program>, user_input: &str) -> Option<KeywordMatch>;
+
+    /// 计算每个关键字单独的得分（用于调试页面解释排序结果）
+    ///
+    /// # Returns
+    /// * 与 `Program::search_keywords` 一一对应，`None` 表示这个关键字不匹配
+    fn keyword_scores(&self, program: &Arc<Program>, user_input: &str) -> Vec<Option<f64>>;
+
+    /// 程序注册表发生变化后调用，用于（重新）构建评分器自己的索引
+    ///
+    /// 默认不需要索引
+    fn build_index(&self, _programs: &[Arc<Program>]) {}
+
+    /// 使用索引预先筛选出可能匹配的程序
+    ///
+    /// 筛选必须是安全的：被筛掉的程序在这个评分器下一定不匹配
+    ///
+    /// # Returns
+    /// * `None` 表示无法筛选，需要对所有程序评分
+    fn prefilter(&self, _user_input: &str) -> Option<HashSet<u64>> {
+        None
+    }
+
+    /// 表示“不匹配”的分数
+    ///
+    /// 只有当评分器满足“对某个输入不匹配的程序，在输入变长后也一定不匹配”时才返回 `Some`，
+    /// 此时逐字输入可以只在上一次匹配到的程序中继续搜索
+    fn no_match_score(&self) -> Option<f64> {
+        None
+    }
+
+    /// 判断一个分数是否表示匹配，不匹配的程序不参与分数归一化
+    ///
+    /// 默认使用 `no_match_score` 判断，没有时认为都匹配
+    fn is_match(&self, score: f64) -> bool {
+        self.no_match_score()
+            .is_none_or(|no_match_score| score > no_match_score)
+    }
+}
+
+/// 一次匹配中得分最高的关键字
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordMatch {
+    /// 关键字在 `Program::search_keywords` 中的下标
+    pub keyword_index: usize,
+    /// 被命中的字符在关键字中的下标（以 char 为单位，升序）
+    pub indices: Vec<usize>,
+}
+
+/// 按每个关键字的得分选出最佳关键字，并用 `find_match_indices` 计算命中的字符
+pub fn best_keyword_match<F>(
+    program: &Program,
+    user_input: &str,
+    keyword_score: F,
+) -> Option<KeywordMatch>
+where
+    F: Fn(&str) -> Option<f64>,
+{
+    let mut best: Option<(usize, f64)> = None;
+    for (index, keyword) in program.search_keywords.iter().enumerate() {
+        if let Some(score) = keyword_score(keyword) {
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((index, score));
+            }
+        }
+    }
+    best.map(|(keyword_index, _)| KeywordMatch {
+        keyword_index,
+        indices: find_match_indices(&program.search_keywords[keyword_index], user_input),
+    })
+}
+
+/// 输入少于这么多个字符时不使用关键字索引筛选：短输入几乎能命中所有程序，直接全部评分更快
+const MIN_INDEXED_INPUT_LEN: usize = 3;
+
+/// 关键字的字符倒排索引，加载程序时构建，用于在评分前筛掉一定不匹配的程序
+#[derive(Debug, Default)]
+pub struct KeywordIndex {
+    /// 字符 => 关键字中含有这个字符的程序，按 guid 升序
+    ///
+    /// 同时记录关键字本身与它小写形式中的字符，这样无论评分器是否区分大小写，筛选都不会漏掉匹配
+    char_postings: HashMap<char, Vec<u64>>,
+    /// 按最长关键字的字符数升序排列的 (字符数, guid)
+    keyword_lens: Vec<(usize, u64)>,
+}
+
+impl KeywordIndex {
+    pub fn build(programs: &[Arc<Program>]) -> Self {
+        let mut char_postings: HashMap<char, Vec<u64>> = HashMap::new();
+        let mut keyword_lens = Vec::with_capacity(programs.len());
+        for program in programs {
+            let mut seen_chars: HashSet<char> = HashSet::new();
+            let mut max_len = 0;
+            for keyword in &program.search_keywords {
+                let lowercase = keyword.to_lowercase();
+                max_len = max_len
+                    .max(keyword.chars().count())
+                    .max(lowercase.chars().count());
+                seen_chars.extend(keyword.chars());
+                seen_chars.extend(lowercase.chars());
+            }
+            for c in seen_chars {
+                char_postings
+                    .entry(c)
+                    .or_default()
+                    .push(program.program_guid);
+            }
+            keyword_lens.push((max_len, program.program_guid));
+        }
+        for guids in char_postings.values_mut() {
+            guids.sort_unstable();
+        }
+        keyword_lens.sort_unstable();
+        KeywordIndex {
+            char_postings,
+            keyword_lens,
+        }
+    }
+
+    /// 至少有一个关键字不比输入短的程序
+    ///
+    /// # Returns
+    /// * 输入太短、筛选不划算时返回 `None`
+    pub fn with_long_enough_keyword(&self, user_input: &str) -> Option<HashSet<u64>> {
+        let input_len = user_input.chars().count();
+        if input_len < MIN_INDEXED_INPUT_LEN {
+            return None;
+        }
+        let start = self
+            .keyword_lens
+            .partition_point(|(len, _)| *len < input_len);
+        Some(
+            self.keyword_lens[start..]
+                .iter()
+                .map(|(_, guid)| *guid)
+                .collect(),
+        )
+    }
+
+    /// 关键字中含有输入里的每一个字符（忽略空白），并且至少有一个关键字不比输入短的程序
+    ///
+    /// 字符按程序的所有关键字合并统计，比“同一个关键字含有所有字符”宽松，所以不会漏掉匹配
+    ///
+    /// # Returns
+    /// * 输入太短、筛选不划算时返回 `None`
+    pub fn containing_all_chars(&self, user_input: &str) -> Option<HashSet<u64>> {
+        let long_enough = self.with_long_enough_keyword(user_input)?;
+        let input_chars: HashSet<char> =
+            user_input.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut postings = Vec::with_capacity(input_chars.len());
+        for c in &input_chars {
+            match self.char_postings.get(c) {
+                Some(guids) => postings.push(guids),
+                // 没有任何程序含有这个字符
+                None => return Some(HashSet::new()),
+            }
+        }
+        // 从最短的倒排表开始求交集
+        postings.sort_unstable_by_key(|guids| guids.len());
+        let Some((shortest, rest)) = postings.split_first() else {
+            return Some(long_enough);
+        };
+        Some(
+            shortest
+                .iter()
+                .filter(|guid| {
+                    long_enough.contains(guid)
+                        && rest.iter().all(|guids| guids.binary_search(guid).is_ok())
+                })
+                .copied()
+                .collect(),
+        )
+    }
+}
+
+/// 计算用户输入在关键字中命中的字符位置
+///
+/// 优先使用连续子串匹配，否则按顺序贪心匹配子序列，剩余无法按顺序匹配的字符会被忽略
+pub fn find_match_indices(keyword: &str, user_input: &str) -> Vec<usize> {
+    if user_input.is_empty() {
+        return Vec::new();
+    }
+    if let Some(byte_index) = keyword.find(user_input) {
+        let start = keyword[..byte_index].chars().count();
+        return (start..start + user_input.chars().count()).collect();
+    }
+    let keyword_chars: Vec<char> = keyword.chars().collect();
+    let mut result = Vec::new();
+    let mut position = 0;
+    for c in user_input.chars() {
+        if let Some(offset) = keyword_chars[position..].iter().position(|k| *k == c) {
+            result.push(position + offset);
+            position += offset + 1;
+        }
+    }
+    result
 }
 
 /// 搜索模型的配置信息（用于序列化/反序列化）
@@ -40,8 +244,42 @@ pub enum SearchModelConfig {
     Standard,
     #[serde(rename = "launchy")]
     Launchy,
+    #[serde(rename = "typo")]
+    Typo,
     #[serde(rename = "semantic")]
     Semantic,
+    /// 同时使用传统搜索模型与语义搜索，并将两者的结果融合
+    #[serde(rename = "hybrid")]
+    Hybrid {
+        /// 负责字面匹配的传统搜索模型
+        #[serde(default)]
+        lexical_model: Box<SearchModelConfig>,
+        /// 融合方式
+        #[serde(default)]
+        fusion: ScoreFusion,
+        /// 字面匹配结果的权重
+        #[serde(default = "default_hybrid_weight")]
+        lexical_weight: f64,
+        /// 语义搜索结果的权重
+        #[serde(default = "default_hybrid_weight")]
+        semantic_weight: f64,
+    },
+}
+
+fn default_hybrid_weight() -> f64 {
+    0.5
+}
+
+/// 混合搜索中两路结果的融合方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ScoreFusion {
+    /// 倒数排名融合：只看名次，不受两路分数尺度不同的影响
+    #[serde(rename = "rrf")]
+    #[default]
+    ReciprocalRank,
+    /// 将两路分数分别归一化到 [0, 1] 后加权求和
+    #[serde(rename = "weighted_sum")]
+    WeightedSum,
 }
 
 impl SearchModelConfig {
@@ -49,9 +287,27 @@ impl SearchModelConfig {
     pub fn is_traditional_search(&self) -> bool {
         matches!(
             self,
-            SearchModelConfig::Skim | SearchModelConfig::Standard | SearchModelConfig::Launchy
+            SearchModelConfig::Skim
+                | SearchModelConfig::Standard
+                | SearchModelConfig::Launchy
+                | SearchModelConfig::Typo
         )
     }
+
+    /// 语义后端不可用时退回的传统搜索模型
+    ///
+    /// 混合搜索退回到它自己的字面匹配模型，其余的退回到默认模型
+    pub fn traditional_fallback(&self) -> SearchModelConfig {
+        match self {
+            config if config.is_traditional_search() => config.clone(),
+            SearchModelConfig::Hybrid { lexical_model, .. }
+                if lexical_model.is_traditional_search() =>
+            {
+                lexical_model.as_ref().clone()
+            }
+            _ => SearchModelConfig::default(),
+        }
+    }
 }
 
 /// 搜索模型工厂
@@ -63,6 +319,7 @@ impl SearchModelFactory {
             SearchModelConfig::Skim => Arc::new(SkimScorer::new()),
             SearchModelConfig::Standard => Arc::new(StandardScorer::new()),
             SearchModelConfig::Launchy => Arc::new(LaunchyScorer::new()),
+            SearchModelConfig::Typo => Arc::new(TypoScorer::new()),
             // SearchModelConfig::Semantic => {
             //     let semantic_manager = semantic_manager
             //         .expect_programming("semantic搜索模型需要语义管理器");
@@ -98,6 +355,30 @@ impl Scorer for SearchModel {
     fn calculate_score(&self, program: &Arc<Program>, user_input: &str) -> f64 {
         self.scorer.calculate_score(program, user_input)
     }
+
+    fn match_keyword(&self, program: &Arc<Program>, user_input: &str) -> Option<KeywordMatch> {
+        self.scorer.match_keyword(program, user_input)
+    }
+
+    fn keyword_scores(&self, program: &Arc<Program>, user_input: &str) -> Vec<Option<f64>> {
+        self.scorer.keyword_scores(program, user_input)
+    }
+
+    fn build_index(&self, programs: &[Arc<Program>]) {
+        self.scorer.build_index(programs)
+    }
+
+    fn prefilter(&self, user_input: &str) -> Option<HashSet<u64>> {
+        self.scorer.prefilter(user_input)
+    }
+
+    fn no_match_score(&self) -> Option<f64> {
+        self.scorer.no_match_score()
+    }
+
+    fn is_match(&self, score: f64) -> bool {
+        self.scorer.is_match(score)
+    }
 }
 
 impl std::fmt::Debug for SearchModel {
@@ -223,3 +504,25 @@ pub fn get_first_letters(s: &str) -> String {
         .filter_map(|word| word.chars().next())
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_match_indices() {
+        // 连续子串优先
+        assert_eq!(
+            find_match_indices("visual studio code", "stu"),
+            vec![7, 8, 9]
+        );
+        // 否则按顺序匹配子序列
+        assert_eq!(
+            find_match_indices("visual studio code", "vsc"),
+            vec![0, 2, 14]
+        );
+        // 非 ascii 字符按 char 计算下标
+        assert_eq!(find_match_indices("微信 wechat", "we"), vec![3, 4]);
+        assert!(find_match_indices("code", "").is_empty());
+    }
+}
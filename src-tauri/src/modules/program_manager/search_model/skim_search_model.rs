@@ -1,14 +1,18 @@
-use crate::program_manager::search_model::Scorer;
+use crate::program_manager::search_model::{KeywordIndex, KeywordMatch, Scorer};
 use crate::program_manager::Program;
 use crate::Arc;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt::Debug;
 #[derive(Serialize, Deserialize, Default)]
 pub struct SkimScorer {
     #[serde(skip)]
     matcher: SkimMatcherV2,
+    #[serde(skip)]
+    index: RwLock<KeywordIndex>,
 }
 
 impl Scorer for SkimScorer {
@@ -25,6 +29,60 @@ impl Scorer for SkimScorer {
         }
         ret
     }
+
+    fn match_keyword(&self, program: &Arc<Program>, user_input: &str) -> Option<KeywordMatch> {
+        let mut best: Option<(i64, KeywordMatch)> = None;
+        for (keyword_index, name) in program.search_keywords.iter().enumerate() {
+            if name.chars().count() < user_input.chars().count() {
+                continue;
+            }
+            // skim 自身就能给出命中的字符位置
+            if let Some((score, indices)) = self.matcher.fuzzy_indices(name, user_input) {
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_score, _)| score > *best_score)
+                {
+                    best = Some((
+                        score,
+                        KeywordMatch {
+                            keyword_index,
+                            indices,
+                        },
+                    ));
+                }
+            }
+        }
+        best.map(|(_, keyword_match)| keyword_match)
+    }
+
+    fn keyword_scores(&self, program: &Arc<Program>, user_input: &str) -> Vec<Option<f64>> {
+        program
+            .search_keywords
+            .iter()
+            .map(|name| {
+                if name.chars().count() < user_input.chars().count() {
+                    return None;
+                }
+                self.matcher
+                    .fuzzy_match(name, user_input)
+                    .map(|score| score as f64)
+            })
+            .collect()
+    }
+
+    fn build_index(&self, programs: &[Arc<Program>]) {
+        *self.index.write() = KeywordIndex::build(programs);
+    }
+
+    fn prefilter(&self, user_input: &str) -> Option<HashSet<u64>> {
+        // 子序列匹配：输入中的每个字符都要出现在关键字里
+        self.index.read().containing_all_chars(user_input)
+    }
+
+    fn no_match_score(&self) -> Option<f64> {
+        // 子序列匹配：输入变长后不会由不匹配变为匹配
+        Some(-10000.0)
+    }
 }
 
 impl Debug for SkimScorer {
@@ -37,6 +95,7 @@ impl SkimScorer {
     pub fn new() -> Self {
         SkimScorer {
             matcher: SkimMatcherV2::default(),
+            index: RwLock::new(KeywordIndex::default()),
         }
     }
 }
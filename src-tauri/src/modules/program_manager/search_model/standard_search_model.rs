@@ -1,11 +1,17 @@
-use crate::program_manager::search_model::Scorer;
+use crate::program_manager::search_model::{
+    best_keyword_match, KeywordIndex, KeywordMatch, Scorer,
+};
 use crate::program_manager::Program;
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::Arc;
 #[derive(Serialize, Deserialize)]
-pub struct StandardScorer;
+pub struct StandardScorer {
+    #[serde(skip)]
+    index: RwLock<KeywordIndex>,
+}
 
 impl Scorer for StandardScorer {
     fn calculate_score(&self, program: &Arc<Program>, user_input: &str) -> f64 {
@@ -13,19 +19,40 @@ impl Scorer for StandardScorer {
         // program中的字符串与user_input都已经是预处理过了，不再需要预处理了
         let mut ret: f64 = -10000.0;
         for names in &program.search_keywords {
-            if names.chars().count() < user_input.chars().count() {
-                continue;
+            if let Some(score) = self.keyword_score(names, user_input) {
+                ret = f64::max(ret, score);
             }
-            let mut score: f64 = shortest_edit_dis(names, user_input);
-            score *= adjust_score_log2(
-                (user_input.chars().count() as f64) / (names.chars().count() as f64),
-            );
-            score += subset_dis(names, user_input);
-            score += kmp(names, user_input);
-            ret = f64::max(ret, score);
         }
         ret
     }
+
+    fn match_keyword(&self, program: &Arc<Program>, user_input: &str) -> Option<KeywordMatch> {
+        best_keyword_match(program, user_input, |keyword| {
+            self.keyword_score(keyword, user_input)
+        })
+    }
+
+    fn keyword_scores(&self, program: &Arc<Program>, user_input: &str) -> Vec<Option<f64>> {
+        program
+            .search_keywords
+            .iter()
+            .map(|keyword| self.keyword_score(keyword, user_input))
+            .collect()
+    }
+
+    fn build_index(&self, programs: &[Arc<Program>]) {
+        *self.index.write() = KeywordIndex::build(programs);
+    }
+
+    fn prefilter(&self, user_input: &str) -> Option<HashSet<u64>> {
+        // 编辑距离允许任意字符不同，只能按关键字长度筛选
+        self.index.read().with_long_enough_keyword(user_input)
+    }
+
+    fn no_match_score(&self) -> Option<f64> {
+        // 只有关键字都比输入短时才不匹配，输入变长后依然更短
+        Some(-10000.0)
+    }
 }
 
 impl Debug for StandardScorer {
@@ -42,7 +69,22 @@ impl Default for StandardScorer {
 
 impl StandardScorer {
     pub fn new() -> Self {
-        StandardScorer
+        StandardScorer {
+            index: RwLock::new(KeywordIndex::default()),
+        }
+    }
+
+    /// 计算单个关键字的得分，关键字比用户输入短时不参与匹配
+    fn keyword_score(&self, names: &str, user_input: &str) -> Option<f64> {
+        if names.chars().count() < user_input.chars().count() {
+            return None;
+        }
+        let mut score: f64 = shortest_edit_dis(names, user_input);
+        score *=
+            adjust_score_log2((user_input.chars().count() as f64) / (names.chars().count() as f64));
+        score += subset_dis(names, user_input);
+        score += kmp(names, user_input);
+        Some(score)
     }
 }
 
@@ -0,0 +1,332 @@
+use crate::program_manager::search_model::{best_keyword_match, KeywordMatch, Scorer};
+use crate::program_manager::Program;
+/// 这个文件实现了一个可以容忍输入错误的搜索模型
+///
+/// 加载程序时，会为所有关键字建立字符与三元组(trigram)的倒排索引。搜索时先通过索引筛选出
+/// 可能匹配的程序，再使用有上限的 Damerau–Levenshtein 距离（支持相邻字符交换，如 "chorme"）评分
+use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// 字符与三元组的倒排索引
+#[derive(Debug, Default)]
+struct GramIndex {
+    /// 字符 => 含有这个字符的程序(去重)
+    char_postings: HashMap<char, Vec<u64>>,
+    /// trigram => 含有这个 trigram 的程序(去重)
+    trigram_postings: HashMap<[char; 3], Vec<u64>>,
+}
+
+impl GramIndex {
+    fn build(programs: &[Arc<Program>]) -> Self {
+        let mut index = GramIndex::default();
+        for program in programs {
+            let mut seen_chars: HashSet<char> = HashSet::new();
+            let mut seen_trigrams: HashSet<[char; 3]> = HashSet::new();
+            for keyword in &program.search_keywords {
+                seen_chars.extend(keyword.chars());
+                seen_trigrams.extend(trigrams(keyword));
+            }
+            for c in seen_chars {
+                index
+                    .char_postings
+                    .entry(c)
+                    .or_default()
+                    .push(program.program_guid);
+            }
+            for trigram in seen_trigrams {
+                index
+                    .trigram_postings
+                    .entry(trigram)
+                    .or_default()
+                    .push(program.program_guid);
+            }
+        }
+        index
+    }
+}
+
+/// 统计每个程序含有多少个给定的 gram
+fn count_hits<K: Hash + Eq>(
+    postings: &HashMap<K, Vec<u64>>,
+    grams: &HashSet<K>,
+) -> HashMap<u64, usize> {
+    let mut hit_count: HashMap<u64, usize> = HashMap::new();
+    for gram in grams {
+        if let Some(guids) = postings.get(gram) {
+            for guid in guids {
+                *hit_count.entry(*guid).or_insert(0) += 1;
+            }
+        }
+    }
+    hit_count
+}
+
+/// 获得一个字符串中所有的 trigram（不做边界填充，因为用户输入通常只是名字的一部分）
+fn trigrams(text: &str) -> Vec<[char; 3]> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+/// 没有任何关键字可以在允许的错误数内匹配时的分数
+const NO_MATCH_SCORE: f64 = -10000.0;
+
+/// 根据输入长度决定允许的最大错误数
+fn max_typos(input_len: usize) -> usize {
+    match input_len {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+pub struct TypoScorer {
+    index: RwLock<GramIndex>,
+}
+
+impl Debug for TypoScorer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypoScorer")
+            .field("trigram_count", &self.index.read().trigram_postings.len())
+            .finish()
+    }
+}
+
+impl Default for TypoScorer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypoScorer {
+    pub fn new() -> Self {
+        TypoScorer {
+            index: RwLock::new(GramIndex::default()),
+        }
+    }
+
+    /// 计算单个关键字的得分，错误数超过上限时返回 `None`
+    fn keyword_score(&self, keyword: &str, user_input: &str) -> Option<f64> {
+        let pattern: Vec<char> = user_input.chars().collect();
+        let text: Vec<char> = keyword.chars().collect();
+        let n = pattern.len();
+        if n == 0 {
+            return Some(0.0);
+        }
+        let distance = bounded_damerau_levenshtein(&pattern, &text, max_typos(n))?;
+
+        let mut score = n as f64 - 1.5 * distance.infix as f64;
+        // 从开头匹配的更符合用户的习惯
+        if distance.prefix == Some(distance.infix) {
+            score += 2.0;
+        }
+        if distance.infix == 0 && n == text.len() {
+            score += 3.0;
+        }
+        // 关键字被覆盖得越多，得分越高
+        score += 3.0 * (n as f64 / text.len().max(1) as f64).min(1.0);
+        Some(score)
+    }
+}
+
+impl Scorer for TypoScorer {
+    fn calculate_score(&self, program: &Arc<Program>, user_input: &str) -> f64 {
+        let mut ret: f64 = NO_MATCH_SCORE;
+        for keyword in &program.search_keywords {
+            if let Some(score) = self.keyword_score(keyword, user_input) {
+                ret = f64::max(ret, score);
+            }
+        }
+        ret
+    }
+
+    fn match_keyword(&self, program: &Arc<Program>, user_input: &str) -> Option<KeywordMatch> {
+        best_keyword_match(program, user_input, |keyword| {
+            self.keyword_score(keyword, user_input)
+        })
+    }
+
+    fn keyword_scores(&self, program: &Arc<Program>, user_input: &str) -> Vec<Option<f64>> {
+        program
+            .search_keywords
+            .iter()
+            .map(|keyword| self.keyword_score(keyword, user_input))
+            .collect()
+    }
+
+    fn is_match(&self, score: f64) -> bool {
+        // 输入变长后错误数上限也会变大，所以不提供 no_match_score，但依然可以判断是否匹配
+        score > NO_MATCH_SCORE
+    }
+
+    fn build_index(&self, programs: &[Arc<Program>]) {
+        *self.index.write() = GramIndex::build(programs);
+    }
+
+    fn prefilter(&self, user_input: &str) -> Option<HashSet<u64>> {
+        let max = max_typos(user_input.chars().count());
+        // 替换或删除一个字符最多让一种字符消失（插入与相邻交换不会），所以至少有 (字符种类数 - k) 种字符相同
+        let input_chars: HashSet<char> = user_input.chars().collect();
+        let required_chars = input_chars
+            .len()
+            .checked_sub(max)
+            .filter(|required| *required > 0)?;
+        // q-gram 引理：替换、插入或删除一个字符最多破坏 3 个 trigram，相邻交换最多破坏 4 个，
+        // 所以至少有 (trigram种类数 - 4k) 种 trigram 相同。输入较短时这个下界不是正数，只按字符筛选
+        let input_trigrams: HashSet<[char; 3]> = trigrams(user_input).into_iter().collect();
+        let required_trigrams = input_trigrams.len().saturating_sub(4 * max);
+
+        let index = self.index.read();
+        let trigram_hits = if required_trigrams > 0 {
+            count_hits(&index.trigram_postings, &input_trigrams)
+        } else {
+            HashMap::new()
+        };
+        Some(
+            count_hits(&index.char_postings, &input_chars)
+                .into_iter()
+                .filter(|(guid, count)| {
+                    *count >= required_chars
+                        && (required_trigrams == 0
+                            || trigram_hits
+                                .get(guid)
+                                .is_some_and(|count| *count >= required_trigrams))
+                })
+                .map(|(guid, _)| guid)
+                .collect(),
+        )
+    }
+}
+
+/// 用户输入与一个关键字之间的距离
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypoDistance {
+    /// 与关键字中任意一段之间的最小距离
+    pub infix: usize,
+    /// 与关键字的某个前缀之间的最小距离，超过上限时为 `None`
+    pub prefix: Option<usize>,
+}
+
+/// 有上限的 Damerau–Levenshtein 距离（optimal string alignment 版本）
+///
+/// 一次遍历同时计算 `pattern` 与 `text` 的任意子串、以及与 `text` 的某个前缀之间的最小距离，
+/// 任意子串的距离超过 `max` 时提前返回 `None`
+pub fn bounded_damerau_levenshtein(
+    pattern: &[char],
+    text: &[char],
+    max: usize,
+) -> Option<TypoDistance> {
+    let n = pattern.len();
+    let m = text.len();
+    // 两组三行滚动数组 dp[i-2], dp[i-1], dp[i]，每组中 [0] 可以从任意位置开始，[1] 必须从开头开始
+    let mut prev2: [Vec<usize>; 2] = [vec![0; m + 1], vec![0; m + 1]];
+    let mut prev: [Vec<usize>; 2] = [vec![0; m + 1], (0..=m).collect()];
+    let mut current: [Vec<usize>; 2] = [vec![0; m + 1], vec![0; m + 1]];
+
+    for i in 1..=n {
+        current[0][0] = i;
+        current[1][0] = i;
+        let mut row_min = i;
+        for j in 1..=m {
+            let cost = usize::from(pattern[i - 1] != text[j - 1]);
+            let transposed =
+                i > 1 && j > 1 && pattern[i - 1] == text[j - 2] && pattern[i - 2] == text[j - 1];
+            for k in 0..2 {
+                let mut value = (prev[k][j] + 1)
+                    .min(current[k][j - 1] + 1)
+                    .min(prev[k][j - 1] + cost);
+                if transposed {
+                    value = value.min(prev2[k][j - 2] + 1);
+                }
+                current[k][j] = value;
+            }
+            row_min = row_min.min(current[0][j]);
+        }
+        // 从开头开始的距离不会小于从任意位置开始的距离，所以只需要检查后者
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut current);
+    }
+
+    // 匹配可以在 text 的任意位置结束
+    let infix = prev[0].iter().copied().min().unwrap_or(n);
+    if infix > max {
+        return None;
+    }
+    let prefix = prev[1]
+        .iter()
+        .copied()
+        .min()
+        .filter(|distance| *distance <= max);
+    Some(TypoDistance { infix, prefix })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program_manager::test_support::test_program;
+
+    fn dis(pattern: &str, text: &str, free_start: bool) -> Option<usize> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        let distance = bounded_damerau_levenshtein(&pattern, &text, 2)?;
+        if free_start {
+            Some(distance.infix)
+        } else {
+            distance.prefix
+        }
+    }
+
+    #[test]
+    fn test_bounded_damerau_levenshtein() {
+        assert_eq!(dis("chrome", "chrome", false), Some(0));
+        // 相邻字符交换只算一次错误
+        assert_eq!(dis("chorme", "chrome", false), Some(1));
+        // 前缀匹配
+        assert_eq!(dis("vis", "visual studio code", false), Some(0));
+        assert_eq!(dis("studoi", "visual studio code", false), None);
+        assert_eq!(dis("studoi", "visual studio code", true), Some(1));
+        assert_eq!(dis("xyzxyz", "chrome", true), None);
+    }
+
+    #[test]
+    fn test_prefilter_and_score() {
+        let programs = vec![
+            Arc::new(test_program(1, "google chrome", &["google chrome"])),
+            Arc::new(test_program(2, "notepad", &["notepad"])),
+        ];
+        let scorer = TypoScorer::new();
+        scorer.build_index(&programs);
+
+        assert!(scorer.prefilter("").is_none());
+        // 短输入只按字符筛选
+        assert_eq!(scorer.prefilter("ch").unwrap(), HashSet::from([1]));
+        assert_eq!(scorer.prefilter("chorme").unwrap(), HashSet::from([1]));
+        let candidates = scorer.prefilter("google chorme").unwrap();
+        assert!(candidates.contains(&1));
+        assert!(!candidates.contains(&2));
+
+        assert!(scorer.calculate_score(&programs[0], "chorme") > 0.0);
+        assert_eq!(scorer.calculate_score(&programs[1], "chorme"), -10000.0);
+    }
+
+    #[test]
+    fn test_prefilter_keeps_transpositions() {
+        let programs = vec![Arc::new(test_program(
+            1,
+            "abcdefghijklmn",
+            &["abcdefghijklmn"],
+        ))];
+        let scorer = TypoScorer::new();
+        scorer.build_index(&programs);
+
+        // 两次相隔较远的相邻交换一共破坏了 8 个 trigram，只剩下 4 个相同
+        let input = "abcedfghijlkmn";
+        assert!(scorer.calculate_score(&programs[0], input) > NO_MATCH_SCORE);
+        assert_eq!(scorer.prefilter(input).unwrap(), HashSet::from([1]));
+    }
+}
@@ -0,0 +1,18 @@
+//! 单元测试共用的辅助函数
+use crate::core::image_processor::ImageIdentity;
+use crate::program_manager::{LaunchMethod, Program};
+
+/// 创建一个测试用的程序，启动方式为 `LaunchMethod::Path(show_name)`
+///
+/// 需要其他启动方式或字段时，使用结构体更新语法覆盖：`Program { launch_method, ..test_program(..) }`
+pub(crate) fn test_program(program_guid: u64, show_name: &str, keywords: &[&str]) -> Program {
+    Program {
+        program_guid,
+        show_name: show_name.to_string(),
+        launch_method: LaunchMethod::Path(show_name.to_string()),
+        search_keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(),
+        stable_bias: 0.0,
+        icon_path: ImageIdentity::File(String::new()),
+        embedding: Vec::new(),
+    }
+}
@@ -1,5 +1,6 @@
 // 存放辅助型的小类型
 use crate::core::image_processor::ImageIdentity;
+use crate::program_manager::program_ranker::RankerScoreComponents;
 use crate::program_manager::PartialProgramManagerConfig;
 use bincode::{Decode, Encode};
 pub type EmbeddingVec = Vec<f32>;
@@ -111,7 +112,7 @@ fn fill_template(template: &str, args: &[String]) -> Result<String, String> {
 }
 
 /// 表示一个数据
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Program {
     /// 全局唯一标识符，用于快速索引，用于内存中存储
     pub program_guid: u64,
@@ -129,6 +130,69 @@ pub struct Program {
     pub embedding: EmbeddingVec,
 }
 
+impl Program {
+    /// 判断两个程序除 guid 以外的内容是否一致（用于增量更新时判断程序是否发生变化）
+    pub fn has_same_content(&self, other: &Program) -> bool {
+        self.show_name == other.show_name
+            && self.launch_method == other.launch_method
+            && self.search_keywords == other.search_keywords
+            && self.stable_bias == other.stable_bias
+            && self.icon_path == other.icon_path
+            && self.embedding == other.embedding
+    }
+}
+
+/// 被用户隐藏的程序
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HiddenProgramInfo {
+    /// launch_method.get_text()
+    pub launch_method: String,
+    pub show_name: String,
+}
+
+/// 一次程序注册表增量更新的变化摘要
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramRegistryDiff {
+    /// 新增的程序数量
+    pub added: usize,
+    /// 被移除的程序数量
+    pub removed: usize,
+    /// 启动方式不变但内容发生变化的程序数量
+    pub changed: usize,
+    /// 完全没有变化的程序数量
+    pub unchanged: usize,
+    /// 这次没有扫描到，但所在的根目录没有扫描完而保留下来的程序数量
+    pub kept: usize,
+}
+
+/// 搜索结果的高亮信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MatchHighlight {
+    /// 得分最高的关键字
+    pub keyword: String,
+    /// `show_name` 中被命中的字符区间 [start, end)，以字符(char)为单位
+    ///
+    /// 命中的是用户自定义的别名时无法映射到 `show_name` 上，此时为空
+    pub ranges: Vec<(usize, usize)>,
+    /// `keyword` 中被命中的字符区间 [start, end)，以字符(char)为单位
+    pub keyword_ranges: Vec<(usize, usize)>,
+}
+
+/// 将被命中的字符下标合并为连续的区间 [start, end)
+pub fn indices_to_ranges(mut indices: Vec<usize>) -> Vec<(usize, usize)> {
+    indices.sort_unstable();
+    indices.dedup();
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for index in indices {
+        match ranges.last_mut() {
+            Some((_, end)) if *end == index => *end = index + 1,
+            _ => ranges.push((index, index + 1)),
+        }
+    }
+    ranges
+}
+
 /// 表示搜索测试的结果项
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchTestResult {
@@ -140,6 +204,59 @@ pub struct SearchTestResult {
     pub program_path: String,
     /// 匹配的权重值
     pub score: f64,
+    /// 权重值的组成
+    pub breakdown: ScoreBreakdown,
+}
+
+/// 单个关键字的得分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordScore {
+    pub keyword: String,
+    /// 不匹配时为 None
+    pub score: Option<f64>,
+}
+
+/// 一个搜索结果的得分是如何组成的
+///
+/// `score` = `normalized_score` + `stable_bias` + 智能排序的各项加分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    /// 每个关键字单独的得分（语义搜索没有）
+    pub keyword_scores: Vec<KeywordScore>,
+    /// 得分最高的关键字
+    pub winning_keyword: Option<String>,
+    /// 程序的固定偏移量
+    pub stable_bias: f64,
+    /// 搜索模型给出的基础分数（不含固定偏移量）
+    pub base_score: f64,
+    /// 归一化后的基础分数
+    pub normalized_score: f64,
+    /// 智能排序的各项加分（已乘以权重）
+    pub ranker: RankerScoreComponents,
+}
+
+/// 表示一次搜索测试的完整结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchTestReport {
+    /// 去除过滤条件后交给评分器的文本
+    pub query_text: String,
+    /// 从查询中解析出来的过滤条件
+    pub filters: Vec<String>,
+    /// 按得分排序的结果
+    pub results: Vec<SearchTestResult>,
+}
+
+/// 表示搜索性能基准测试中的一次查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchBenchmarkItem {
+    /// 查询的内容
+    pub query: String,
+    /// 查询耗时(ms)
+    pub time_ms: f64,
+    /// 经过索引筛选后需要评分的程序数量
+    pub candidate_count: usize,
+    /// 程序总数
+    pub program_count: usize,
 }
 
 /// 表示语义信息的存储项
@@ -0,0 +1,554 @@
+/// 这个文件用于从其他启动器导入使用记录，让刚开始使用时的智能排序不用从零开始
+///
+/// 支持的数据：
+/// - Launchy：`launchy.db`，是用 qCompress 压缩过的 QDataStream，每个条目记录了完整路径、名字与使用次数
+/// - Wox / Flow Launcher：`UserSelectedRecord.json`，`records` 中记录了每个结果（标题与副标题拼接）被选中的次数
+/// - PowerToys Run：`UserSelectedRecord.json`（每个结果被选中的次数）或 `QueryHistory.json`（查询 => 选中的结果）
+///
+/// 读出来的记录先按路径、再按名字对应到当前注册表中的程序，对应不上的记录会列在报告中。
+/// 按名字对应时要求名字是一个完整的词（"Word" 不会对应到 "WordPad"），或者与文件名完全相同
+use crate::error::{AppError, AppResult};
+use crate::program_manager::program_ranker::QueryLaunchRecord;
+use crate::program_manager::{LaunchMethod, Program};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use flate2::read::ZlibDecoder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 报告中最多列出这么多条没有对应上的记录
+const MAX_REPORTED_UNMATCHED: usize = 50;
+
+/// 按名字对应时，名字至少要有这么多个字符，太短的名字容易对应错
+const MIN_NAME_MATCH_CHARS: usize = 2;
+
+/// QDataStream 中表示空字符串的长度
+const QT_NULL_STRING: u32 = u32::MAX;
+
+/// 使用记录的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UsageImportSource {
+    #[serde(rename = "launchy")]
+    Launchy,
+    /// Wox 与 Flow Launcher 的格式相同
+    #[serde(rename = "flow_launcher")]
+    FlowLauncher,
+    #[serde(rename = "powertoys_run")]
+    PowerToysRun,
+}
+
+impl UsageImportSource {
+    /// 保存导入情况时使用的名字
+    pub fn id(&self) -> &'static str {
+        match self {
+            UsageImportSource::Launchy => "launchy",
+            UsageImportSource::FlowLauncher => "flow_launcher",
+            UsageImportSource::PowerToysRun => "powertoys_run",
+        }
+    }
+}
+
+/// 从其他启动器读出来的一条使用记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedUsage {
+    /// 用来对应程序的文本：完整路径，或者结果的标题（可能带着副标题）
+    pub target: String,
+    pub launch_count: u64,
+    /// 选中这个结果时输入的查询
+    pub query: Option<String>,
+    /// 来源中记录的最近一次使用的时间（时间戳），没有记录时为 `None`
+    pub last_launch_time: Option<i64>,
+}
+
+/// 导入的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageImportReport {
+    /// 文件中的记录数量
+    pub record_count: usize,
+    /// 对应上了程序的记录数量
+    pub matched_count: usize,
+    /// 实际计入的启动次数（每条记录有上限，之前从同一个来源导入过的不再计入）
+    pub imported_launches: u64,
+    /// 导入的查询-程序关联数量
+    pub imported_queries: usize,
+    /// 没有对应上的记录（最多 `MAX_REPORTED_UNMATCHED` 条）
+    pub unmatched: Vec<String>,
+}
+
+/// 按来源读取并解析一个文件
+///
+/// 来源中没有记录使用时间的记录，使用文件的修改时间（启动器最后一次写入使用记录的时间）
+pub fn read_usage_file(source: UsageImportSource, path: &Path) -> AppResult<Vec<ImportedUsage>> {
+    let mut usages = match source {
+        UsageImportSource::Launchy => parse_launchy_catalog(&std::fs::read(path)?)?,
+        UsageImportSource::FlowLauncher => {
+            parse_flow_launcher_records(&std::fs::read_to_string(path)?)?
+        }
+        UsageImportSource::PowerToysRun => parse_powertoys_run(&std::fs::read_to_string(path)?)?,
+    };
+    let modified_time = std::fs::metadata(path)?
+        .modified()
+        .ok()
+        .map(|time| DateTime::<Local>::from(time).timestamp());
+    for usage in &mut usages {
+        usage.last_launch_time = usage.last_launch_time.or(modified_time);
+    }
+    Ok(usages)
+}
+
+fn import_error(message: impl Into<String>) -> AppError {
+    AppError::ImportError {
+        message: message.into(),
+    }
+}
+
+/// 按大端序读取 QDataStream 中的数据
+struct QDataStreamReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> QDataStreamReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        QDataStreamReader { data, position: 0 }
+    }
+
+    fn is_end(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let end = self.position.checked_add(length)?;
+        let bytes = self.data.get(self.position..end)?;
+        self.position = end;
+        Some(bytes)
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        let bytes = self.read_bytes(4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_i32(&mut self) -> Option<i32> {
+        self.read_u32().map(|value| value as i32)
+    }
+
+    /// QString: 字节长度 + UTF-16BE
+    fn read_string(&mut self) -> Option<String> {
+        let length = self.read_u32()?;
+        if length == QT_NULL_STRING {
+            return Some(String::new());
+        }
+        if length % 2 != 0 {
+            return None;
+        }
+        let units: Vec<u16> = self
+            .read_bytes(length as usize)?
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        Some(String::from_utf16_lossy(&units))
+    }
+}
+
+/// 解析 Launchy 的目录文件
+///
+/// 文件内容为 qCompress 的结果（4 字节大端序的原始长度 + zlib 数据），
+/// 解压之后依次是每个条目的：完整路径、名字、小写名字、图标（QString），使用次数、插件 id（qint32）
+pub fn parse_launchy_catalog(bytes: &[u8]) -> AppResult<Vec<ImportedUsage>> {
+    if bytes.len() < 4 {
+        return Err(import_error("Launchy 的目录文件太短"));
+    }
+    let mut data = Vec::new();
+    ZlibDecoder::new(&bytes[4..])
+        .read_to_end(&mut data)
+        .map_err(|e| import_error(format!("无法解压 Launchy 的目录文件: {}", e)))?;
+
+    let mut reader = QDataStreamReader::new(&data);
+    let mut result = Vec::new();
+    while !reader.is_end() {
+        let item = (|| {
+            let full_path = reader.read_string()?;
+            let _short_name = reader.read_string()?;
+            let _low_name = reader.read_string()?;
+            let _icon = reader.read_string()?;
+            let usage = reader.read_i32()?;
+            let _plugin_id = reader.read_i32()?;
+            Some((full_path, usage))
+        })();
+        let Some((full_path, usage)) = item else {
+            return Err(import_error("Launchy 的目录文件格式不正确"));
+        };
+        if usage > 0 && !full_path.is_empty() {
+            result.push(ImportedUsage {
+                target: full_path,
+                launch_count: usage as u64,
+                query: None,
+                last_launch_time: None,
+            });
+        }
+    }
+    Ok(result)
+}
+
+/// Wox / Flow Launcher 的 `UserSelectedRecord.json`
+#[derive(Debug, Deserialize)]
+struct FlowLauncherRecords {
+    #[serde(default, alias = "Records")]
+    records: HashMap<String, i64>,
+}
+
+/// 解析 Wox / Flow Launcher 的 `UserSelectedRecord.json`
+pub fn parse_flow_launcher_records(text: &str) -> AppResult<Vec<ImportedUsage>> {
+    let records: FlowLauncherRecords = serde_json::from_str(text)?;
+    let mut result: Vec<ImportedUsage> = records
+        .records
+        .into_iter()
+        .filter(|(target, count)| *count > 0 && !target.is_empty())
+        .map(|(target, count)| ImportedUsage {
+            target,
+            launch_count: count as u64,
+            query: None,
+            last_launch_time: None,
+        })
+        .collect();
+    result.sort_by(|a, b| a.target.cmp(&b.target));
+    Ok(result)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PowerToysSelectedItem {
+    #[serde(default)]
+    selected_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PowerToysHistoryItem {
+    #[serde(default)]
+    query: String,
+    #[serde(default)]
+    selected: String,
+    #[serde(default)]
+    executed_date_time: String,
+}
+
+/// PowerToys Run 的 `UserSelectedRecord.json` 与 `QueryHistory.json` 共用这个结构，各自只有其中一个字段
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PowerToysRunData {
+    #[serde(default)]
+    selected_items: HashMap<String, PowerToysSelectedItem>,
+    #[serde(default)]
+    items: Vec<PowerToysHistoryItem>,
+}
+
+/// 解析 PowerToys Run 的 `UserSelectedRecord.json` 或 `QueryHistory.json`
+pub fn parse_powertoys_run(text: &str) -> AppResult<Vec<ImportedUsage>> {
+    let data: PowerToysRunData = serde_json::from_str(text)?;
+    let mut result: Vec<ImportedUsage> = data
+        .selected_items
+        .into_iter()
+        .filter(|(target, item)| item.selected_count > 0 && !target.is_empty())
+        .map(|(target, item)| ImportedUsage {
+            target,
+            launch_count: item.selected_count as u64,
+            query: None,
+            last_launch_time: None,
+        })
+        .collect();
+
+    // 查询历史中每一项是一次选择，相同的 (选中的结果, 查询) 合并在一起，保留最近一次选择的时间
+    let mut history: HashMap<(String, String), (u64, Option<i64>)> = HashMap::new();
+    for item in data.items {
+        let query = item.query.trim().to_string();
+        if item.selected.is_empty() || query.is_empty() {
+            continue;
+        }
+        let executed_time = parse_local_date_time(&item.executed_date_time);
+        let (count, last_time) = history.entry((item.selected, query)).or_insert((0, None));
+        *count += 1;
+        *last_time = (*last_time).max(executed_time);
+    }
+    result.extend(
+        history
+            .into_iter()
+            .map(|((target, query), (count, last_time))| ImportedUsage {
+                target,
+                launch_count: count,
+                query: Some(query),
+                last_launch_time: last_time,
+            }),
+    );
+    result.sort_by(|a, b| a.target.cmp(&b.target).then_with(|| a.query.cmp(&b.query)));
+    Ok(result)
+}
+
+/// 解析 .NET 序列化的时间，没有时区时按本地时间处理
+fn parse_local_date_time(text: &str) -> Option<i64> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.timestamp());
+    }
+    let time = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.timestamp())
+}
+
+/// 统一路径的写法：小写，使用 `/` 作为分隔符
+fn normalize_target(text: &str) -> String {
+    text.to_lowercase().replace('\\', "/")
+}
+
+/// 路径的文件名去掉扩展名
+fn path_stem(path: &str) -> &str {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    match file_name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => file_name,
+    }
+}
+
+/// `target` 是不是以 `name` 这个完整的词开头（后面没有内容，或者紧跟着的不是字母与数字）
+fn starts_with_word(target: &str, name: &str) -> bool {
+    target.strip_prefix(name).is_some_and(|rest| {
+        rest.chars()
+            .next()
+            .is_none_or(|next| !next.is_alphanumeric())
+    })
+}
+
+/// 把导入的记录对应到注册表中的程序
+pub struct UsageMatcher {
+    /// (程序guid, 规范化后的路径)，只包含通过路径启动的程序
+    paths: Vec<(u64, String)>,
+    /// (程序guid, 小写的名字)
+    names: Vec<(u64, String)>,
+    /// (程序guid, 小写的文件名，不含扩展名)，只包含通过路径启动的程序
+    stems: Vec<(u64, String)>,
+}
+
+impl UsageMatcher {
+    pub fn new(programs: &[Arc<Program>]) -> Self {
+        let mut paths = Vec::new();
+        let mut names = Vec::new();
+        let mut stems = Vec::new();
+        for program in programs {
+            if let LaunchMethod::Path(path) | LaunchMethod::File(path) = &program.launch_method {
+                if !path.is_empty() {
+                    let path = normalize_target(path);
+                    stems.push((program.program_guid, path_stem(&path).to_string()));
+                    paths.push((program.program_guid, path));
+                }
+            }
+            let name = program.show_name.to_lowercase();
+            if name.chars().count() >= MIN_NAME_MATCH_CHARS {
+                names.push((program.program_guid, name));
+            }
+        }
+        UsageMatcher {
+            paths,
+            names,
+            stems,
+        }
+    }
+
+    /// 依次按路径（记录中包含程序的完整路径）、名字（记录以程序的名字这个完整的词开头）、
+    /// 文件名（记录的文件名与程序的文件名完全相同）对应，都取最长的那一个
+    pub fn find(&self, target: &str) -> Option<u64> {
+        let target = normalize_target(target.trim());
+        let target_stem = path_stem(&target);
+        let longest = |candidates: &[(u64, String)], matches: &dyn Fn(&str) -> bool| {
+            candidates
+                .iter()
+                .filter(|(_, text)| matches(text))
+                .max_by_key(|(_, text)| text.len())
+                .map(|(guid, _)| *guid)
+        };
+        longest(&self.paths, &|path| target.contains(path))
+            .or_else(|| longest(&self.names, &|name| starts_with_word(&target, name)))
+            .or_else(|| longest(&self.stems, &|stem| stem == target_stem))
+    }
+
+    /// 对应所有的记录，返回可以交给排序器的查询-启动记录（没有查询时 `query` 为空）与导入报告
+    pub fn match_usages(
+        &self,
+        usages: &[ImportedUsage],
+    ) -> (Vec<QueryLaunchRecord>, UsageImportReport) {
+        let mut report = UsageImportReport {
+            record_count: usages.len(),
+            ..Default::default()
+        };
+        let mut records = Vec::new();
+        for usage in usages {
+            match self.find(&usage.target) {
+                Some(program_guid) => {
+                    report.matched_count += 1;
+                    records.push(QueryLaunchRecord {
+                        query: usage.query.clone().unwrap_or_default(),
+                        program_guid,
+                        launch_count: usage.launch_count,
+                        last_launch_time: usage.last_launch_time.unwrap_or(0),
+                    });
+                }
+                None => {
+                    if report.unmatched.len() < MAX_REPORTED_UNMATCHED {
+                        report.unmatched.push(usage.target.clone());
+                    }
+                }
+            }
+        }
+        (records, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program_manager::test_support::test_program;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    /// 按 Launchy 的格式生成目录文件
+    fn launchy_catalog(items: &[(&str, &str, i32)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let write_string = |data: &mut Vec<u8>, text: &str| {
+            let units: Vec<u16> = text.encode_utf16().collect();
+            data.extend(((units.len() * 2) as u32).to_be_bytes());
+            units
+                .iter()
+                .for_each(|unit| data.extend(unit.to_be_bytes()));
+        };
+        for (full_path, short_name, usage) in items {
+            write_string(&mut data, full_path);
+            write_string(&mut data, short_name);
+            write_string(&mut data, &short_name.to_lowercase());
+            data.extend(QT_NULL_STRING.to_be_bytes());
+            data.extend(usage.to_be_bytes());
+            data.extend(0i32.to_be_bytes());
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let mut result = (data.len() as u32).to_be_bytes().to_vec();
+        result.extend(encoder.finish().unwrap());
+        result
+    }
+
+    #[test]
+    fn test_parse_launcher_exports() {
+        let catalog = launchy_catalog(&[
+            ("C:/Program Files/Git/git-bash.exe", "git-bash", 7),
+            ("C:/Windows/notepad.exe", "notepad", 0),
+        ]);
+        let usages = parse_launchy_catalog(&catalog).unwrap();
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].target, "C:/Program Files/Git/git-bash.exe");
+        assert_eq!(usages[0].launch_count, 7);
+        assert!(parse_launchy_catalog(&catalog[..catalog.len() - 4]).is_err());
+
+        let usages = parse_flow_launcher_records(
+            r#"{"records": {"Visual Studio CodeC:\\Code\\Code.exe": 12, "Calculator": 0}}"#,
+        )
+        .unwrap();
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].launch_count, 12);
+
+        let usages = parse_powertoys_run(
+            r#"{"Items": [
+                {"Query": "code", "Selected": "Visual Studio Code", "ExecutedDateTime": "2024-01-01T00:00:00"},
+                {"Query": "code ", "Selected": "Visual Studio Code", "ExecutedDateTime": "2024-01-02T00:00:00"},
+                {"Query": "", "Selected": "Calculator", "ExecutedDateTime": "2024-01-02T00:00:00"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            usages,
+            vec![ImportedUsage {
+                target: "Visual Studio Code".to_string(),
+                launch_count: 2,
+                query: Some("code".to_string()),
+                last_launch_time: parse_local_date_time("2024-01-02T00:00:00"),
+            }]
+        );
+        assert!(usages[0].last_launch_time.is_some());
+        let usages =
+            parse_powertoys_run(r#"{"SelectedItems": {"Terminal": {"SelectedCount": 3}}}"#)
+                .unwrap();
+        assert_eq!(usages[0].launch_count, 3);
+    }
+
+    #[test]
+    fn test_match_usages() {
+        let programs = vec![
+            Arc::new(Program {
+                launch_method: LaunchMethod::Path("C:\\Code\\Code.exe".to_string()),
+                ..test_program(1, "Visual Studio Code", &[])
+            }),
+            Arc::new(Program {
+                launch_method: LaunchMethod::Path("C:\\VS\\devenv.exe".to_string()),
+                ..test_program(2, "Visual Studio", &[])
+            }),
+            Arc::new(Program {
+                launch_method: LaunchMethod::PackageFamilyName("Terminal_8wekyb".to_string()),
+                ..test_program(3, "Terminal", &[])
+            }),
+        ];
+        let matcher = UsageMatcher::new(&programs);
+        assert_eq!(matcher.find("c:/code/code.exe"), Some(1));
+        assert_eq!(
+            matcher.find("Visual Studio CodeC:\\Code\\Code.exe"),
+            Some(1)
+        );
+        // 按名字对应时取最长的名字
+        assert_eq!(matcher.find("Visual Studio Code"), Some(1));
+        assert_eq!(matcher.find("Visual Studio 2022"), Some(2));
+        assert_eq!(matcher.find("Terminal"), Some(3));
+        assert_eq!(matcher.find("Calculator"), None);
+        // 名字必须是一个完整的词
+        assert_eq!(matcher.find("Terminals"), None);
+        // 路径不同时按文件名对应
+        assert_eq!(matcher.find("D:\\Portable\\devenv.exe"), Some(2));
+
+        let word_programs = vec![Arc::new(Program {
+            launch_method: LaunchMethod::Path("C:\\Office\\WINWORD.EXE".to_string()),
+            ..test_program(4, "Word", &[])
+        })];
+        let word_matcher = UsageMatcher::new(&word_programs);
+        assert_eq!(word_matcher.find("WordPad"), None);
+        assert_eq!(word_matcher.find("Word 2016"), Some(4));
+        assert_eq!(word_matcher.find("winword"), Some(4));
+
+        let usages = vec![
+            ImportedUsage {
+                target: "Terminal".to_string(),
+                launch_count: 4,
+                query: Some("term".to_string()),
+                last_launch_time: Some(1_700_000_000),
+            },
+            ImportedUsage {
+                target: "Calculator".to_string(),
+                launch_count: 2,
+                query: None,
+                last_launch_time: None,
+            },
+        ];
+        let (records, report) = matcher.match_usages(&usages);
+        assert_eq!(report.record_count, 2);
+        assert_eq!(report.matched_count, 1);
+        assert_eq!(report.unmatched, vec!["Calculator"]);
+        assert_eq!(
+            records,
+            vec![QueryLaunchRecord {
+                query: "term".to_string(),
+                program_guid: 3,
+                launch_count: 4,
+                last_launch_time: 1_700_000_000,
+            }]
+        );
+    }
+}
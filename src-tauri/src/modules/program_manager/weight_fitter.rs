@@ -0,0 +1,172 @@
+/// 这个文件用于根据用户自己的启动历史拟合智能排序的权重
+///
+/// 每一条“查询 => 启动的程序”的记录都会被重放一次：用记录中的查询重新搜索，得到所有匹配的程序的归一化分数
+/// 与智能排序未加权的各项分数（这条记录的所有启动都不计入任何一项分数，避免“因为启动过所以排第一”的自我强化），
+/// 然后对各个权重做坐标轮换的网格搜索，使按启动次数加权的 MRR 最大
+use crate::program_manager::program_ranker::{RankerScoreComponents, RankerWeights};
+use serde::{Deserialize, Serialize};
+
+/// 少于这个数量的记录时不拟合，数据太少时拟合出来的权重没有意义
+pub const MIN_FITTING_SAMPLES: usize = 5;
+
+/// 每个权重可以取的值（与设置页面的范围一致）
+const WEIGHT_GRID: [f64; 13] = [
+    0.0, 0.25, 0.5, 0.8, 1.2, 1.6, 2.0, 2.5, 3.0, 3.5, 4.5, 6.0, 8.0,
+];
+
+/// 坐标轮换的最大轮数
+const MAX_ROUNDS: usize = 5;
+
+/// MRR 至少提高这么多才接受新的权重，避免在等价的权重之间来回跳
+const MIN_IMPROVEMENT: f64 = 1e-6;
+
+/// 一次被重放的查询
+#[derive(Debug, Clone)]
+pub struct FittingSample {
+    /// 这条记录的启动次数，作为计算 MRR 时的权重
+    pub weight: f64,
+    /// 匹配的程序：(归一化后的基础分数, 智能排序未加权的各项分数)
+    pub candidates: Vec<(f64, RankerScoreComponents)>,
+    /// 用户启动的程序在 candidates 中的下标
+    pub target: usize,
+}
+
+impl FittingSample {
+    /// 在给定的权重下，用户启动的程序的排名（从 1 开始，分数相同时算作排在后面）
+    fn rank(&self, weights: &RankerWeights) -> usize {
+        let score = |(base_score, components): &(f64, RankerScoreComponents)| {
+            base_score + components.weighted(weights).total()
+        };
+        let target_score = score(&self.candidates[self.target]);
+        1 + self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter(|(index, candidate)| *index != self.target && score(candidate) >= target_score)
+            .count()
+    }
+}
+
+/// 权重拟合的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightFittingReport {
+    /// 参与重放的记录数量
+    pub sample_count: usize,
+    /// 拟合前的权重
+    pub before: RankerWeights,
+    /// 拟合后的权重（没有应用时与拟合前相同）
+    pub after: RankerWeights,
+    pub mrr_before: f64,
+    pub mrr_after: f64,
+    /// 是否找到了更好的权重（样本太少或没有提升时为 false）
+    pub applied: bool,
+}
+
+/// 按启动次数加权的平均倒数排名
+pub fn replay_mrr(samples: &[FittingSample], weights: &RankerWeights) -> f64 {
+    let total_weight: f64 = samples.iter().map(|sample| sample.weight).sum();
+    if total_weight <= 0.0 {
+        return 0.0;
+    }
+    samples
+        .iter()
+        .map(|sample| sample.weight / sample.rank(weights) as f64)
+        .sum::<f64>()
+        / total_weight
+}
+
+/// 从当前的权重出发，依次调整每一个权重，直到 MRR 不再提高
+pub fn fit_weights(samples: &[FittingSample], initial: RankerWeights) -> WeightFittingReport {
+    let mrr_before = replay_mrr(samples, &initial);
+    let mut best = initial;
+    let mut best_mrr = mrr_before;
+
+    if samples.len() >= MIN_FITTING_SAMPLES {
+        let setters: [fn(&mut RankerWeights, f64); 5] = [
+            |weights, value| weights.history = value,
+            |weights, value| weights.recent_habit = value,
+            |weights, value| weights.temporal = value,
+            |weights, value| weights.query_affinity = value,
+            |weights, value| weights.time_of_day = value,
+        ];
+        for _ in 0..MAX_ROUNDS {
+            let mut improved = false;
+            for set in setters {
+                for value in WEIGHT_GRID {
+                    let mut candidate = best;
+                    set(&mut candidate, value);
+                    let mrr = replay_mrr(samples, &candidate);
+                    if mrr > best_mrr + MIN_IMPROVEMENT {
+                        best = candidate;
+                        best_mrr = mrr;
+                        improved = true;
+                    }
+                }
+            }
+            if !improved {
+                break;
+            }
+        }
+    }
+
+    WeightFittingReport {
+        sample_count: samples.len(),
+        before: initial,
+        after: best,
+        mrr_before,
+        mrr_after: best_mrr,
+        applied: best != initial,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components(history: f64, query_affinity: f64) -> RankerScoreComponents {
+        RankerScoreComponents {
+            history,
+            recent_habit: 0.0,
+            temporal: 0.0,
+            query_affinity,
+            time_of_day: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_fit_weights() {
+        // 用户启动的程序基础分数更低，但查询亲和度更高；另一个程序历史启动次数更多
+        let sample = FittingSample {
+            weight: 1.0,
+            candidates: vec![
+                (20.0, components(3.0, 0.0)),
+                (15.0, components(0.0, 10.0)),
+                (0.0, components(0.0, 0.0)),
+            ],
+            target: 1,
+        };
+        let initial = RankerWeights {
+            history: 1.2,
+            recent_habit: 2.5,
+            temporal: 0.8,
+            query_affinity: 0.0,
+            time_of_day: 1.0,
+        };
+        assert_eq!(sample.rank(&initial), 2);
+
+        // 样本太少时保持原样
+        let report = fit_weights(std::slice::from_ref(&sample), initial);
+        assert!(!report.applied);
+        assert_eq!(report.after, initial);
+        assert_eq!(report.mrr_after, 0.5);
+
+        let samples = vec![sample; MIN_FITTING_SAMPLES];
+        let report = fit_weights(&samples, initial);
+        assert!(report.applied);
+        assert_eq!(report.sample_count, MIN_FITTING_SAMPLES);
+        assert_eq!(report.mrr_before, 0.5);
+        assert_eq!(report.mrr_after, 1.0);
+        assert!(report.after.query_affinity > 0.0);
+        assert_eq!(report.after.recent_habit, initial.recent_habit);
+    }
+}
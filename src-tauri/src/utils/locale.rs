@@ -1,6 +1,6 @@
 /// 系统区域设置和语言检测工具
 use tracing::{debug, info, warn};
-use windows::Win32::Globalization::GetUserDefaultLocaleName;
+use windows::Win32::Globalization::{GetUserDefaultLocaleName, GetUserDefaultUILanguage};
 
 /// 使用 Windows API GetUserDefaultLocaleName 来获取用户的默认区域设置
 pub fn get_system_locale() -> Option<String> {
@@ -26,6 +26,11 @@ pub fn get_system_locale() -> Option<String> {
     }
 }
 
+/// 使用 Windows API GetUserDefaultUILanguage 来获取用户界面语言的 LANGID，比如简体中文为 0x0804
+pub fn get_user_ui_language_id() -> u16 {
+    unsafe { GetUserDefaultUILanguage() }
+}
+
 pub fn map_locale_to_language(locale: &str) -> String {
     // 转换为小写以便于匹配
     let locale_lower = locale.to_lowercase();
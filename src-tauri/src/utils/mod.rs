@@ -8,7 +8,7 @@ pub mod service_locator;
 pub mod ui_controller;
 pub mod waiting_hashmap;
 pub mod windows;
-use chrono::{Local, NaiveDate};
+use chrono::{Datelike, Local, NaiveDate, Timelike};
 use dashmap::DashMap;
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -25,6 +25,12 @@ pub fn get_current_time() -> i64 {
     now.unix_timestamp()
 }
 
+/// 当前是一周中的第几个小时（本地时间，周一 0 点为 0，共 168 个）
+pub fn get_current_hour_of_week() -> usize {
+    let now = Local::now();
+    now.weekday().num_days_from_monday() as usize * 24 + now.hour() as usize
+}
+
 /// 比较日期字符串与当前日期的函数
 pub fn is_date_current(date_str: &str) -> bool {
     // 解析输入的日期字符串
@@ -164,9 +164,14 @@ export type ProgramRankerConfig = {
     recent_habit_weight: number
     temporal_weight: number
     query_affinity_weight: number
+    time_of_day_weight: number
     query_affinity_time_decay: number
     temporal_decay: number
+    transition_window: number
     is_enable: boolean
+    max_tracked_programs: number
+    max_launch_age_days: number
+    prune_after_absent_refreshes: number
 }
 
 export type DirectoryConfig = {
@@ -175,16 +180,23 @@ export type DirectoryConfig = {
     pattern: string[]
     pattern_type: string
     excluded_keywords: string[]
+    max_scan_millis?: number
+    max_scan_entries?: number
 }
 
 export type ProgramLoaderConfig = {
     target_paths: DirectoryConfig[]
     program_bias: { [key: string]: [number, string] }
     is_scan_uwp_programs: boolean
+    is_scan_desktop_entries: boolean
     index_web_pages: [string, string][]
     custom_command: [string, string][]
     forbidden_paths: string[]
     program_alias: { [key: string]: string[] }
+    hidden_programs: { [key: string]: string }
+    bookmark_paths: string[]
+    disabled_sources: string[]
+    source_options: { [key: string]: unknown }
 }
 
 export type ImageLoaderConfig = {
@@ -192,13 +204,29 @@ export type ImageLoaderConfig = {
     enable_online: boolean,
 }
 
+// 混合搜索：同时使用传统搜索模型与语义搜索
+export type HybridSearchModelConfig = {
+    hybrid: {
+        lexical_model: string
+        fusion: 'rrf' | 'weighted_sum'
+        lexical_weight: number
+        semantic_weight: number
+    }
+}
+
+export type SearchModelConfig = string | HybridSearchModelConfig
+
+// 基础分数的归一化方式
+export type ScoreNormalization = 'none' | 'min_max' | 'z_score'
+
 export type ProgramManagerConfig = {
     ranker: ProgramRankerConfig
     loader: ProgramLoaderConfig
     image_loader: ImageLoaderConfig
     enable_lru_search_cache: boolean
     search_cache_capacity: number
-    search_model: string
+    search_model: SearchModelConfig
+    score_normalization: ScoreNormalization
 }
 
 export type RemoteConfig = {
@@ -209,7 +237,8 @@ export type RemoteConfig = {
         ranker: ProgramRankerConfig
         loader: ProgramLoaderConfig
         image_loader: ImageLoaderConfig
-        search_model: string
+        search_model: SearchModelConfig
+        score_normalization: ScoreNormalization
         enable_lru_search_cache: boolean
         search_cache_capacity: number
     }
@@ -230,7 +259,8 @@ export type PartialRemoteConfig = {
         ranker?: PartialProgramRankerConfig
         loader?: PartialProgramLoaderConfig
         image_loader?: PartialImageLoaderConfig
-        search_model?: Partial<string>
+        search_model?: SearchModelConfig
+        score_normalization?: ScoreNormalization
         enable_lru_search_cache?: boolean
         search_cache_capacity?: number
     }
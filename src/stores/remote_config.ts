@@ -25,6 +25,7 @@ function mergeConfig(config: RemoteConfig , partial: PartialRemoteConfig): Remot
                 loader: pmPartial.loader ? { ...pmConfig.loader, ...pmPartial.loader } : pmConfig.loader,
                 image_loader: pmPartial.image_loader ? { ...pmConfig.image_loader, ...pmPartial.image_loader}:pmConfig.image_loader,
                 search_model: pmPartial.search_model ? pmPartial.search_model : pmConfig.search_model,
+                score_normalization: pmPartial.score_normalization ?? pmConfig.score_normalization,
                 enable_lru_search_cache: pmPartial.enable_lru_search_cache !== undefined ? pmPartial.enable_lru_search_cache : pmConfig.enable_lru_search_cache,
                 search_cache_capacity: pmPartial.search_cache_capacity !== undefined ? pmPartial.search_cache_capacity : pmConfig.search_cache_capacity,
         }
@@ -118,6 +119,7 @@ function mergePartialProgramManagerConfig(
             : undefined;
     // 合并 search_model
     const mergedSearchModel = pm2?.search_model ?? pm1?.search_model;
+    const mergedScoreNormalization = pm2?.score_normalization ?? pm1?.score_normalization;
     const mergedEnableCache = pm2?.enable_lru_search_cache ?? pm1?.enable_lru_search_cache;
     const mergedCacheCapacity = pm2?.search_cache_capacity ?? pm1?.search_cache_capacity;
 
@@ -127,6 +129,7 @@ function mergePartialProgramManagerConfig(
     if (mergedLoader !== undefined) mergedPm.loader = mergedLoader;
     if (mergedImageLoaderConfig !== undefined) mergedPm.image_loader = mergedImageLoaderConfig;
     if (mergedSearchModel !== undefined) mergedPm.search_model = mergedSearchModel;
+    if (mergedScoreNormalization !== undefined) mergedPm.score_normalization = mergedScoreNormalization;
     if (mergedEnableCache !== undefined) mergedPm.enable_lru_search_cache = mergedEnableCache;
     if (mergedCacheCapacity !== undefined) mergedPm.search_cache_capacity = mergedCacheCapacity;
     return Object.keys(mergedPm).length > 0 ? mergedPm : undefined;
@@ -143,24 +146,35 @@ export const useRemoteConfigStore = defineStore('config', {
                     recent_habit_weight: 2.5,
                     temporal_weight: 0.8,
                     query_affinity_weight: 3.5,
+                    time_of_day_weight: 1.0,
                     query_affinity_time_decay: 259200,
                     temporal_decay: 10800,
-                    is_enable: true
+                    transition_window: 900,
+                    is_enable: true,
+                    max_tracked_programs: 3000,
+                    max_launch_age_days: 365,
+                    prune_after_absent_refreshes: 100,
                 } as ProgramRankerConfig,
                 loader: {
                     target_paths: [],
                     program_bias: {},
                     is_scan_uwp_programs: false,
+                    is_scan_desktop_entries: false,
                     index_web_pages: [],
                     custom_command: [],
                     forbidden_paths: [],
                     program_alias: {},
+                    hidden_programs: {},
+                    bookmark_paths: [],
+                    disabled_sources: [],
+                    source_options: {},
                 } as ProgramLoaderConfig,
                 image_loader: {
                     enable_icon_cache: true,
                     enable_online: true,
                 } as ImageLoaderConfig,
                 search_model: 'standard',
+                score_normalization: 'none',
                 enable_lru_search_cache: false,
                 search_cache_capacity: 120,
             } as ProgramManagerConfig